@@ -1,113 +1,167 @@
+// Feature F1: Streaming ASR (Backend Abstraction)
+// ModelManager talks to any engine through AsrBackend:
+//   - whisper: whisper.cpp via whisper-rs (native only)
+//   - onnx:    Moonshine via tract-onnx (pure Rust, runs on wasm/desktop)
+//   - mock:    scriptable output for tests and the web demo
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod whisper;
+pub mod onnx;
+pub mod mock;
+
 #[cfg(not(target_arch = "wasm32"))]
-mod real {
-    // Feature F1: Streaming ASR
-    // Uses Whisper-Tiny (GGML) via whisper-rs bindings
-    use whisper_rs::{WhisperContext, WhisperContextParameters, FullParams, SamplingStrategy};
-    use std::path::Path;
-
-    pub struct WhisperModel {
-        ctx: Option<WhisperContext>,
-        // We create state on the fly or cache it. For simplicity in this architectural phase, we wrap context.
-        // Ideally, we'd cache WhisperState for performance, but it requires self-referential structs or unsafe/Arc.
-        // V5.4: We re-create state per transcribe to be safe/simple.
+pub use whisper::WhisperModel;
+pub use onnx::OnnxAsr;
+pub use mock::MockAsr;
+
+// One decoded segment plus the decoder statistics used by the hallucination filter.
+#[derive(Debug, Clone, Default)]
+pub struct AsrSegment {
+    pub text: String,
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub avg_token_prob: f32,
+    pub min_token_prob: f32,
+    // whisper-rs 0.11 does not expose whisper_full_get_segment_no_speech_prob,
+    // so backends without it estimate from signal energy (None = unknown).
+    pub no_speech_prob: Option<f32>,
+    // F17.2: Set when the text was decoded with this language forced (None = auto-detected)
+    pub language: Option<String>,
+}
+
+// Per-call decoding switches
+#[derive(Debug, Clone, Default)]
+pub struct DecodeOptions {
+    pub language: Option<String>, // None = model default ("en" for .en models, else auto-detect)
+    pub translate: bool,          // Emit English text instead of the spoken language
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AsrCapabilities {
+    pub name: String,
+    pub languages: Vec<String>,    // Empty = multilingual / auto-detect
+    pub token_probabilities: bool, // Real decoder confidences (not a constant)
+    pub timestamps: bool,          // Per-segment timing inside a chunk
+    pub translation: bool,         // Can translate speech into English
+}
+
+pub trait AsrBackend: Send {
+    fn load(&mut self, models_dir: &str) -> anyhow::Result<()>;
+
+    fn is_loaded(&self) -> bool;
+
+    fn segments(&self, audio_chunk: &[f32], options: &DecodeOptions) -> Vec<AsrSegment>;
+
+    fn capabilities(&self) -> AsrCapabilities;
+
+    fn transcribe(&self, audio_chunk: &[f32]) -> String {
+        let segments = self.segments(audio_chunk, &DecodeOptions::default());
+        let texts: Vec<&str> = segments.iter().map(|s| s.text.as_str()).collect();
+        texts.join(" ").trim().to_string()
     }
+}
 
-    impl WhisperModel {
-        pub fn load(models_dir: &str) -> Self {
-            println!("Loading Whisper-Tiny from {}/ggml-tiny.en.bin...", models_dir);
-            
-            let path = Path::new(models_dir).join("ggml-tiny.en.bin");
-            let path_str = path.to_str().unwrap_or("");
-            
-            if path.exists() {
-                 println!("Found model at: {:?}", path);
-                 let ctx_params = WhisperContextParameters::default();
-                 match WhisperContext::new_with_params(path_str, ctx_params) {
-                     Ok(ctx) => {
-                         println!("Whisper Engine Loaded Successfully.");
-                         return Self { ctx: Some(ctx) };
-                     },
-                     Err(e) => {
-                         println!("Failed to load Whisper context: {:?}", e);
-                     }
-                 }
-            }
-            
-            println!("ERROR: GGML Model not found at {:?}", path);
-            Self { ctx: None }
-        }
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AsrBackendKind {
+    Whisper,
+    Onnx,
+    Mock,
+}
 
-        pub fn transcribe(&self, audio_chunk: &[f32]) -> String {
-            if let Some(ctx) = &self.ctx {
-                // 1. Create State
-                let mut state = match ctx.create_state() {
-                    Ok(s) => s,
-                    Err(e) => {
-                        println!("Failed to create Whisper state: {:?}", e);
-                        return String::new();
-                    }
-                };
-                
-                // 2. Configure Params
-                let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
-                params.set_n_threads(2); // Keep low for Endurance
-                params.set_language(Some("en"));
-                params.set_print_special(false);
-                params.set_print_progress(false);
-                params.set_print_realtime(false);
-                params.set_print_timestamps(false);
-
-                // 3. Run Inference
-                if let Err(e) = state.full(params, audio_chunk) {
-                    println!("Whisper Inference Failed: {:?}", e);
-                    return String::new();
-                }
-
-                // 4. Collect Text
-                let num_segments = state.full_n_segments().unwrap_or(0);
-                let mut full_text = String::new();
-                
-                for i in 0..num_segments {
-                    if let Ok(segment) = state.full_get_segment_text(i) {
-                        full_text.push_str(&segment);
-                        full_text.push(' ');
-                    }
-                }
-                
-                return full_text.trim().to_string();
-            }
-            
-            String::new()
+impl AsrBackendKind {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "whisper" | "whisper.cpp" | "ggml" => Some(Self::Whisper),
+            "onnx" | "moonshine" | "tract" => Some(Self::Onnx),
+            "mock" => Some(Self::Mock),
+            _ => None,
         }
     }
 
-    impl Drop for WhisperModel {
-        fn drop(&mut self) {
-            println!("Unloading Whisper Engine...");
-            // whisper-rs handles drop/free via FFI
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Whisper => "whisper",
+            Self::Onnx => "onnx",
+            Self::Mock => "mock",
         }
     }
+
+    // whisper.cpp needs a C toolchain and threads, so the web build runs ONNX
+    pub fn platform_default() -> Self {
+        if cfg!(target_arch = "wasm32") { Self::Onnx } else { Self::Whisper }
+    }
 }
 
-#[cfg(not(target_arch = "wasm32"))]
-pub use real::WhisperModel;
+// F1.5: whisper.cpp with a specific GGML file; None where whisper.cpp is unavailable
+pub fn create_whisper_backend(model_file: &str) -> Option<Box<dyn AsrBackend>> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        Some(Box::new(WhisperModel::with_model_file(model_file)))
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        let _ = model_file;
+        None
+    }
+}
 
-#[cfg(target_arch = "wasm32")]
-mod mock {
-    pub struct WhisperModel;
+// F17: Backend bound to a file/directory from the model registry
+pub fn create_backend_for_file(kind: AsrBackendKind, file: &str) -> Box<dyn AsrBackend> {
+    match kind {
+        #[cfg(not(target_arch = "wasm32"))]
+        AsrBackendKind::Whisper => Box::new(WhisperModel::with_model_file(file)),
+        AsrBackendKind::Onnx => Box::new(OnnxAsr::with_model_dir(file)),
+        _ => create_backend(kind),
+    }
+}
 
-    impl WhisperModel {
-        pub fn load(_models_dir: &str) -> Self {
-            println!("WASM: Mock WhisperModel loaded.");
-            Self
-        }
+// F1.2: Built-in model of `kind` that can translate, if there is one (a file in models_dir)
+pub fn multilingual_file(kind: AsrBackendKind) -> Option<&'static str> {
+    match kind {
+        #[cfg(not(target_arch = "wasm32"))]
+        AsrBackendKind::Whisper => Some(whisper::MULTILINGUAL_MODEL_FILE),
+        _ => None,
+    }
+}
+
+// whisper.cpp names its English-only models "*.en.bin"
+pub fn is_multilingual_file(file: &str) -> bool {
+    !file.contains(".en.")
+}
 
-        pub fn transcribe(&self, _audio_chunk: &[f32]) -> String {
-            // Return some mock text for verification on web
-            "Simulated Transcription (WASM)".to_string()
+pub fn create_backend(kind: AsrBackendKind) -> Box<dyn AsrBackend> {
+    match kind {
+        #[cfg(not(target_arch = "wasm32"))]
+        AsrBackendKind::Whisper => Box::new(WhisperModel::new()),
+        #[cfg(target_arch = "wasm32")]
+        AsrBackendKind::Whisper => {
+            println!("ASR: whisper.cpp is unavailable on wasm32, using ONNX backend.");
+            Box::new(OnnxAsr::new())
         }
+        AsrBackendKind::Onnx => Box::new(OnnxAsr::new()),
+        AsrBackendKind::Mock => Box::new(MockAsr::new()),
     }
 }
 
-#[cfg(target_arch = "wasm32")]
-pub use mock::WhisperModel;
+fn mean(values: &[f32]) -> f32 {
+    if values.is_empty() { return 0.0; }
+    values.iter().sum::<f32>() / values.len() as f32
+}
+
+// Fraction of 20ms frames in [start_ms, end_ms) below the noise-gate floor (-45dB).
+// The capture NoiseGate zeroes silence, so a gated span is a strong no-speech signal.
+fn estimate_no_speech(audio: &[f32], start_ms: i64, end_ms: i64) -> f32 {
+    let start = ((start_ms.max(0) as usize) * 16).min(audio.len());
+    let end = ((end_ms.max(0) as usize) * 16).min(audio.len());
+    if end <= start { return 0.0; }
+
+    let floor = 10.0f32.powf(-45.0 / 20.0);
+    let frames: Vec<&[f32]> = audio[start..end].chunks(320).collect();
+    let silent = frames.iter()
+        .filter(|frame| {
+            let rms = (frame.iter().map(|x| x * x).sum::<f32>() / frame.len() as f32).sqrt();
+            rms < floor
+        })
+        .count();
+    silent as f32 / frames.len() as f32
+}
@@ -0,0 +1,125 @@
+// Scriptable ASR for tests and the web demo.
+// Each call to `segments` pops the next scripted response; once the script is exhausted
+// the mock returns its fallback text (or nothing). Translate passes pop from the same script,
+// so a test scripts [original, translation, original, translation, ...].
+
+use super::{AsrBackend, AsrCapabilities, AsrSegment, DecodeOptions};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+pub struct MockAsr {
+    script: Mutex<VecDeque<Vec<AsrSegment>>>,
+    fallback: Option<String>,
+    loaded: bool,
+    calls: AtomicUsize,
+}
+
+impl MockAsr {
+    pub fn new() -> Self {
+        Self {
+            script: Mutex::new(VecDeque::new()),
+            fallback: None,
+            loaded: false,
+            calls: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn with_script(responses: Vec<Vec<AsrSegment>>) -> Self {
+        let mock = Self::new();
+        mock.script.lock().unwrap().extend(responses);
+        mock
+    }
+
+    // Text returned after the script runs out (e.g. "Simulated Transcription (WASM)")
+    pub fn with_fallback(mut self, text: &str) -> Self {
+        self.fallback = Some(text.to_string());
+        self
+    }
+
+    // Queue a single confident segment
+    pub fn push_text(&self, text: &str) {
+        self.push_segments(vec![AsrSegment {
+            text: text.to_string(),
+            start_ms: 0,
+            end_ms: 3000,
+            avg_token_prob: 1.0,
+            min_token_prob: 1.0,
+            no_speech_prob: None,
+            language: None,
+        }]);
+    }
+
+    pub fn push_segments(&self, segments: Vec<AsrSegment>) {
+        if let Ok(mut script) = self.script.lock() {
+            script.push_back(segments);
+        }
+    }
+
+    pub fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+}
+
+impl Default for MockAsr {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AsrBackend for MockAsr {
+    fn load(&mut self, _models_dir: &str) -> anyhow::Result<()> {
+        println!("Mock ASR loaded.");
+        self.loaded = true;
+        Ok(())
+    }
+
+    fn is_loaded(&self) -> bool {
+        self.loaded
+    }
+
+    fn segments(&self, audio_chunk: &[f32], _options: &DecodeOptions) -> Vec<AsrSegment> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        if let Some(next) = self.script.lock().ok().and_then(|mut script| script.pop_front()) {
+            return next;
+        }
+        match &self.fallback {
+            Some(text) => vec![AsrSegment {
+                text: text.clone(),
+                start_ms: 0,
+                end_ms: (audio_chunk.len() as i64 * 1000) / 16000,
+                avg_token_prob: 1.0,
+                min_token_prob: 1.0,
+                no_speech_prob: None,
+                language: None,
+            }],
+            None => Vec::new(),
+        }
+    }
+
+    fn capabilities(&self) -> AsrCapabilities {
+        AsrCapabilities {
+            name: "mock".to_string(),
+            languages: Vec::new(),
+            token_probabilities: false,
+            timestamps: false,
+            translation: true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_script_then_fallback() {
+        let mock = MockAsr::with_script(vec![Vec::new()]).with_fallback("fallback");
+        mock.push_text("Second response");
+
+        assert!(mock.segments(&[0.0; 16000], &DecodeOptions::default()).is_empty());
+        assert_eq!(mock.transcribe(&[0.0; 16000]), "Second response");
+        assert_eq!(mock.transcribe(&[0.0; 16000]), "fallback");
+        assert_eq!(mock.calls(), 3);
+    }
+}
@@ -0,0 +1,203 @@
+// Feature F1 (Web/Desktop): Moonshine ASR via tract-onnx
+// Pure Rust inference, so it also runs in the wasm32 build where whisper.cpp cannot.
+// Expected layout (sherpa-onnx Moonshine export):
+//   {models_dir}/moonshine-tiny/preprocess.onnx       audio [1, N] f32 -> features
+//   {models_dir}/moonshine-tiny/encode.onnx           features, len i32 -> encoder states
+//   {models_dir}/moonshine-tiny/uncached_decode.onnx  tokens [1, L] i32, states, len i32 -> logits
+//   {models_dir}/moonshine-tiny/tokens.txt            "<piece> <id>" per line
+
+use crate::ai::mmap::MappedModel;
+use super::{AsrBackend, AsrCapabilities, AsrSegment, DecodeOptions, mean, estimate_no_speech};
+use std::collections::HashMap;
+use std::path::Path;
+use tract_onnx::prelude::*;
+
+type OnnxPlan = TypedRunnableModel<TypedModel>;
+
+const MODEL_DIR: &str = "moonshine-tiny";
+const BOS_TOKEN: i32 = 1;
+const EOS_TOKEN: i32 = 2;
+// Moonshine emits well under 6 tokens per second of speech
+const MAX_TOKENS_PER_SECOND: usize = 6;
+
+struct MoonshineGraphs {
+    preprocess: OnnxPlan,
+    encode: OnnxPlan,
+    decode: OnnxPlan,
+    vocab: HashMap<i32, String>,
+}
+
+pub struct OnnxAsr {
+    graphs: Option<MoonshineGraphs>,
+    model_dir: String, // Directory inside models_dir
+}
+
+impl OnnxAsr {
+    pub fn new() -> Self {
+        Self::with_model_dir(MODEL_DIR)
+    }
+
+    // F17: Directory named by a registry entry
+    pub fn with_model_dir(model_dir: &str) -> Self {
+        Self { graphs: None, model_dir: model_dir.to_string() }
+    }
+}
+
+impl Default for OnnxAsr {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn load_plan(path: &Path) -> anyhow::Result<OnnxPlan> {
+    if !path.exists() {
+        return Err(anyhow::anyhow!("ONNX graph not found at {:?}", path));
+    }
+    // F8.2: Parsed from the mapping; the graph keeps its own tensors, the file stays in page cache
+    let weights = MappedModel::open(path)?;
+    let plan = tract_onnx::onnx()
+        .model_for_read(&mut weights.reader())?
+        .into_optimized()?
+        .into_runnable()?;
+    Ok(plan)
+}
+
+fn load_vocab(path: &Path) -> anyhow::Result<HashMap<i32, String>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Tokenizer not found at {:?}: {}", path, e))?;
+    let mut vocab = HashMap::new();
+    for line in content.lines() {
+        // Pieces may themselves contain spaces, so split from the right
+        if let Some((piece, id)) = line.rsplit_once(' ') {
+            if let Ok(id) = id.trim().parse::<i32>() {
+                vocab.insert(id, piece.to_string());
+            }
+        }
+    }
+    Ok(vocab)
+}
+
+fn softmax_max(logits: &[f32]) -> (i32, f32) {
+    let (best, max) = logits.iter().enumerate()
+        .fold((0, f32::NEG_INFINITY), |acc, (i, &v)| if v > acc.1 { (i, v) } else { acc });
+    let sum: f32 = logits.iter().map(|v| (v - max).exp()).sum();
+    (best as i32, if sum > 0.0 { 1.0 / sum } else { 0.0 })
+}
+
+impl MoonshineGraphs {
+    fn decode_text(&self, tokens: &[i32]) -> String {
+        let mut text = String::new();
+        for id in tokens {
+            if let Some(piece) = self.vocab.get(id) {
+                // Byte-fallback pieces like <0x0A> carry no printable text here
+                if piece.starts_with("<0x") { continue; }
+                text.push_str(&piece.replace('\u{2581}', " "));
+            }
+        }
+        text.trim().to_string()
+    }
+
+    fn run(&self, audio: &[f32]) -> anyhow::Result<(Vec<i32>, Vec<f32>)> {
+        // 1. Audio -> features
+        let audio_tensor = Tensor::from_shape(&[1, audio.len()], audio)?;
+        let features = self.preprocess.run(tvec!(audio_tensor.into()))?;
+        let feature_len = features[0].shape().get(1).copied().unwrap_or(0) as i32;
+
+        // 2. Encoder (once per chunk)
+        let len_tensor = Tensor::from_shape(&[1], &[feature_len])?;
+        let encoded = self.encode.run(tvec!(features[0].clone(), len_tensor.into()))?;
+        let encoded_len = encoded[0].shape().get(1).copied().unwrap_or(0) as i32;
+
+        // 3. Greedy decode. The uncached graph recomputes the prefix each step, which is
+        // fine for 3-second chunks and avoids threading KV-cache tensors through tract.
+        let seconds = audio.len() / 16000 + 1;
+        let max_tokens = seconds * MAX_TOKENS_PER_SECOND;
+        let mut tokens = vec![BOS_TOKEN];
+        let mut probs = Vec::new();
+
+        for _ in 0..max_tokens {
+            let token_tensor = Tensor::from_shape(&[1, tokens.len()], &tokens)?;
+            let len_tensor = Tensor::from_shape(&[1], &[encoded_len])?;
+            let outputs = self.decode.run(tvec!(token_tensor.into(), encoded[0].clone(), len_tensor.into()))?;
+
+            let logits = outputs[0].to_array_view::<f32>()?;
+            let vocab_size = *logits.shape().last().unwrap_or(&0);
+            let flat: Vec<f32> = logits.iter().cloned().collect();
+            if vocab_size == 0 || flat.len() < vocab_size { break; }
+            let last = &flat[flat.len() - vocab_size..];
+
+            let (next, prob) = softmax_max(last);
+            if next == EOS_TOKEN { break; }
+            tokens.push(next);
+            probs.push(prob);
+        }
+
+        Ok((tokens[1..].to_vec(), probs))
+    }
+}
+
+impl AsrBackend for OnnxAsr {
+    fn load(&mut self, models_dir: &str) -> anyhow::Result<()> {
+        let dir = Path::new(models_dir).join(&self.model_dir);
+        println!("Loading Moonshine (ONNX) from {:?}...", dir);
+
+        self.graphs = Some(MoonshineGraphs {
+            preprocess: load_plan(&dir.join("preprocess.onnx"))?,
+            encode: load_plan(&dir.join("encode.onnx"))?,
+            decode: load_plan(&dir.join("uncached_decode.onnx"))?,
+            vocab: load_vocab(&dir.join("tokens.txt"))?,
+        });
+        println!("Moonshine Engine Loaded Successfully.");
+        Ok(())
+    }
+
+    fn is_loaded(&self) -> bool {
+        self.graphs.is_some()
+    }
+
+    fn segments(&self, audio_chunk: &[f32], options: &DecodeOptions) -> Vec<AsrSegment> {
+        let Some(graphs) = &self.graphs else { return Vec::new() };
+        // English-only model: nothing to translate into English
+        if audio_chunk.is_empty() || options.translate { return Vec::new(); }
+
+        match graphs.run(audio_chunk) {
+            Ok((tokens, probs)) => {
+                let text = graphs.decode_text(&tokens);
+                if text.is_empty() { return Vec::new(); }
+                // Moonshine has no timestamp tokens: one segment spans the whole chunk
+                let end_ms = (audio_chunk.len() as i64 * 1000) / 16000;
+                vec![AsrSegment {
+                    text,
+                    start_ms: 0,
+                    end_ms,
+                    avg_token_prob: mean(&probs),
+                    min_token_prob: probs.iter().cloned().reduce(f32::min).unwrap_or(0.0),
+                    no_speech_prob: Some(estimate_no_speech(audio_chunk, 0, end_ms)),
+                    language: None,
+                }]
+            }
+            Err(e) => {
+                println!("Moonshine Inference Failed: {:?}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    fn capabilities(&self) -> AsrCapabilities {
+        AsrCapabilities {
+            name: "moonshine-onnx".to_string(),
+            languages: vec!["en".to_string()],
+            token_probabilities: true,
+            timestamps: false,
+            translation: false,
+        }
+    }
+}
+
+impl Drop for OnnxAsr {
+    fn drop(&mut self) {
+        if self.graphs.is_some() {
+            println!("Unloading Moonshine Engine...");
+        }
+    }
+}
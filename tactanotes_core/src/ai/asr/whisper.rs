@@ -0,0 +1,170 @@
+// Feature F1: Streaming ASR
+// Uses Whisper-Tiny (GGML) via whisper-rs bindings
+use whisper_rs::{WhisperContext, WhisperContextParameters, FullParams, SamplingStrategy};
+use std::path::Path;
+use crate::ai::mmap::MappedModel;
+use super::{AsrBackend, AsrCapabilities, AsrSegment, DecodeOptions, mean, estimate_no_speech};
+
+const NO_SPEECH_THRESHOLD: f32 = 0.6;
+const MODEL_FILE: &str = "ggml-tiny.en.bin";
+// F1.2: Same size, but knows other languages and the translate task (used in translate mode)
+pub const MULTILINGUAL_MODEL_FILE: &str = "ggml-tiny.bin";
+
+pub struct WhisperModel {
+    ctx: Option<WhisperContext>,
+    model_file: String, // GGML file inside models_dir
+    // We create state on the fly or cache it. For simplicity in this architectural phase, we wrap context.
+    // Ideally, we'd cache WhisperState for performance, but it requires self-referential structs or unsafe/Arc.
+    // V5.4: We re-create state per transcribe to be safe/simple.
+}
+
+impl WhisperModel {
+    pub fn new() -> Self {
+        Self::with_model_file(MODEL_FILE)
+    }
+
+    pub fn multilingual() -> Self {
+        Self::with_model_file(MULTILINGUAL_MODEL_FILE)
+    }
+
+    // F1.5: Larger GGML files (e.g. ggml-base.en.bin) for offline re-transcription
+    pub fn with_model_file(model_file: &str) -> Self {
+        Self { ctx: None, model_file: model_file.to_string() }
+    }
+
+    // Convenience constructor kept for callers that want a ready model in one step
+    pub fn load(models_dir: &str) -> Self {
+        let mut model = Self::new();
+        if let Err(e) = AsrBackend::load(&mut model, models_dir) {
+            println!("ERROR: {}", e);
+        }
+        model
+    }
+
+    pub fn transcribe(&self, audio_chunk: &[f32]) -> String {
+        AsrBackend::transcribe(self, audio_chunk)
+    }
+}
+
+impl Default for WhisperModel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AsrBackend for WhisperModel {
+    fn load(&mut self, models_dir: &str) -> anyhow::Result<()> {
+        println!("Loading Whisper from {}/{}...", models_dir, self.model_file);
+        
+        let path = Path::new(models_dir).join(&self.model_file);
+        if !path.exists() {
+            return Err(anyhow::anyhow!("GGML Model not found at {:?}", path));
+        }
+
+        println!("Found model at: {:?}", path);
+        // F8.2: whisper.cpp copies tensors out of the mapping; the GGML file is never heap-read
+        let weights = MappedModel::open(&path)?;
+        let ctx_params = WhisperContextParameters::default();
+        let ctx = WhisperContext::new_from_buffer_with_params(&weights, ctx_params)
+            .map_err(|e| anyhow::anyhow!("Failed to load Whisper context: {:?}", e))?;
+        println!("Whisper Engine Loaded Successfully.");
+        self.ctx = Some(ctx);
+        Ok(())
+    }
+
+    fn is_loaded(&self) -> bool {
+        self.ctx.is_some()
+    }
+
+    // Feature F1.1: Segment-level output with decoder statistics for the hallucination filter
+    fn segments(&self, audio_chunk: &[f32], options: &DecodeOptions) -> Vec<AsrSegment> {
+        let mut segments = Vec::new();
+
+        if let Some(ctx) = &self.ctx {
+            // 1. Create State
+            let mut state = match ctx.create_state() {
+                Ok(s) => s,
+                Err(e) => {
+                    println!("Failed to create Whisper state: {:?}", e);
+                    return segments;
+                }
+            };
+            
+            // 2. Configure Params
+            // F1.2: .en models only know English; multilingual models detect the language
+            let default_language = if ctx.is_multilingual() { "auto" } else { "en" };
+            let language = options.language.as_deref().unwrap_or(default_language);
+            let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+            params.set_n_threads(2); // Keep low for Endurance
+            params.set_language(Some(language));
+            params.set_translate(options.translate && ctx.is_multilingual());
+            params.set_print_special(false);
+            params.set_print_progress(false);
+            params.set_print_realtime(false);
+            params.set_print_timestamps(false);
+            params.set_no_speech_thold(NO_SPEECH_THRESHOLD);
+
+            // 3. Run Inference
+            if let Err(e) = state.full(params, audio_chunk) {
+                println!("Whisper Inference Failed: {:?}", e);
+                return segments;
+            }
+
+            // 4. Collect Segments + Token Probabilities
+            let num_segments = state.full_n_segments().unwrap_or(0);
+            let eot = ctx.token_eot();
+            
+            for i in 0..num_segments {
+                let text = match state.full_get_segment_text(i) {
+                    Ok(text) => text.trim().to_string(),
+                    Err(_) => continue,
+                };
+
+                // Special tokens ([_BEG_], timestamps) carry no lexical confidence
+                let mut probs = Vec::new();
+                let num_tokens = state.full_n_tokens(i).unwrap_or(0);
+                for j in 0..num_tokens {
+                    let id = state.full_get_token_id(i, j).unwrap_or(eot);
+                    if id >= eot { continue; }
+                    if let Ok(p) = state.full_get_token_prob(i, j) {
+                        probs.push(p);
+                    }
+                }
+
+                // Whisper timestamps are in 10ms units
+                let start_ms = state.full_get_segment_t0(i).unwrap_or(0) * 10;
+                let end_ms = state.full_get_segment_t1(i).unwrap_or(0) * 10;
+
+                segments.push(AsrSegment {
+                    text,
+                    start_ms,
+                    end_ms,
+                    avg_token_prob: mean(&probs),
+                    min_token_prob: probs.iter().cloned().reduce(f32::min).unwrap_or(0.0),
+                    no_speech_prob: Some(estimate_no_speech(audio_chunk, start_ms, end_ms)),
+                    language: None,
+                });
+            }
+        }
+        
+        segments
+    }
+
+    fn capabilities(&self) -> AsrCapabilities {
+        let multilingual = self.ctx.as_ref().map(|ctx| ctx.is_multilingual()).unwrap_or(false);
+        AsrCapabilities {
+            name: "whisper.cpp".to_string(),
+            languages: if multilingual { Vec::new() } else { vec!["en".to_string()] },
+            token_probabilities: true,
+            timestamps: true,
+            translation: multilingual,
+        }
+    }
+}
+
+impl Drop for WhisperModel {
+    fn drop(&mut self) {
+        println!("Unloading Whisper Engine...");
+        // whisper-rs handles drop/free via FFI
+    }
+}
@@ -0,0 +1,200 @@
+// Feature F2.5: Prompt budget
+// The generative model sees one context window (4096 tokens for Qwen2.5-0.5B as loaded) shared by
+// the instructions, the transcript, the RAG context from past notes, the unsure-segment hints and
+// the answer. Tokens are counted with the model's own tokenizer. The answer's reservation comes
+// first; the instructions are never cut. The rest is filled by priority:
+//   1. RAG context: at most CONTEXT_SHARE of the prompt, whole excerpts dropped from the end
+//   2. Unsure-segment hints: dropped from the end
+//   3. Transcript: condensed (least central sentences left out, textrank.rs), as a last resort cut
+//      at the end; then the remaining RAG context goes too
+// Whatever was left out is listed in the report.
+
+use super::llm::{summary_prompt, MAX_UNSURE_HINTS};
+use super::textrank;
+
+// Past notes may not crowd out more than this share of the prompt
+const CONTEXT_SHARE: f32 = 0.25;
+// Half the window at most goes to the answer, so a long max_tokens cannot starve the prompt
+const MAX_OUTPUT_SHARE: usize = 2;
+// Condense, re-count, condense again: token counts only approximate the word target
+const CONDENSE_ROUNDS: usize = 3;
+const TRUNCATE_ROUNDS: usize = 8;
+
+#[derive(Debug, Clone, Copy)]
+pub struct PromptParts<'a> {
+    pub template: Option<&'a str>, // Prompt template (prompt.rs); None = default
+    pub subject: &'a str,
+    pub language: &'a str,         // Summary language code
+    pub transcript: &'a str,
+    pub context: &'a str,          // "Context from past notes:" block, one excerpt per "- " line
+    pub hints: &'a [String],       // Low-confidence segments (F1.4)
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BudgetReport {
+    pub context_window: usize,
+    pub output_tokens: usize,     // Reserved for the answer
+    pub prompt_tokens: usize,     // Everything sent, instructions included
+    pub transcript_tokens: usize,
+    pub context_tokens: usize,
+    pub dropped: Vec<String>,     // What was left out, e.g. "2 of 3 past-note excerpts"
+}
+
+// Prompt fitting `context_window` with `max_output` tokens reserved, and what was given up for it.
+// `count` is the model's tokenizer.
+pub fn fit(
+    parts: PromptParts,
+    context_window: usize,
+    max_output: usize,
+    count: &mut dyn FnMut(&str) -> anyhow::Result<usize>,
+) -> anyhow::Result<(String, BudgetReport)> {
+    let mut report = BudgetReport { context_window, ..Default::default() };
+    let max_output = max_output.max(1);
+    report.output_tokens = max_output.min(context_window / MAX_OUTPUT_SHARE).max(1);
+    if report.output_tokens < max_output {
+        report.dropped.push(format!("answer limited to {} of {} tokens", report.output_tokens, max_output));
+    }
+    let budget = context_window.saturating_sub(report.output_tokens);
+
+    let mut transcript = parts.transcript.trim().to_string();
+    let mut excerpts: Vec<&str> = parts.context.lines().filter(|l| l.trim_start().starts_with("- ")).collect();
+    let total_excerpts = excerpts.len();
+    let mut hints: Vec<String> = parts.hints.iter().filter(|h| !h.trim().is_empty()).take(MAX_UNSURE_HINTS).cloned().collect();
+    let total_hints = hints.len();
+
+    let header = parts.context.lines().find(|l| !l.trim().is_empty() && !l.trim_start().starts_with("- ")).unwrap_or_default();
+    let render_context = |excerpts: &[&str]| {
+        if excerpts.is_empty() { String::new() } else { format!("{}\n{}\n", header, excerpts.join("\n")) }
+    };
+    let mut build = |transcript: &str, excerpts: &[&str], hints: &[String]| -> anyhow::Result<(String, usize)> {
+        let context = render_context(excerpts);
+        let prompt = summary_prompt(PromptParts { transcript, context: &context, hints, ..parts });
+        let tokens = count(&prompt)?;
+        Ok((prompt, tokens))
+    };
+
+    let instructions = build("", &[], &[])?.1;
+    if instructions > budget {
+        return Err(anyhow::anyhow!("Instructions take {} tokens, only {} fit with {} reserved for the answer", instructions, budget, report.output_tokens));
+    }
+
+    // 1. RAG context within its share
+    let context_cap = (budget as f32 * CONTEXT_SHARE) as usize;
+    while !excerpts.is_empty() && build("", &excerpts, &[])?.1.saturating_sub(instructions) > context_cap {
+        excerpts.pop();
+    }
+    let (mut prompt, mut tokens) = build(&transcript, &excerpts, &hints)?;
+
+    // 2. Hints
+    while tokens > budget && !hints.is_empty() {
+        hints.pop();
+        (prompt, tokens) = build(&transcript, &excerpts, &hints)?;
+    }
+
+    // 3. Transcript: condensed, then cut
+    let original_chars = transcript.len();
+    let mut condensed = false;
+    for _ in 0..CONDENSE_ROUNDS {
+        if tokens <= budget { break; }
+        let words = transcript.split_whitespace().count();
+        let target = shrink(words, budget, tokens);
+        let shorter = textrank::condense(&transcript, parts.hints, target).join(" ");
+        if shorter.is_empty() || shorter.len() >= transcript.len() { break; }
+        transcript = shorter;
+        condensed = true;
+        (prompt, tokens) = build(&transcript, &excerpts, &hints)?;
+    }
+    let mut truncated = false;
+    for _ in 0..TRUNCATE_ROUNDS {
+        if tokens <= budget || transcript.is_empty() { break; }
+        // Keep the start: lectures state their topic up front
+        let mut cut = shrink(transcript.len(), budget, tokens);
+        while !transcript.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        transcript.truncate(cut);
+        truncated = true;
+        (prompt, tokens) = build(&transcript, &excerpts, &hints)?;
+    }
+    while tokens > budget && !excerpts.is_empty() {
+        excerpts.pop();
+        (prompt, tokens) = build(&transcript, &excerpts, &hints)?;
+    }
+    if tokens > budget {
+        return Err(anyhow::anyhow!("Prompt does not fit {} tokens", budget));
+    }
+
+    if excerpts.len() < total_excerpts {
+        report.dropped.push(format!("{} of {} past-note excerpts", total_excerpts - excerpts.len(), total_excerpts));
+    }
+    if hints.len() < total_hints {
+        report.dropped.push(format!("{} of {} unsure-segment hints", total_hints - hints.len(), total_hints));
+    }
+    if condensed || truncated {
+        let how = match (condensed, truncated) {
+            (true, true) => "condensed and cut",
+            (true, false) => "condensed",
+            _ => "cut",
+        };
+        report.dropped.push(format!("transcript {} from {} to {} characters", how, original_chars, transcript.len()));
+    }
+    report.prompt_tokens = tokens;
+    report.transcript_tokens = tokens.saturating_sub(build("", &excerpts, &hints)?.1);
+    report.context_tokens = build("", &excerpts, &[])?.1.saturating_sub(instructions);
+    Ok((prompt, report))
+}
+
+// `size` scaled by how far over budget the prompt is, with some slack
+fn shrink(size: usize, budget: usize, tokens: usize) -> usize {
+    size * budget / tokens.max(1) * 9 / 10
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // One token per word is close enough for the test
+    fn words(text: &str) -> anyhow::Result<usize> {
+        Ok(text.split_whitespace().count())
+    }
+
+    #[test]
+    fn test_small_prompts_are_left_alone() {
+        let hints = vec!["maybe entropy".to_string()];
+        let context = "Context from past notes:\n- [Thermo 1] (Similarity: 0.81): Heat flows from hot to cold.\n";
+        let parts = PromptParts { template: None, subject: "", language: "", transcript: "Entropy rises in closed systems.", context, hints: &hints };
+        let (prompt, report) = fit(parts, 4096, 320, &mut words).unwrap();
+        assert_eq!(prompt, summary_prompt(parts));
+        assert!(report.dropped.is_empty());
+        assert_eq!((report.context_window, report.output_tokens), (4096, 320));
+        assert!(report.transcript_tokens >= 5 && report.context_tokens >= 10);
+    }
+
+    #[test]
+    fn test_parts_are_dropped_by_priority() {
+        let transcript: String = (0..60)
+            .map(|i| format!("Sentence {} explains another detail of heat engines and their efficiency limits.", i))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let context: String = (0..8)
+            .map(|i| format!("- [Note {}] (Similarity: 0.5): Earlier lecture about engines and reservoirs number {}.\n", i, i))
+            .collect();
+        let context = format!("Context from past notes:\n{}", context);
+        let hints: Vec<String> = (0..8).map(|i| format!("unclear phrase number {}", i)).collect();
+        let parts = PromptParts { template: None, subject: "", language: "", transcript: &transcript, context: &context, hints: &hints };
+
+        let (prompt, report) = fit(parts, 600, 400, &mut words).unwrap();
+        assert!(words(&prompt).unwrap() <= 600 - report.output_tokens);
+        assert_eq!(report.output_tokens, 300);
+        assert_eq!(report.dropped[0], "answer limited to 300 of 400 tokens");
+        // Context over its share, every hint, and the transcript condensed
+        assert!(report.dropped.iter().any(|d| d.ends_with("of 8 past-note excerpts")));
+        assert!(report.dropped.contains(&"8 of 8 unsure-segment hints".to_string()));
+        assert!(report.dropped.iter().any(|d| d.starts_with("transcript condensed")));
+        assert!(prompt.contains("Sentence 0 ") || prompt.contains("Sentence 1 "));
+        assert!(report.context_tokens <= 75);
+
+        // Instructions alone do not fit
+        assert!(fit(parts, 40, 10, &mut words).is_err());
+    }
+}
@@ -0,0 +1,323 @@
+// Feature F1.1: Hallucination & Repetition-Loop Guard
+// Whisper-tiny invents text on silence/noise ("Thanks for watching!") and can get stuck
+// repeating the same phrase. Every ASR segment passes through here before RollingBuffer.
+
+use super::asr::AsrSegment;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::collections::HashMap;
+use std::io::Write;
+
+// Longest *action* annotation; a longer span between two stars is speech
+const MAX_ACTION_CHARS: usize = 24;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilterReason {
+    Blank,             // Only annotations like [BLANK_AUDIO] / (music)
+    PhantomPhrase,     // Matches the blocklist
+    LowConfidence,     // Average token probability below threshold
+    HighCompression,   // gzip ratio > threshold => repetitive text
+    NoSpeech,          // Decoder (or energy estimate) says nobody was talking
+    RepetitionLoop,    // Same n-gram repeated inside the segment
+    Duplicate,         // Segment already at the end of the transcript
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilterVerdict {
+    Keep,
+    Flag(FilterReason), // Keep the text, but mark it as suspicious
+    Drop(FilterReason),
+}
+
+#[derive(Debug, Clone)]
+pub struct FilterConfig {
+    pub drop_avg_token_prob: f32,
+    pub flag_avg_token_prob: f32,
+    pub max_compression_ratio: f32, // Whisper reference decoder uses 2.4
+    pub max_no_speech_prob: f32,
+    pub ngram_size: usize,
+    pub max_ngram_repeats: usize,
+    pub blocklist: Vec<String>,
+}
+
+impl Default for FilterConfig {
+    fn default() -> Self {
+        Self {
+            drop_avg_token_prob: 0.30,
+            flag_avg_token_prob: 0.50,
+            max_compression_ratio: 2.4,
+            max_no_speech_prob: 0.6,
+            ngram_size: 3,
+            max_ngram_repeats: 3,
+            blocklist: DEFAULT_PHANTOM_PHRASES.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+}
+
+// Known Whisper phantoms, mostly learned from YouTube subtitle credits.
+const DEFAULT_PHANTOM_PHRASES: &[&str] = &[
+    "thanks for watching",
+    "thank you for watching",
+    "thank you so much for watching",
+    "please subscribe",
+    "please like and subscribe",
+    "subscribe to my channel",
+    "see you in the next video",
+    "subtitles by the amara org community",
+    "transcribed by esoteric",
+    "thank you",
+    "you",
+];
+
+pub struct FilteredSegment {
+    pub segment: AsrSegment, // Text is cleaned (annotations stripped)
+    pub verdict: FilterVerdict,
+}
+
+#[derive(Clone)]
+pub struct HallucinationFilter {
+    config: FilterConfig,
+    blocklist: Vec<String>, // Normalized copy of config.blocklist
+}
+
+impl HallucinationFilter {
+    pub fn new(config: FilterConfig) -> Self {
+        let blocklist = config.blocklist.iter().map(|p| normalize(p)).filter(|p| !p.is_empty()).collect();
+        Self { config, blocklist }
+    }
+
+    pub fn config(&self) -> &FilterConfig {
+        &self.config
+    }
+
+    pub fn set_blocklist(&mut self, phrases: Vec<String>) {
+        self.blocklist = phrases.iter().map(|p| normalize(p)).filter(|p| !p.is_empty()).collect();
+        self.config.blocklist = phrases;
+    }
+
+    // `recent_context` is the tail of the transcript so far (RollingBuffer contents).
+    pub fn apply(&self, mut segment: AsrSegment, recent_context: &str) -> FilteredSegment {
+        segment.text = strip_annotations(&segment.text);
+        let verdict = self.judge(&segment, recent_context);
+        FilteredSegment { segment, verdict }
+    }
+
+    fn judge(&self, segment: &AsrSegment, recent_context: &str) -> FilterVerdict {
+        let normalized = normalize(&segment.text);
+        if normalized.is_empty() {
+            return FilterVerdict::Drop(FilterReason::Blank);
+        }
+
+        if self.is_phantom(&normalized) {
+            return FilterVerdict::Drop(FilterReason::PhantomPhrase);
+        }
+
+        if let Some(p) = segment.no_speech_prob {
+            // Whisper's own rule: silence AND an unsure decoder. Confident text over "silence"
+            // is usually a quiet speaker behind the noise gate.
+            if p > self.config.max_no_speech_prob && segment.avg_token_prob < self.config.flag_avg_token_prob {
+                return FilterVerdict::Drop(FilterReason::NoSpeech);
+            }
+        }
+
+        if self.has_repetition_loop(&normalized) {
+            return FilterVerdict::Drop(FilterReason::RepetitionLoop);
+        }
+
+        if compression_ratio(&segment.text) > self.config.max_compression_ratio {
+            return FilterVerdict::Drop(FilterReason::HighCompression);
+        }
+
+        let context = normalize(recent_context);
+        if context == normalized || context.ends_with(&format!(" {}", normalized)) {
+            return FilterVerdict::Drop(FilterReason::Duplicate);
+        }
+
+        if segment.avg_token_prob < self.config.drop_avg_token_prob {
+            return FilterVerdict::Drop(FilterReason::LowConfidence);
+        }
+        if segment.avg_token_prob < self.config.flag_avg_token_prob {
+            return FilterVerdict::Flag(FilterReason::LowConfidence);
+        }
+
+        FilterVerdict::Keep
+    }
+
+    fn is_phantom(&self, normalized: &str) -> bool {
+        self.blocklist.iter().any(|phrase| {
+            if normalized == phrase {
+                return true;
+            }
+            // Multi-word phantoms also count when they make up most of the segment,
+            // e.g. "Thanks for watching, bye!"
+            phrase.contains(' ')
+                && normalized.contains(phrase.as_str())
+                && phrase.len() * 10 >= normalized.len() * 6
+        })
+    }
+
+    fn has_repetition_loop(&self, normalized: &str) -> bool {
+        let words: Vec<&str> = normalized.split_whitespace().collect();
+
+        // "the the the the ..." style stutter
+        let mut run = 1;
+        for pair in words.windows(2) {
+            if pair[0] == pair[1] {
+                run += 1;
+                if run > self.config.max_ngram_repeats + 1 { return true; }
+            } else {
+                run = 1;
+            }
+        }
+
+        let n = self.config.ngram_size;
+        if n == 0 || words.len() < n * (self.config.max_ngram_repeats + 1) {
+            return false;
+        }
+
+        let mut counts: HashMap<&[&str], usize> = HashMap::new();
+        for gram in words.windows(n) {
+            let count = counts.entry(gram).or_insert(0);
+            *count += 1;
+            if *count > self.config.max_ngram_repeats {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+// Removes [BLANK_AUDIO], [MUSIC], (applause), *laughs* and similar non-speech annotations.
+// An opener without its closer is speech ("f(x equals", "3 * 4 is 12") and stays.
+pub fn strip_annotations(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(at) = rest.find(['[', '(', '*']) {
+        out.push_str(&rest[..at]);
+        let after = &rest[at + 1..];
+        let end = match &rest[at..at + 1] {
+            "[" => after.find(']'),
+            "(" => after.find(')'),
+            _ => after.find('*').filter(|&end| is_action(&after[..end])),
+        };
+        match end {
+            Some(end) => rest = &after[end + 1..],
+            None => {
+                out.push_str(&rest[at..at + 1]);
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// "*laughs*", but not the " 4 is 12 " of "3 * 4 is 12 * 2"
+fn is_action(inner: &str) -> bool {
+    !inner.is_empty() && inner.chars().count() <= MAX_ACTION_CHARS && inner.trim() == inner
+}
+
+// Ratio of raw bytes to zlib bytes, as in the Whisper reference decoder.
+pub fn compression_ratio(text: &str) -> f32 {
+    if text.is_empty() { return 0.0; }
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    if encoder.write_all(text.as_bytes()).is_err() { return 0.0; }
+    match encoder.finish() {
+        Ok(compressed) if !compressed.is_empty() => text.len() as f32 / compressed.len() as f32,
+        _ => 0.0,
+    }
+}
+
+fn normalize(text: &str) -> String {
+    text.to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c.is_whitespace() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(text: &str, prob: f32) -> AsrSegment {
+        AsrSegment {
+            text: text.to_string(),
+            start_ms: 0,
+            end_ms: 3000,
+            avg_token_prob: prob,
+            min_token_prob: prob,
+            no_speech_prob: None,
+            language: None,
+        }
+    }
+
+    #[test]
+    fn test_blank_and_annotations() {
+        let filter = HallucinationFilter::new(FilterConfig::default());
+        let out = filter.apply(segment("[BLANK_AUDIO]", 0.9), "");
+        assert_eq!(out.verdict, FilterVerdict::Drop(FilterReason::Blank));
+
+        let out = filter.apply(segment("(music) The derivative of x squared is 2x.", 0.9), "");
+        assert_eq!(out.verdict, FilterVerdict::Keep);
+        assert_eq!(out.segment.text, "The derivative of x squared is 2x.");
+    }
+
+    #[test]
+    fn test_unclosed_annotations_are_speech() {
+        assert_eq!(strip_annotations("So f(x equals two x"), "So f(x equals two x");
+        assert_eq!(strip_annotations("(laughs"), "(laughs");
+        assert_eq!(strip_annotations("and [inaudible"), "and [inaudible");
+        assert_eq!(strip_annotations("a * b is the product"), "a * b is the product");
+        assert_eq!(strip_annotations("3 * 4 is 12 * 2 is 24"), "3 * 4 is 12 * 2 is 24");
+        assert_eq!(strip_annotations("*laughs* 3*4 is 12 [MUSIC]"), "3*4 is 12");
+        assert_eq!(strip_annotations("*clears throat* Right (applause) so"), "Right so");
+    }
+
+    #[test]
+    fn test_phantom_phrases() {
+        let mut filter = HallucinationFilter::new(FilterConfig::default());
+        let out = filter.apply(segment("Thanks for watching!", 0.9), "");
+        assert_eq!(out.verdict, FilterVerdict::Drop(FilterReason::PhantomPhrase));
+
+        // Real sentence containing "thank you" is kept
+        let out = filter.apply(segment("Thank you for the question about entropy, let's go back to the slide.", 0.9), "");
+        assert_eq!(out.verdict, FilterVerdict::Keep);
+
+        filter.set_blocklist(vec!["Merci d'avoir regardé".to_string()]);
+        let out = filter.apply(segment("Merci d'avoir regardé.", 0.9), "");
+        assert_eq!(out.verdict, FilterVerdict::Drop(FilterReason::PhantomPhrase));
+    }
+
+    #[test]
+    fn test_repetition_loop() {
+        let filter = HallucinationFilter::new(FilterConfig::default());
+        let looped = "and then we have the and then we have the and then we have the and then we have the";
+        let out = filter.apply(segment(looped, 0.9), "");
+        assert_eq!(out.verdict, FilterVerdict::Drop(FilterReason::RepetitionLoop));
+
+        let out = filter.apply(segment("so so so so so so", 0.9), "");
+        assert_eq!(out.verdict, FilterVerdict::Drop(FilterReason::RepetitionLoop));
+    }
+
+    #[test]
+    fn test_confidence_and_duplicates() {
+        let filter = HallucinationFilter::new(FilterConfig::default());
+        let text = "Entropy always increases in an isolated system.";
+        assert_eq!(filter.apply(segment(text, 0.1), "").verdict, FilterVerdict::Drop(FilterReason::LowConfidence));
+        assert_eq!(filter.apply(segment(text, 0.4), "").verdict, FilterVerdict::Flag(FilterReason::LowConfidence));
+
+        let context = "We started with heat. Entropy always increases in an isolated system. ";
+        assert_eq!(filter.apply(segment(text, 0.9), context).verdict, FilterVerdict::Drop(FilterReason::Duplicate));
+    }
+
+    #[test]
+    fn test_no_speech() {
+        let filter = HallucinationFilter::new(FilterConfig::default());
+        let mut seg = segment("Okay.", 0.45);
+        seg.no_speech_prob = Some(0.95);
+        assert_eq!(filter.apply(seg, "").verdict, FilterVerdict::Drop(FilterReason::NoSpeech));
+    }
+}
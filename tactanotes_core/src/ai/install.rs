@@ -0,0 +1,533 @@
+// Feature F17.1: Offline Model Packs
+// Phones in class are often offline, so models arrive as a zip / tar / tar.gz copied over USB or
+// SD card. A pack carries its own manifest.json (registry schema, optionally inside one top-level
+// folder); a pack without one may only contain files the device manifest already lists.
+//   1. Files are extracted into {models_dir}/.staging/<pack>/ as `<name>.part`, then renamed.
+//      Re-running after an interruption skips finished files and appends to `.part` files.
+//   2. Every model is hashed against its manifest SHA-256 while still in staging.
+//   3. Only then are files renamed into models_dir and the manifest merged (manifest last),
+//      so a corrupt pack never replaces a working model. Replaced files are kept in staging until
+//      the manifest is written; if the commit fails they go back (on the next run, if the app died).
+
+use super::asr::AsrBackendKind;
+use super::registry::{self, Manifest, ModelEntry, ModelKind, MANIFEST_FILE, MANIFEST_FORMAT};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+const STAGING_DIR: &str = ".staging";
+const MAX_MANIFEST_BYTES: u64 = 1 << 20;
+
+#[derive(Debug, thiserror::Error)]
+pub enum InstallError {
+    #[error("Cannot read model pack {path}: {source}")]
+    Open { path: PathBuf, source: io::Error },
+    #[error("{0} is not a zip, tar or tar.gz archive")]
+    UnknownFormat(PathBuf),
+    #[error("Model pack is damaged: {0}")]
+    Corrupt(String),
+    #[error("Model pack manifest is invalid: {0}")]
+    InvalidManifest(String),
+    #[error("Model pack needs a newer app (manifest format {found}, this app reads up to {supported})")]
+    NewerFormat { found: u32, supported: u32 },
+    #[error("Model '{id}' is not supported on this device: {reason}")]
+    Incompatible { id: String, reason: String },
+    #[error("Unsafe path in model pack: {0}")]
+    UnsafePath(String),
+    #[error("Model '{id}' is listed but {file} is missing from the pack")]
+    MissingFile { id: String, file: String },
+    #[error("Model '{id}' failed checksum (expected {expected}, found {actual}); the pack is corrupt")]
+    ChecksumMismatch { id: String, expected: String, actual: String },
+    #[error("Model pack contains no models this app knows about")]
+    NothingToInstall,
+    #[error("Writing {path} failed: {source}")]
+    Write { path: PathBuf, source: io::Error },
+    #[error("Model pack install cancelled; running it again resumes")]
+    Cancelled,
+}
+
+#[derive(Debug)]
+pub struct InstallReport {
+    pub installed: Vec<String>, // Model ids
+    pub resumed_files: usize,   // Files an earlier, interrupted run had already staged
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+type MemberFn<'a> = dyn FnMut(&str, u64, &mut dyn Read) -> Result<(), InstallError> + 'a;
+
+pub fn install_pack(archive: &Path, models_dir: &Path, device: &Manifest) -> Result<InstallReport, InstallError> {
+    install_pack_with_progress(archive, models_dir, device, &mut |_, _, _| true)
+}
+
+// F18: `progress(done, total, step)` is called per extracted and per verified file; returning
+// false stops before the next one (staging is kept, so the next run resumes) and nothing is committed
+pub fn install_pack_with_progress(
+    archive: &Path,
+    models_dir: &Path,
+    device: &Manifest,
+    progress: &mut dyn FnMut(usize, usize, &str) -> bool,
+) -> Result<InstallReport, InstallError> {
+    let format = detect_format(archive)?;
+    println!("ModelInstall: Reading {:?} ({:?})...", archive, format);
+
+    // Pass 1: member list and the pack manifest
+    let mut members: Vec<String> = Vec::new();
+    let mut pack: Option<(String, Manifest)> = None;
+    for_each_member(archive, format, &mut |name, size, reader| {
+        let name = clean_name(name);
+        let is_manifest = name == MANIFEST_FILE
+            || (name.ends_with(&format!("/{}", MANIFEST_FILE)) && name.matches('/').count() == 1);
+        if is_manifest && pack.is_none() {
+            if size > MAX_MANIFEST_BYTES {
+                return Err(InstallError::InvalidManifest(format!("{} is {} bytes", MANIFEST_FILE, size)));
+            }
+            let mut content = String::new();
+            reader.read_to_string(&mut content).map_err(|e| InstallError::Corrupt(e.to_string()))?;
+            let manifest: Manifest = serde_json::from_str(&content)
+                .map_err(|e| InstallError::InvalidManifest(e.to_string()))?;
+            pack = Some((name[..name.len() - MANIFEST_FILE.len()].to_string(), manifest));
+        }
+        members.push(name);
+        Ok(())
+    })?;
+
+    let (prefix, entries) = match pack {
+        Some((prefix, manifest)) => {
+            if manifest.format > MANIFEST_FORMAT {
+                return Err(InstallError::NewerFormat { found: manifest.format, supported: MANIFEST_FORMAT });
+            }
+            (prefix, manifest.models)
+        }
+        // No manifest: only files the device already expects
+        None => {
+            let known = device.models.iter()
+                .filter(|e| members.iter().any(|m| belongs(m, &e.file)))
+                .cloned()
+                .collect();
+            (String::new(), known)
+        }
+    };
+    if entries.is_empty() {
+        return Err(InstallError::NothingToInstall);
+    }
+    for entry in &entries {
+        validate_entry(entry)?;
+        let present = members.iter()
+            .filter_map(|m| m.strip_prefix(prefix.as_str()))
+            .any(|m| belongs(m, &entry.file));
+        if !present {
+            return Err(InstallError::MissingFile { id: entry.id.clone(), file: entry.file.clone() });
+        }
+    }
+
+    // Pass 2: extract into staging
+    let staging = staging_dir(models_dir, archive);
+    fs::create_dir_all(&staging).map_err(|e| InstallError::Write { path: staging.clone(), source: e })?;
+    let mut resumed_files = 0;
+    // Extract every member, then verify every model
+    let total = members.iter().filter_map(|m| m.strip_prefix(prefix.as_str()))
+        .filter(|m| entries.iter().any(|e| belongs(m, &e.file)))
+        .count() + entries.len();
+    let mut done = 0;
+    for_each_member(archive, format, &mut |name, size, reader| {
+        let name = clean_name(name);
+        let Some(relative) = name.strip_prefix(prefix.as_str()) else { return Ok(()) };
+        if !entries.iter().any(|e| belongs(relative, &e.file)) {
+            return Ok(()); // README, licences, ...
+        }
+        if !progress(done, total, relative) {
+            return Err(InstallError::Cancelled);
+        }
+        done += 1;
+        let dest = staging.join(safe_relative(relative)?);
+        if extract_member(reader, size, &dest)? {
+            resumed_files += 1;
+        }
+        Ok(())
+    })?;
+
+    // Verify before anything touches models_dir
+    for entry in &entries {
+        if !progress(done, total, &format!("Verifying {}", entry.id)) {
+            return Err(InstallError::Cancelled);
+        }
+        done += 1;
+        let staged = staging.join(&entry.file);
+        let actual = registry::sha256_of(&staged).map_err(|e| InstallError::Corrupt(e.to_string()))?;
+        if !actual.eq_ignore_ascii_case(entry.sha256.trim()) {
+            // Re-extract on the next attempt
+            let _ = if staged.is_dir() { fs::remove_dir_all(&staged) } else { fs::remove_file(&staged) };
+            return Err(InstallError::ChecksumMismatch {
+                id: entry.id.clone(),
+                expected: entry.sha256.clone(),
+                actual,
+            });
+        }
+    }
+
+    // Commit: each rename is atomic; replaced files wait in staging until the manifest is written
+    let replaced = staging.join(".replaced");
+    restore_backups(&entries, &replaced, models_dir);
+    let mut committed = Vec::new();
+    let result = commit(&entries, &staging, &replaced, models_dir, &mut committed).and_then(|()| {
+        let mut merged = device.clone();
+        merged.merge(&Manifest { format: MANIFEST_FORMAT, models: entries.clone() });
+        registry::write_manifest(models_dir, &merged).map_err(|e| InstallError::Write {
+            path: models_dir.join(MANIFEST_FILE),
+            source: io::Error::other(e.to_string()),
+        })
+    });
+    if let Err(e) = result {
+        roll_back(&committed);
+        return Err(e);
+    }
+    let _ = fs::remove_dir_all(&staging);
+
+    let installed: Vec<String> = entries.into_iter().map(|e| e.id).collect();
+    println!("ModelInstall: Installed {:?} ({} files resumed).", installed, resumed_files);
+    Ok(InstallReport { installed, resumed_files })
+}
+
+// (staged file, installed path, backup of the file it replaced)
+type Committed = (PathBuf, PathBuf, Option<PathBuf>);
+
+fn commit(entries: &[ModelEntry], staging: &Path, replaced: &Path, models_dir: &Path, committed: &mut Vec<Committed>) -> Result<(), InstallError> {
+    for entry in entries {
+        let from = staging.join(&entry.file);
+        let to = models_dir.join(&entry.file);
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent).map_err(|e| InstallError::Write { path: parent.to_path_buf(), source: e })?;
+        }
+        let mut backup = None;
+        if to.exists() {
+            let path = replaced.join(&entry.file);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).map_err(|e| InstallError::Write { path: parent.to_path_buf(), source: e })?;
+            }
+            fs::rename(&to, &path).map_err(|e| InstallError::Write { path: to.clone(), source: e })?;
+            backup = Some(path);
+        }
+        if let Err(e) = fs::rename(&from, &to) {
+            // Not in `committed` yet: put the old file back here
+            if let Some(backup) = &backup {
+                let _ = fs::rename(backup, &to);
+            }
+            return Err(InstallError::Write { path: to, source: e });
+        }
+        committed.push((from, to, backup));
+    }
+    Ok(())
+}
+
+// New files go back to staging (verified, so a retry only commits), old ones back into models_dir
+fn roll_back(committed: &[Committed]) {
+    for (staged, installed, backup) in committed.iter().rev() {
+        let _ = fs::rename(installed, staged);
+        if let Some(backup) = backup {
+            let _ = fs::rename(backup, installed);
+        }
+    }
+}
+
+// Backups left behind by a commit that never got to its manifest (the app was killed): the manifest
+// still describes the old files, so those go back before this run commits again
+fn restore_backups(entries: &[ModelEntry], replaced: &Path, models_dir: &Path) {
+    for entry in entries {
+        let backup = replaced.join(&entry.file);
+        if !backup.exists() { continue; }
+        let installed = models_dir.join(&entry.file);
+        let _ = if installed.is_dir() { fs::remove_dir_all(&installed) } else { fs::remove_file(&installed) };
+        if fs::rename(&backup, &installed).is_ok() {
+            println!("ModelInstall: Restored {} from an unfinished install.", entry.file);
+        }
+    }
+}
+
+fn detect_format(archive: &Path) -> Result<ArchiveFormat, InstallError> {
+    let mut head = [0u8; 512];
+    let mut file = File::open(archive).map_err(|e| InstallError::Open { path: archive.to_path_buf(), source: e })?;
+    let mut len = 0;
+    while len < head.len() {
+        match file.read(&mut head[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(e) => return Err(InstallError::Open { path: archive.to_path_buf(), source: e }),
+        }
+    }
+    let head = &head[..len];
+    if head.starts_with(b"PK\x03\x04") {
+        Ok(ArchiveFormat::Zip)
+    } else if head.starts_with(&[0x1f, 0x8b]) {
+        Ok(ArchiveFormat::TarGz)
+    } else if head.len() >= 262 && &head[257..262] == b"ustar" {
+        Ok(ArchiveFormat::Tar)
+    } else {
+        Err(InstallError::UnknownFormat(archive.to_path_buf()))
+    }
+}
+
+fn for_each_member(archive: &Path, format: ArchiveFormat, f: &mut MemberFn) -> Result<(), InstallError> {
+    let file = File::open(archive).map_err(|e| InstallError::Open { path: archive.to_path_buf(), source: e })?;
+    match format {
+        ArchiveFormat::Zip => {
+            let mut zip = zip::ZipArchive::new(file).map_err(|e| InstallError::Corrupt(e.to_string()))?;
+            for i in 0..zip.len() {
+                let mut member = zip.by_index(i).map_err(|e| InstallError::Corrupt(e.to_string()))?;
+                if member.is_dir() { continue; }
+                let name = member.name().to_string();
+                let size = member.size();
+                f(&name, size, &mut member)?;
+            }
+        }
+        ArchiveFormat::Tar | ArchiveFormat::TarGz => {
+            let reader: Box<dyn Read> = if format == ArchiveFormat::TarGz {
+                Box::new(flate2::read::GzDecoder::new(file))
+            } else {
+                Box::new(file)
+            };
+            let mut tar = tar::Archive::new(reader);
+            let entries = tar.entries().map_err(|e| InstallError::Corrupt(e.to_string()))?;
+            for member in entries {
+                let mut member = member.map_err(|e| InstallError::Corrupt(e.to_string()))?;
+                if !member.header().entry_type().is_file() { continue; }
+                let name = member.path().map_err(|e| InstallError::Corrupt(e.to_string()))?
+                    .to_string_lossy()
+                    .to_string();
+                let size = member.size();
+                f(&name, size, &mut member)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Returns true if an earlier run had already staged (part of) this file.
+fn extract_member(reader: &mut dyn Read, size: u64, dest: &Path) -> Result<bool, InstallError> {
+    let write_err = |e: io::Error| InstallError::Write { path: dest.to_path_buf(), source: e };
+    if fs::metadata(dest).map(|m| m.is_file() && m.len() == size).unwrap_or(false) {
+        return Ok(true);
+    }
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent).map_err(write_err)?;
+    }
+
+    let part = PathBuf::from(format!("{}.part", dest.display()));
+    let mut done = fs::metadata(&part).map(|m| m.len()).unwrap_or(0);
+    if done > size {
+        fs::remove_file(&part).map_err(write_err)?;
+        done = 0;
+    }
+    let resumed = done > 0;
+
+    // Compressed streams cannot seek: decode and drop what is already on disk
+    if done > 0 {
+        let skipped = io::copy(&mut reader.take(done), &mut io::sink())
+            .map_err(|e| InstallError::Corrupt(e.to_string()))?;
+        if skipped != done {
+            return Err(InstallError::Corrupt(format!("{:?} is shorter than its header says", dest)));
+        }
+    }
+
+    let mut out = OpenOptions::new().create(true).append(true).open(&part).map_err(write_err)?;
+    let mut buffer = vec![0u8; 1 << 16];
+    let mut written = done;
+    loop {
+        let n = reader.read(&mut buffer).map_err(|e| InstallError::Corrupt(e.to_string()))?;
+        if n == 0 { break; }
+        out.write_all(&buffer[..n]).map_err(write_err)?;
+        written += n as u64;
+    }
+    out.sync_all().map_err(write_err)?;
+    if written != size {
+        return Err(InstallError::Corrupt(format!("{:?} is truncated ({} of {} bytes)", dest, written, size)));
+    }
+    fs::rename(&part, dest).map_err(write_err)?;
+    Ok(resumed)
+}
+
+fn validate_entry(entry: &ModelEntry) -> Result<(), InstallError> {
+    safe_relative(&entry.file)?;
+    if entry.file == MANIFEST_FILE || entry.file.starts_with(STAGING_DIR) {
+        return Err(InstallError::UnsafePath(entry.file.clone()));
+    }
+    let sha = entry.sha256.trim();
+    if sha.len() != 64 || !sha.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(InstallError::InvalidManifest(format!("'{}' has no valid sha256", entry.id)));
+    }
+
+    if entry.kind == ModelKind::Asr {
+        let backend = entry.backend.as_deref().unwrap_or("");
+        match AsrBackendKind::parse(backend) {
+            None => {
+                return Err(InstallError::Incompatible {
+                    id: entry.id.clone(),
+                    reason: format!("unknown ASR backend '{}'", backend),
+                });
+            }
+            Some(AsrBackendKind::Whisper) if cfg!(target_arch = "wasm32") => {
+                return Err(InstallError::Incompatible {
+                    id: entry.id.clone(),
+                    reason: "whisper.cpp is not available in the web build".to_string(),
+                });
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+// Rejects absolute paths and `..` so a pack cannot write outside models_dir
+fn safe_relative(name: &str) -> Result<PathBuf, InstallError> {
+    let path = Path::new(name);
+    if name.is_empty() || !path.components().all(|c| matches!(c, Component::Normal(_))) {
+        return Err(InstallError::UnsafePath(name.to_string()));
+    }
+    Ok(path.to_path_buf())
+}
+
+fn clean_name(name: &str) -> String {
+    name.trim_start_matches("./").replace('\\', "/")
+}
+
+// `member` is the entry's file, or inside the entry's directory
+fn belongs(member: &str, file: &str) -> bool {
+    let file = file.trim_end_matches('/');
+    member == file || member.starts_with(&format!("{}/", file))
+}
+
+// Stable per archive, so a retry finds the previous run's files
+fn staging_dir(models_dir: &Path, archive: &Path) -> PathBuf {
+    let stem = archive.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let len = fs::metadata(archive).map(|m| m.len()).unwrap_or(0);
+    models_dir.join(STAGING_DIR).join(format!("{}-{}", stem, len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tactanotes_install_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn sha256_hex(data: &[u8]) -> String {
+        ring::digest::digest(&ring::digest::SHA256, data).as_ref().iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn pack_manifest(sha256: &str) -> String {
+        format!(r#"{{ "format": 1, "models": [
+            {{ "id": "whisper-test", "kind": "asr", "backend": "whisper", "file": "ggml-test.bin",
+               "sha256": "{}", "ram_mb": 10 }} ] }}"#, sha256)
+    }
+
+    #[test]
+    fn test_zip_install_resumes_partial_file() {
+        let dir = temp_dir("zip");
+        let models_dir = dir.join("models");
+        fs::create_dir_all(&models_dir).unwrap();
+        let model = vec![7u8; 100_000];
+
+        let archive = dir.join("pack.zip");
+        let mut zip = zip::ZipWriter::new(File::create(&archive).unwrap());
+        let options = zip::write::FileOptions::default();
+        zip.start_file("pack/manifest.json", options).unwrap();
+        zip.write_all(pack_manifest(&sha256_hex(&model)).as_bytes()).unwrap();
+        zip.start_file("pack/ggml-test.bin", options).unwrap();
+        zip.write_all(&model).unwrap();
+        zip.start_file("pack/README.txt", options).unwrap();
+        zip.write_all(b"not a model").unwrap();
+        zip.finish().unwrap();
+
+        // An earlier run got a third of the way
+        let staging = staging_dir(&models_dir, &archive);
+        fs::create_dir_all(&staging).unwrap();
+        fs::write(staging.join("ggml-test.bin.part"), &model[..30_000]).unwrap();
+
+        let report = install_pack(&archive, &models_dir, &Manifest::default()).unwrap();
+        assert_eq!(report.installed, vec!["whisper-test"]);
+        assert_eq!(report.resumed_files, 1);
+        assert_eq!(fs::read(models_dir.join("ggml-test.bin")).unwrap(), model);
+        assert!(!models_dir.join("README.txt").exists());
+        assert!(!staging.exists());
+
+        let manifest: Manifest = serde_json::from_str(&fs::read_to_string(models_dir.join(MANIFEST_FILE)).unwrap()).unwrap();
+        assert_eq!(manifest.models.len(), 1);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_corrupt_tar_gz_is_rejected() {
+        let dir = temp_dir("targz");
+        let models_dir = dir.join("models");
+        fs::create_dir_all(&models_dir).unwrap();
+
+        let archive = dir.join("pack.tar.gz");
+        let encoder = flate2::write::GzEncoder::new(File::create(&archive).unwrap(), flate2::Compression::default());
+        let mut tar = tar::Builder::new(encoder);
+        for (name, data) in [
+            ("manifest.json", pack_manifest(&sha256_hex(b"expected")).into_bytes()),
+            ("ggml-test.bin", b"tampered".to_vec()),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            tar.append_data(&mut header, name, data.as_slice()).unwrap();
+        }
+        tar.into_inner().unwrap().finish().unwrap();
+
+        let err = install_pack(&archive, &models_dir, &Manifest::default()).unwrap_err();
+        assert!(matches!(err, InstallError::ChecksumMismatch { .. }), "{}", err);
+        assert!(!models_dir.join("ggml-test.bin").exists());
+        assert!(!models_dir.join(MANIFEST_FILE).exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_failed_manifest_write_puts_the_old_model_back() {
+        let dir = temp_dir("rollback");
+        let models_dir = dir.join("models");
+        fs::create_dir_all(&models_dir).unwrap();
+        fs::write(models_dir.join("ggml-test.bin"), b"old model").unwrap();
+        // A directory in the way: the manifest rename fails after the model was committed
+        fs::create_dir_all(models_dir.join(MANIFEST_FILE)).unwrap();
+
+        let model = b"new model".to_vec();
+        let archive = dir.join("pack.tar");
+        let mut tar = tar::Builder::new(File::create(&archive).unwrap());
+        for (name, data) in [
+            ("manifest.json", pack_manifest(&sha256_hex(&model)).into_bytes()),
+            ("ggml-test.bin", model.clone()),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            tar.append_data(&mut header, name, data.as_slice()).unwrap();
+        }
+        tar.into_inner().unwrap();
+
+        let err = install_pack(&archive, &models_dir, &Manifest::default()).unwrap_err();
+        assert!(matches!(err, InstallError::Write { .. }), "{}", err);
+        assert_eq!(fs::read(models_dir.join("ggml-test.bin")).unwrap(), b"old model");
+
+        fs::remove_dir_all(models_dir.join(MANIFEST_FILE)).unwrap();
+        let report = install_pack(&archive, &models_dir, &Manifest::default()).unwrap();
+        assert_eq!(report.resumed_files, 1); // Verified last time, only committed now
+        assert_eq!(fs::read(models_dir.join("ggml-test.bin")).unwrap(), model);
+        assert!(!staging_dir(&models_dir, &archive).exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,291 @@
+// Feature F2.7: Keyphrases and tags
+// Candidates are runs of content words between stopwords and punctuation (stopwords of the note's
+// language, as LanguageDetector reports it). Two rankings are fused by reciprocal rank:
+//   RAKE: word score = degree / frequency, phrase score = sum of its word scores (favours specific
+//         multi-word terms)
+//   YAKE: per-word statistics (casing, position of first sentences, frequency, spread over
+//         sentences, variety of neighbours), lower = more important (favours terms the lecture is
+//         about over words that are merely frequent)
+// The best phrases become the note's automatic tags, lower case.
+
+use super::textrank::{is_stopword, split_sentences};
+use std::collections::{HashMap, HashSet};
+
+pub const AUTO_TAGS: usize = 5;
+pub const MAX_TAG_CHARS: usize = 40;
+const MAX_PHRASE_WORDS: usize = 3;
+// Reciprocal rank fusion constant; 60 is the usual choice
+const RRF_K: f32 = 60.0;
+
+// Words the summary stopwords let through that never make a useful tag: spoken filler, common
+// verbs and adverbs
+const FILLER_EN: &[&str] = &[
+    "actually", "basically", "going", "gonna", "know", "lecture", "look", "lot", "mean", "means",
+    "need", "talk", "think", "today", "want", "week", "class", "example", "remember", "next",
+    "take", "never", "always", "often", "give", "gives", "given", "make", "makes", "made", "use",
+    "used", "uses", "using", "stay", "stays", "keep", "keeps", "show", "shows", "explain",
+    "explains", "lose", "loses", "call", "called", "depend", "depends", "many", "much", "every",
+    "each", "important",
+];
+const STOPWORDS_FR: &[&str] = &[
+    "alors", "après", "au", "aussi", "aux", "avec", "avez", "avoir", "bon", "car", "ce", "cela",
+    "ces", "cet", "cette", "comme", "comment", "dans", "des", "donc", "dont", "elle", "elles", "en",
+    "est", "et", "étaient", "était", "être", "eux", "fait", "faut", "ici", "il", "ils", "je", "la",
+    "le", "les", "leur", "leurs", "lui", "mais", "même", "mes", "moi", "mon", "nous", "on", "ont",
+    "ou", "où", "par", "pas", "peut", "plus", "pour", "quand", "que", "quel", "quelle", "qui",
+    "sa", "sans", "se", "ses", "si", "son", "sont", "sous", "sur", "ta", "tes", "ton", "tous",
+    "tout", "très", "tu", "un", "une", "vous", "voilà", "donc", "euh", "ben", "aujourd", "hui",
+];
+const STOPWORDS_ES: &[&str] = &[
+    "al", "algo", "así", "como", "con", "cuando", "de", "del", "donde", "el", "ella", "ellos", "en",
+    "entonces", "era", "es", "esa", "ese", "eso", "esta", "este", "esto", "están", "fue", "hay",
+    "la", "las", "le", "les", "lo", "los", "más", "me", "muy", "nos", "no", "o", "para", "pero",
+    "por", "porque", "que", "qué", "se", "ser", "si", "sí", "sin", "sobre", "son", "su", "sus",
+    "también", "tiene", "todo", "todos", "un", "una", "uno", "unos", "vamos", "y", "ya", "bueno",
+    "pues", "hoy", "eh",
+];
+const STOPWORDS_DE: &[&str] = &[
+    "aber", "als", "also", "am", "an", "auch", "auf", "aus", "bei", "bis", "das", "dass", "dem",
+    "den", "der", "des", "die", "dies", "diese", "doch", "ein", "eine", "einem", "einen", "einer",
+    "es", "für", "hat", "haben", "heute", "ich", "ihr", "im", "in", "ist", "ja", "jetzt", "kann",
+    "mit", "nach", "nicht", "noch", "nur", "oder", "schon", "sein", "sich", "sie", "sind", "so",
+    "über", "um", "und", "uns", "von", "vor", "war", "was", "wenn", "wie", "wir", "wird", "zu",
+    "zum", "zur", "ähm", "genau", "okay",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keyphrase {
+    pub phrase: String, // As first written
+    pub score: f32,     // Fused; higher = better
+}
+
+fn is_stop(word: &str, lang: &str) -> bool {
+    // English stopwords also apply elsewhere: lectures mix in English terms
+    let local = match lang {
+        "fr" => STOPWORDS_FR,
+        "es" => STOPWORDS_ES,
+        "de" => STOPWORDS_DE,
+        _ => FILLER_EN,
+    };
+    is_stopword(word) || local.contains(&word) || FILLER_EN.contains(&word)
+}
+
+struct Candidate {
+    words: Vec<String>, // Lower case
+    surface: String,
+}
+
+struct Occurrence {
+    sentence: usize,
+    capitalized: bool, // Upper-case first letter, not at the start of a sentence
+    left: Option<String>,
+    right: Option<String>,
+}
+
+// Candidate phrases in order, and every occurrence of every content word
+fn scan(text: &str, lang: &str) -> (Vec<Candidate>, HashMap<String, Vec<Occurrence>>, usize) {
+    let mut candidates = Vec::new();
+    let mut occurrences: HashMap<String, Vec<Occurrence>> = HashMap::new();
+    let sentences = split_sentences(text);
+    for (index, sentence) in sentences.iter().enumerate() {
+        let mut run: Vec<(String, String)> = Vec::new();
+        let flush = |run: &mut Vec<(String, String)>, candidates: &mut Vec<Candidate>| {
+            for piece in run.chunks(MAX_PHRASE_WORDS) {
+                candidates.push(Candidate {
+                    words: piece.iter().map(|(lower, _)| lower.clone()).collect(),
+                    surface: piece.iter().map(|(_, surface)| surface.as_str()).collect::<Vec<_>>().join(" "),
+                });
+            }
+            run.clear();
+        };
+        let tokens: Vec<&str> = sentence.split(|c: char| c.is_whitespace() || c == '/').filter(|t| !t.is_empty()).collect();
+        let mut previous: Option<String> = None;
+        for (position, token) in tokens.iter().enumerate() {
+            // French elision: "l'entropie" is "entropie"
+            let token = match token.rsplit_once(['\'', '’']) {
+                Some((head, tail)) if head.chars().count() <= 2 => tail,
+                _ => token,
+            };
+            let word = token.trim_matches(|c: char| !c.is_alphanumeric());
+            let lower = word.to_lowercase();
+            let content = word.chars().count() > 2
+                && word.chars().all(|c| c.is_alphanumeric() || c == '-')
+                && !word.chars().all(|c| c.is_numeric())
+                && !is_stop(&lower, lang);
+            if let Some(prev) = previous.as_ref().filter(|_| content) {
+                if let Some(last) = occurrences.get_mut(prev).and_then(|o| o.last_mut()) {
+                    last.right = Some(lower.clone());
+                }
+            }
+            if content {
+                occurrences.entry(lower.clone()).or_default().push(Occurrence {
+                    sentence: index,
+                    capitalized: position > 0 && word.chars().next().is_some_and(char::is_uppercase),
+                    left: previous.clone(),
+                    right: None,
+                });
+                run.push((lower.clone(), word.to_string()));
+            } else {
+                flush(&mut run, &mut candidates);
+            }
+            previous = content.then_some(lower);
+            if token.ends_with([',', ';', ':', ')', '"', '.', '?', '!']) {
+                flush(&mut run, &mut candidates);
+                previous = None;
+            }
+        }
+        flush(&mut run, &mut candidates);
+    }
+    (candidates, occurrences, sentences.len().max(1))
+}
+
+// Distinct phrases (lower case) with their first spelling and how often they occur
+fn distinct(candidates: &[Candidate]) -> Vec<(Vec<String>, String, usize)> {
+    let mut phrases: Vec<(Vec<String>, String, usize)> = Vec::new();
+    for candidate in candidates {
+        match phrases.iter_mut().find(|(words, _, _)| *words == candidate.words) {
+            Some(found) => found.2 += 1,
+            None => phrases.push((candidate.words.clone(), candidate.surface.clone(), 1)),
+        }
+    }
+    phrases
+}
+
+// RAKE: best first
+pub fn rake(text: &str, lang: &str) -> Vec<Keyphrase> {
+    let (candidates, _, _) = scan(text, lang);
+    let mut frequency: HashMap<&str, f32> = HashMap::new();
+    let mut degree: HashMap<&str, f32> = HashMap::new();
+    for candidate in &candidates {
+        for word in &candidate.words {
+            *frequency.entry(word).or_default() += 1.0;
+            *degree.entry(word).or_default() += candidate.words.len() as f32;
+        }
+    }
+    let mut scored: Vec<Keyphrase> = distinct(&candidates).into_iter()
+        .map(|(words, surface, _)| Keyphrase {
+            score: words.iter().map(|w| degree[w.as_str()] / frequency[w.as_str()]).sum(),
+            phrase: surface,
+        })
+        .collect();
+    sort_best_first(&mut scored);
+    scored
+}
+
+// YAKE-style: best first (the score is negated, so higher is better here too)
+pub fn yake(text: &str, lang: &str) -> Vec<Keyphrase> {
+    let (candidates, occurrences, sentences) = scan(text, lang);
+    if occurrences.is_empty() {
+        return Vec::new();
+    }
+    let counts: Vec<f32> = occurrences.values().map(|o| o.len() as f32).collect();
+    let mean = counts.iter().sum::<f32>() / counts.len() as f32;
+    let std = (counts.iter().map(|c| (c - mean).powi(2)).sum::<f32>() / counts.len() as f32).sqrt();
+    let max_tf = counts.iter().cloned().fold(1.0, f32::max);
+
+    let word_score: HashMap<&str, f32> = occurrences.iter()
+        .map(|(word, occ)| {
+            let tf = occ.len() as f32;
+            let casing = occ.iter().filter(|o| o.capitalized).count() as f32 / (1.0 + tf.ln());
+            let mut positions: Vec<usize> = occ.iter().map(|o| o.sentence).collect();
+            positions.sort_unstable();
+            let median = positions[positions.len() / 2] as f32;
+            let position = (3.0 + median).ln().ln();
+            let frequency = tf / (mean + std);
+            let left: HashSet<&str> = occ.iter().filter_map(|o| o.left.as_deref()).collect();
+            let right: HashSet<&str> = occ.iter().filter_map(|o| o.right.as_deref()).collect();
+            let relatedness = 1.0 + (left.len() as f32 / tf + right.len() as f32 / tf) * tf / max_tf;
+            let spread = positions.iter().collect::<HashSet<_>>().len() as f32 / sentences as f32;
+            let score = relatedness * position / (casing + frequency / relatedness + spread / relatedness);
+            (word.as_str(), score)
+        })
+        .collect();
+
+    let mut scored: Vec<Keyphrase> = distinct(&candidates).into_iter()
+        .map(|(words, surface, count)| {
+            let product: f32 = words.iter().map(|w| word_score[w.as_str()]).product();
+            let sum: f32 = words.iter().map(|w| word_score[w.as_str()]).sum();
+            Keyphrase { phrase: surface, score: -(product / (count as f32 * (1.0 + sum))) }
+        })
+        .collect();
+    sort_best_first(&mut scored);
+    scored
+}
+
+fn sort_best_first(phrases: &mut [Keyphrase]) {
+    // Stable: ties keep the earlier phrase
+    phrases.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+}
+
+// Both rankings fused; a phrase inside a better one ("heat" after "heat engines") is left out
+pub fn extract(text: &str, lang: &str, limit: usize) -> Vec<Keyphrase> {
+    let mut fused: Vec<Keyphrase> = Vec::new();
+    for ranking in [rake(text, lang), yake(text, lang)] {
+        for (rank, keyphrase) in ranking.into_iter().enumerate() {
+            let score = 1.0 / (RRF_K + rank as f32 + 1.0);
+            match fused.iter_mut().find(|k| k.phrase.to_lowercase() == keyphrase.phrase.to_lowercase()) {
+                Some(found) => found.score += score,
+                None => fused.push(Keyphrase { phrase: keyphrase.phrase, score }),
+            }
+        }
+    }
+    sort_best_first(&mut fused);
+
+    let mut picked: Vec<Keyphrase> = Vec::new();
+    for keyphrase in fused {
+        let key = singular(&keyphrase.phrase.to_lowercase());
+        let overlaps = picked.iter().any(|p| {
+            let other = singular(&p.phrase.to_lowercase());
+            other.contains(&key) || key.contains(&other)
+        });
+        if !overlaps {
+            picked.push(keyphrase);
+        }
+        if picked.len() == limit { break; }
+    }
+    picked
+}
+
+fn singular(phrase: &str) -> String {
+    phrase.split_whitespace().map(|w| w.strip_suffix('s').filter(|s| s.len() > 3).unwrap_or(w)).collect::<Vec<_>>().join(" ")
+}
+
+// Tags are lower case, single-spaced, without a leading '#'; None if nothing is left
+pub fn normalize_tag(name: &str) -> Option<String> {
+    let tag = name.trim().trim_start_matches('#').split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+    if tag.is_empty() || tag.chars().count() > MAX_TAG_CHARS { None } else { Some(tag) }
+}
+
+// Automatic tags for a note: its best keyphrases
+pub fn propose_tags(text: &str, lang: &str, limit: usize) -> Vec<String> {
+    extract(text, lang, limit).into_iter().filter_map(|k| normalize_tag(&k.phrase)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keyphrases_favour_specific_repeated_terms() {
+        let text = "Today we look at the second law of thermodynamics. Entropy of an isolated system never \
+            decreases. The Carnot efficiency gives the upper limit for heat engines. Real heat engines lose \
+            work to friction, so their efficiency stays below the Carnot efficiency. Entropy explains why.";
+        let tags = propose_tags(text, "en", AUTO_TAGS);
+        assert_eq!(tags.len(), AUTO_TAGS);
+        assert!(tags.contains(&"carnot efficiency".to_string()), "{:?}", tags);
+        assert!(tags.iter().any(|t| t.ends_with("heat engines")), "{:?}", tags);
+        // YAKE's position weight: the topic announced up front
+        assert!(tags.contains(&"second law".to_string()), "{:?}", tags);
+        // Filler and lone parts of picked phrases stay out
+        assert!(!tags.iter().any(|t| t == "today" || t == "heat" || t == "efficiency"), "{:?}", tags);
+
+        let french = "Aujourd'hui nous parlons de l'entropie. L'entropie d'un système isolé ne diminue jamais. \
+            Le rendement de Carnot est la limite des moteurs thermiques.";
+        let tags = propose_tags(french, "fr", 3);
+        assert!(tags.contains(&"entropie".to_string()), "{:?}", tags);
+        assert!(!tags.iter().any(|t| t.split(' ').any(|w| STOPWORDS_FR.contains(&w))), "{:?}", tags);
+
+        assert_eq!(normalize_tag("  #Heat   Engines "), Some("heat engines".to_string()));
+        assert_eq!(normalize_tag(" # "), None);
+    }
+}
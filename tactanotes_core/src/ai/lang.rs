@@ -1,24 +1,386 @@
 // Feature F17: Language Detection
-// Uses fastText (<1MB model)
+// Character n-gram naive Bayes, CLD-style: the text's 1- to 3-grams (within words, word edges
+// marked with a space) are scored against each language's n-gram counts. The model is those
+// counts, trained on first use from lang/corpus.txt (~250 KB of lecture, classroom and everyday
+// text in 30 languages, plus romanized Hindi). Scripts do most of the work for Indic, Ethiopic,
+// CJK and Greek text; the n-grams separate languages that share one (Latin, Cyrillic, Arabic,
+// Devanagari).
+// F17.2: Lecturers switch languages mid-lecture, so transcripts are labelled segment by segment.
+// A 3-second segment is only a few words; labels are limited to the languages the whole session
+// uses, and segments too short or too close to call take their neighbours' language.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+const CORPUS: &str = include_str!("lang/corpus.txt");
+pub const DEFAULT_LANGUAGE: &str = "en";
+const MAX_ORDER: usize = 3;
+const SMOOTHING: f64 = 0.5;
+// Leading characters looked at; plenty to tell languages apart, and cheap on a whole transcript
+const MAX_CHARS: usize = 2000;
+// A letter shows up in up to MAX_ORDER overlapping n-grams, which naive Bayes counts as independent
+// evidence; log-likelihoods are divided by this to undo the overcounting
+const OVERLAP: f64 = MAX_ORDER as f64;
+// A few words are too little to be sure of, however distinctive they look: posteriors are mixed
+// with an even prior worth this many letters, so "Thank you." stays far from 1.0 and a paragraph
+// does not
+const PRIOR_LETTERS: f64 = 8.0;
+// F17.2: A language needs this share of the session's letters to be a segment label
+const SESSION_MIN_SHARE: f32 = 0.1;
+// Segments with fewer letters, or a weaker best label, follow their neighbours
+const SHORT_SEGMENT_LETTERS: usize = 15;
+const SEGMENT_MIN_CONFIDENCE: f32 = 0.6;
+// Punctuation outside ASCII that separates words
+const SEPARATORS: &str = "。、，．？！：；「」『』（）《》«»“”‘’„—–…·¿¡؟،؛।॥፡።፣፤";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LanguageScore {
+    pub code: &'static str,
+    pub confidence: f32, // Share of the probability over all languages, 0..1
+}
+
+struct Profile {
+    code: &'static str,
+    counts: [HashMap<String, u32>; MAX_ORDER],
+    totals: [u32; MAX_ORDER],
+}
+
+struct Model {
+    profiles: Vec<Profile>,
+    codes: Vec<&'static str>, // Languages, in corpus order; a language may have several profiles
+    vocabulary: [usize; MAX_ORDER], // Distinct n-grams of each order across all languages
+}
+
+fn model() -> &'static Model {
+    static MODEL: OnceLock<Model> = OnceLock::new();
+    MODEL.get_or_init(|| Model::build(CORPUS))
+}
+
+impl Model {
+    fn build(corpus: &'static str) -> Self {
+        let mut profiles: Vec<Profile> = Vec::new();
+        for line in corpus.lines() {
+            if line.starts_with('#') { continue; }
+            if let Some(code) = line.strip_prefix('@') {
+                // "@hi-Latn": a Hindi profile written in Latin letters
+                let code = code.trim().split('-').next().unwrap_or_default();
+                profiles.push(Profile { code, counts: Default::default(), totals: [0; MAX_ORDER] });
+                continue;
+            }
+            let Some(profile) = profiles.last_mut() else { continue };
+            ngrams(line, usize::MAX, &mut |order, gram| {
+                *profile.counts[order].entry(gram.to_string()).or_insert(0) += 1;
+                profile.totals[order] += 1;
+            });
+        }
+        let mut vocabulary = [0; MAX_ORDER];
+        for (order, size) in vocabulary.iter_mut().enumerate() {
+            let mut seen: Vec<&String> = profiles.iter().flat_map(|p| p.counts[order].keys()).collect();
+            seen.sort();
+            seen.dedup();
+            *size = seen.len();
+        }
+        let mut codes: Vec<&'static str> = Vec::new();
+        for profile in &profiles {
+            if !codes.contains(&profile.code) { codes.push(profile.code); }
+        }
+        Self { profiles, codes, vocabulary }
+    }
+
+    fn rank(&self, text: &str) -> Vec<LanguageScore> {
+        let mut grams: Vec<(usize, String)> = Vec::new();
+        ngrams(text, MAX_CHARS, &mut |order, gram| grams.push((order, gram.to_string())));
+        if grams.is_empty() { return Vec::new(); }
+
+        let log_likelihoods: Vec<f64> = self.profiles.iter()
+            .map(|profile| {
+                grams.iter()
+                    .map(|(order, gram)| {
+                        let count = profile.counts[*order].get(gram).copied().unwrap_or(0) as f64;
+                        let total = profile.totals[*order] as f64 + SMOOTHING * (self.vocabulary[*order] + 1) as f64;
+                        ((count + SMOOTHING) / total).ln()
+                    })
+                    .sum()
+            })
+            .collect();
+
+        // Posterior per language (summed over its profiles), then tempered by the text's length
+        let best = log_likelihoods.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let mut weights = vec![0.0; self.codes.len()];
+        for (profile, l) in self.profiles.iter().zip(&log_likelihoods) {
+            let index = self.codes.iter().position(|c| *c == profile.code).unwrap_or_default();
+            weights[index] += ((l - best) / OVERLAP).exp();
+        }
+        let sum: f64 = weights.iter().sum();
+        let letters = grams.iter().filter(|(order, _)| *order == 0).count() as f64;
+        let prior = PRIOR_LETTERS / (PRIOR_LETTERS + letters);
+        let even = prior / self.codes.len() as f64;
+
+        let mut scores: Vec<LanguageScore> = self.codes.iter().copied()
+            .zip(weights)
+            .map(|(code, weight)| LanguageScore { code, confidence: ((1.0 - prior) * weight / sum + even) as f32 })
+            .collect();
+        scores.sort_by(|a, b| b.confidence.partial_cmp(&a.confidence).unwrap_or(std::cmp::Ordering::Equal));
+        scores
+    }
+}
+
+// Calls `f(order - 1, gram)` for every 1..=MAX_ORDER-gram of each lowercase word in the first
+// `max_chars` characters; bigrams and trigrams include the word edges (" th", "he ")
+fn ngrams(text: &str, max_chars: usize, f: &mut dyn FnMut(usize, &str)) {
+    let mut padded: Vec<char> = Vec::new();
+    let mut gram = String::new();
+    let mut flush = |padded: &mut Vec<char>| {
+        if padded.len() > 1 {
+            padded.push(' ');
+            for order in 0..MAX_ORDER {
+                for window in padded.windows(order + 1) {
+                    if order == 0 && window[0] == ' ' { continue; }
+                    gram.clear();
+                    gram.extend(window);
+                    f(order, &gram);
+                }
+            }
+        }
+        padded.clear();
+    };
+    for c in text.chars().take(max_chars) {
+        let separator = c.is_whitespace() || c.is_ascii_punctuation() || c.is_numeric() || SEPARATORS.contains(c);
+        if separator {
+            flush(&mut padded);
+        } else {
+            if padded.is_empty() { padded.push(' '); }
+            padded.extend(c.to_lowercase());
+        }
+    }
+    flush(&mut padded);
+}
 
 pub struct LanguageDetector {
-    // model: fasttext::Model
+    model: &'static Model,
 }
 
 impl LanguageDetector {
     pub fn new() -> Self {
-        Self {}
+        Self { model: model() }
     }
 
+    // Most likely language code; DEFAULT_LANGUAGE for text without letters
     pub fn detect(&self, text: &str) -> String {
-        // Mock implementation
-        // Detects language based on simple keywords for testing
-        if text.contains("Bonjour") {
-            "fr".to_string()
-        } else if text.contains("Hola") {
-            "es".to_string()
-        } else {
-            "en".to_string()
+        self.rank(text).first().map(|s| s.code).unwrap_or(DEFAULT_LANGUAGE).to_string()
+    }
+
+    // Every known language, most likely first (empty for text without letters)
+    pub fn rank(&self, text: &str) -> Vec<LanguageScore> {
+        self.model.rank(text)
+    }
+
+    pub fn languages(&self) -> Vec<&'static str> {
+        self.model.codes.clone()
+    }
+
+    // F17.2: A language per segment of one session, in order (None for segments without letters)
+    pub fn label_segments(&self, texts: &[&str]) -> Vec<Option<LanguageScore>> {
+        let ranks: Vec<Vec<LanguageScore>> = texts.iter().map(|t| self.rank(t)).collect();
+        let letters: Vec<usize> = texts.iter().map(|t| t.chars().filter(|c| c.is_alphabetic()).count()).collect();
+
+        // Languages the session uses: each segment's probabilities, weighted by its length
+        let mut weights: HashMap<&'static str, f32> = HashMap::new();
+        for (rank, letters) in ranks.iter().zip(&letters) {
+            for score in rank {
+                *weights.entry(score.code).or_insert(0.0) += score.confidence * *letters as f32;
+            }
         }
+        let total: f32 = weights.values().sum();
+        let top = weights.values().cloned().fold(0.0, f32::max);
+        let session: Vec<&'static str> = weights.iter()
+            .filter(|(_, w)| **w >= total * SESSION_MIN_SHARE || **w == top)
+            .map(|(code, _)| *code)
+            .collect();
+
+        // Best session language per segment, renormalized over the session languages
+        let mut labels: Vec<Option<(LanguageScore, bool)>> = ranks.iter().zip(&letters)
+            .map(|(rank, letters)| {
+                let kept: Vec<&LanguageScore> = rank.iter().filter(|s| session.contains(&s.code)).collect();
+                let sum: f32 = kept.iter().map(|s| s.confidence).sum();
+                let best = kept.first().filter(|_| sum > 0.0)?;
+                let score = LanguageScore { code: best.code, confidence: best.confidence / sum };
+                let certain = *letters >= SHORT_SEGMENT_LETTERS && score.confidence >= SEGMENT_MIN_CONFIDENCE;
+                Some((score, certain))
+            })
+            .collect();
+
+        // Uncertain segments: whichever neighbouring certain label this segment finds more likely
+        let certain: Vec<Option<&'static str>> = labels.iter()
+            .map(|l| l.as_ref().filter(|(_, certain)| *certain).map(|(s, _)| s.code))
+            .collect();
+        for (i, label) in labels.iter_mut().enumerate() {
+            let Some((score, false)) = label else { continue };
+            let before = certain[..i].iter().rev().find_map(|c| *c);
+            let after = certain[i + 1..].iter().find_map(|c| *c);
+            let likelihood = |code: &str| ranks[i].iter().find(|s| s.code == code).map(|s| s.confidence).unwrap_or(0.0);
+            let neighbour = match (before, after) {
+                (Some(b), Some(a)) if likelihood(a) > likelihood(b) => Some(a),
+                (Some(b), _) => Some(b),
+                (None, a) => a,
+            };
+            if let Some(code) = neighbour {
+                if code != score.code {
+                    *score = LanguageScore { code, confidence: likelihood(code) };
+                }
+            }
+        }
+        labels.into_iter().map(|l| l.map(|(score, _)| score)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Sentences that are not in the corpus, on topics it does not cover: per language a long one,
+    // a medium one and two short ones (Hindi's last two romanized)
+    const HELD_OUT: &[(&str, [&str; 4])] = &[
+        ("en", ["Plants use sunlight to turn water and carbon dioxide into sugar, and the library closes early on Sundays.",
+            "The museum opened a new exhibition about ancient Egypt, and tickets are free for students.",
+            "Good question.", "Let's get started. Any questions from last time?"]),
+        ("fr", ["Les plantes utilisent la lumière du soleil pour transformer l'eau et le gaz carbonique en sucre, et la bibliothèque ferme tôt le dimanche.",
+            "Le musée a ouvert une nouvelle exposition sur l'Égypte ancienne, et l'entrée est gratuite pour les étudiants.",
+            "Bonne question.", "On se voit demain."]),
+        ("es", ["Las plantas usan la luz del sol para convertir el agua y el dióxido de carbono en azúcar, y la biblioteca cierra temprano los domingos.",
+            "El museo abrió una nueva exposición sobre el antiguo Egipto y la entrada es gratis para los estudiantes.",
+            "Buena pregunta.", "Hola a todos"]),
+        ("pt", ["As plantas usam a luz do sol para transformar a água e o gás carbônico em açúcar, e a biblioteca fecha cedo aos domingos.",
+            "O museu abriu uma nova exposição sobre o Egito antigo, e a entrada é gratuita para estudantes.",
+            "Boa pergunta.", "Até amanhã, pessoal."]),
+        ("de", ["Pflanzen nutzen das Sonnenlicht, um Wasser und Kohlendioxid in Zucker umzuwandeln, und die Bibliothek schließt sonntags früh.",
+            "Das Museum hat eine neue Ausstellung über das alte Ägypten eröffnet, und für Studenten ist der Eintritt frei.",
+            "Gute Frage.", "Bis morgen, Leute."]),
+        ("it", ["Le piante usano la luce del sole per trasformare l'acqua e l'anidride carbonica in zucchero, e la biblioteca chiude presto la domenica.",
+            "Il museo ha aperto una nuova mostra sull'antico Egitto, e l'ingresso è gratuito per gli studenti.",
+            "Bella domanda.", "Ci vediamo domani."]),
+        ("nl", ["Planten gebruiken zonlicht om water en koolstofdioxide om te zetten in suiker, en de bibliotheek sluit op zondag vroeg.",
+            "Het museum heeft een nieuwe tentoonstelling over het oude Egypte geopend, en studenten mogen gratis naar binnen.",
+            "Goede vraag.", "Tot morgen, jongens."]),
+        ("pl", ["Rośliny wykorzystują światło słoneczne, aby zamienić wodę i dwutlenek węgla w cukier, a biblioteka w niedziele zamyka się wcześnie.",
+            "Muzeum otworzyło nową wystawę o starożytnym Egipcie, a wstęp dla studentów jest bezpłatny.",
+            "Dobre pytanie.", "Do jutra."]),
+        ("tr", ["Bitkiler suyu ve karbondioksiti şekere dönüştürmek için güneş ışığını kullanır ve kütüphane pazar günleri erken kapanır.",
+            "Müze eski Mısır hakkında yeni bir sergi açtı ve öğrenciler için giriş ücretsiz.",
+            "Güzel soru.", "Yarın görüşürüz."]),
+        ("id", ["Tumbuhan menggunakan sinar matahari untuk mengubah air dan karbon dioksida menjadi gula, dan perpustakaan tutup lebih awal pada hari Minggu.",
+            "Museum itu membuka pameran baru tentang Mesir kuno, dan tiketnya gratis untuk mahasiswa.",
+            "Pertanyaan bagus.", "Sampai besok ya."]),
+        ("vi", ["Thực vật dùng ánh sáng mặt trời để biến nước và khí cacbonic thành đường, và thư viện đóng cửa sớm vào Chủ nhật.",
+            "Bảo tàng vừa mở một triển lãm mới về Ai Cập cổ đại, và sinh viên được vào cửa miễn phí.",
+            "Câu hỏi hay.", "Hẹn gặp lại ngày mai."]),
+        ("sw", ["Mimea hutumia mwanga wa jua kubadilisha maji na kaboni dioksidi kuwa sukari, na maktaba hufungwa mapema siku za Jumapili.",
+            "Jumba la makumbusho limefungua maonyesho mapya kuhusu Misri ya kale, na wanafunzi wanaingia bure.",
+            "Swali zuri.", "Tuonane kesho."]),
+        ("yo", ["Àwọn ewéko máa ń lo ìmọ́lẹ̀ oòrùn láti sọ omi àti afẹ́fẹ́ di ṣúgà, ilé ìkàwé sì máa ń tì ní kùtùkùtù ní ọjọ́ Àìkú.",
+            "Ilé ìkóhun-ìṣẹ̀ǹbáyé ti ṣí àfihàn tuntun nípa Íjíbítì àtijọ́, àwọn akẹ́kọ̀ọ́ sì lè wọlé lọ́fẹ̀ẹ́.",
+            "Ìbéèrè tó dára.", "Ó dìgbà ọ̀la."]),
+        ("ha", ["Tsirrai suna amfani da hasken rana don mayar da ruwa da iskar carbon dioxide zuwa sukari, kuma ɗakin karatu yana rufewa da wuri a ranar Lahadi.",
+            "Gidan tarihi ya buɗe sabon baje koli game da tsohuwar Masar, kuma ɗalibai suna shiga kyauta.",
+            "Tambaya mai kyau.", "Sai gobe."]),
+        ("zu", ["Izitshalo zisebenzisa ukukhanya kwelanga ukuguqula amanzi ne-carbon dioxide kube ushukela, futhi umtapo wolwazi uvala ngokushesha ngeSonto.",
+            "Umnyuziyamu uvule umbukiso omusha mayelana neGibhithe lasendulo, futhi abafundi bangena mahhala.",
+            "Umbuzo omuhle.", "Sizobonana kusasa."]),
+        ("am", ["እፅዋት ውሃንና ካርቦን ዳይኦክሳይድን ወደ ስኳር ለመቀየር የፀሐይ ብርሃንን ይጠቀማሉ፣ ቤተ መጻሕፍቱም እሁድ ቀደም ብሎ ይዘጋል።",
+            "ሙዚየሙ ስለ ጥንታዊቷ ግብፅ አዲስ ኤግዚቢሽን ከፍቷል፣ ተማሪዎችም በነጻ መግባት ይችላሉ።",
+            "ጥሩ ጥያቄ ነው።", "ነገ እንገናኝ።"]),
+        ("ar", ["تستخدم النباتات ضوء الشمس لتحويل الماء وثاني أكسيد الكربون إلى سكر، وتغلق المكتبة مبكرا يوم الأحد.",
+            "افتتح المتحف معرضا جديدا عن مصر القديمة، والدخول مجاني للطلاب.",
+            "سؤال جيد.", "أراكم غدا."]),
+        ("fa", ["گیاهان از نور خورشید استفاده می‌کنند تا آب و دی‌اکسید کربن را به قند تبدیل کنند، و کتابخانه یکشنبه‌ها زود بسته می‌شود.",
+            "موزه نمایشگاه تازه‌ای درباره‌ی مصر باستان افتتاح کرده و ورود برای دانشجوها رایگان است.",
+            "سؤال خوبی است.", "فردا می‌بینمتان."]),
+        ("ur", ["پودے سورج کی روشنی سے پانی اور کاربن ڈائی آکسائیڈ کو شکر میں بدلتے ہیں، اور کتب خانہ اتوار کو جلدی بند ہو جاتا ہے۔",
+            "عجائب گھر نے قدیم مصر کے بارے میں ایک نئی نمائش کھولی ہے، اور طلبہ کے لیے داخلہ مفت ہے۔",
+            "اچھا سوال ہے۔", "کل ملتے ہیں۔"]),
+        ("hi", ["पौधे पानी और कार्बन डाइऑक्साइड को शक्कर में बदलने के लिए सूरज की रोशनी का इस्तेमाल करते हैं, और पुस्तकालय रविवार को जल्दी बंद हो जाता है।",
+            "संग्रहालय ने प्राचीन मिस्र पर एक नई प्रदर्शनी खोली है, और छात्रों के लिए प्रवेश मुफ़्त है।",
+            "Aaj hum thermodynamics ke baare mein padhenge, dhyaan se suniye.", "Kal milte hain."]),
+        ("mr", ["वनस्पती पाणी आणि कार्बन डायऑक्साइडचे साखरेत रूपांतर करण्यासाठी सूर्यप्रकाशाचा वापर करतात, आणि ग्रंथालय रविवारी लवकर बंद होते.",
+            "संग्रहालयाने प्राचीन इजिप्तवर एक नवं प्रदर्शन सुरू केलं आहे, आणि विद्यार्थ्यांना प्रवेश मोफत आहे.",
+            "चांगला प्रश्न आहे.", "उद्या भेटू."]),
+        ("bn", ["গাছ পানি আর কার্বন ডাই অক্সাইডকে চিনিতে পরিণত করতে সূর্যের আলো ব্যবহার করে, আর গ্রন্থাগার রবিবারে তাড়াতাড়ি বন্ধ হয়।",
+            "জাদুঘরে প্রাচীন মিশর নিয়ে একটা নতুন প্রদর্শনী শুরু হয়েছে, আর ছাত্রছাত্রীদের জন্য প্রবেশ বিনামূল্যে।",
+            "ভালো প্রশ্ন।", "কাল দেখা হবে।"]),
+        ("ta", ["தாவரங்கள் நீரையும் கார்பன் டை ஆக்சைடையும் சர்க்கரையாக மாற்ற சூரிய ஒளியைப் பயன்படுத்துகின்றன, நூலகம் ஞாயிற்றுக்கிழமைகளில் சீக்கிரம் மூடப்படும்.",
+            "அருங்காட்சியகம் பண்டைய எகிப்து பற்றிய புதிய கண்காட்சியைத் திறந்துள்ளது, மாணவர்களுக்கு அனுமதி இலவசம்.",
+            "நல்ல கேள்வி.", "நாளை சந்திப்போம்."]),
+        ("te", ["మొక్కలు నీటిని మరియు కార్బన్ డయాక్సైడ్‌ను చక్కెరగా మార్చడానికి సూర్యరశ్మిని ఉపయోగిస్తాయి, మరియు గ్రంథాలయం ఆదివారం త్వరగా మూసివేయబడుతుంది.",
+            "మ్యూజియం ప్రాచీన ఈజిప్టు గురించి కొత్త ప్రదర్శనను ప్రారంభించింది, విద్యార్థులకు ప్రవేశం ఉచితం.",
+            "మంచి ప్రశ్న.", "రేపు కలుద్దాం."]),
+        ("ru", ["Растения используют солнечный свет, чтобы превращать воду и углекислый газ в сахар, а библиотека по воскресеньям закрывается рано.",
+            "Музей открыл новую выставку о Древнем Египте, и для студентов вход бесплатный.",
+            "Хороший вопрос.", "Увидимся завтра."]),
+        ("uk", ["Рослини використовують сонячне світло, щоб перетворювати воду й вуглекислий газ на цукор, а бібліотека в неділю зачиняється рано.",
+            "Музей відкрив нову виставку про Стародавній Єгипет, і для студентів вхід безкоштовний.",
+            "Гарне питання.", "Побачимося завтра."]),
+        ("el", ["Τα φυτά χρησιμοποιούν το φως του ήλιου για να μετατρέψουν το νερό και το διοξείδιο του άνθρακα σε ζάχαρη, και η βιβλιοθήκη κλείνει νωρίς την Κυριακή.",
+            "Το μουσείο άνοιξε μια νέα έκθεση για την αρχαία Αίγυπτο, και η είσοδος είναι δωρεάν για τους φοιτητές.",
+            "Καλή ερώτηση.", "Τα λέμε αύριο."]),
+        ("zh", ["植物利用阳光把水和二氧化碳变成糖，图书馆星期天很早就关门了。",
+            "博物馆开了一个关于古埃及的新展览，学生可以免费参观。",
+            "好问题。", "明天见。"]),
+        ("ja", ["植物は太陽の光を使って水と二酸化炭素を糖に変えます。図書館は日曜日には早く閉まります。",
+            "博物館で古代エジプトについての新しい展示が始まり、学生は無料で入れます。",
+            "いい質問ですね。", "また明日。"]),
+        ("ko", ["식물은 햇빛을 이용해 물과 이산화탄소를 당으로 바꾸고, 도서관은 일요일에 일찍 문을 닫습니다.",
+            "박물관에서 고대 이집트에 관한 새 전시회를 열었는데, 학생은 무료로 들어갈 수 있습니다.",
+            "좋은 질문이에요.", "내일 봐요."]),
+    ];
+
+    #[test]
+    fn test_held_out_sentences_in_every_language() {
+        let detector = LanguageDetector::new();
+        assert_eq!(detector.languages().len(), HELD_OUT.len());
+        let wrong: Vec<String> = HELD_OUT.iter()
+            .flat_map(|(code, texts)| texts.iter().map(move |text| (code, text)))
+            .filter_map(|(code, text)| {
+                assert!(!CORPUS.contains(text), "{}", text);
+                let ranked = detector.rank(text);
+                (ranked[0].code != *code).then(|| format!("{} -> {:?}: {}", code, &ranked[..2], text))
+            })
+            .collect();
+        assert!(wrong.is_empty(), "{:#?}", wrong);
+        assert!(CORPUS.len() < 1024 * 1024);
+    }
+
+    #[test]
+    fn test_confidence_grows_with_the_text() {
+        let detector = LanguageDetector::new();
+        let long = detector.rank(HELD_OUT[0].1[0]);
+        let total: f32 = long.iter().map(|s| s.confidence).sum();
+        assert!((total - 1.0).abs() < 1e-3);
+        assert!(long[0].confidence > 0.9 && long.windows(2).all(|w| w[0].confidence >= w[1].confidence));
+
+        // Two words are a guess, however English they look
+        let short = detector.rank("Thank you.");
+        assert_eq!(short[0].code, "en");
+        assert!(short[0].confidence < 0.7 && short[0].confidence < long[0].confidence, "{:?}", short[0]);
+        let medium = detector.rank("Thank you, that was a really interesting question.");
+        assert!(short[0].confidence < medium[0].confidence && medium[0].confidence < long[0].confidence);
+
+        assert_eq!(detector.detect("12:30 -- ?!"), DEFAULT_LANGUAGE);
+    }
+
+    #[test]
+    fn test_code_switched_segments_are_labelled_one_by_one() {
+        let detector = LanguageDetector::new();
+        let segments = [
+            "Okay everyone, today we start with the second law of thermodynamics.",
+            "Sasa, nishati haipotei, inabadilika tu kutoka hali moja kwenda nyingine.",
+            "Right.",
+            "So the entropy of an isolated system never decreases over time.",
+            "Kwa hiyo, kumbukeni kusoma sura ya tatu kabla ya Alhamisi.",
+            "--",
+        ];
+        let labels: Vec<Option<&str>> = detector.label_segments(&segments).iter()
+            .map(|l| l.as_ref().map(|s| s.code))
+            .collect();
+        assert_eq!(labels, vec![Some("en"), Some("sw"), Some("en"), Some("en"), Some("sw"), None]);
     }
 }
@@ -1,63 +1,277 @@
 // Feature F2: Summarisation
-// Uses Gemma-3-1B or Qwen2-0.5B (~300MB)
+// Generative: Qwen2.5-0.5B-Instruct (GGUF, ~400MB) through llama.cpp, see llm/gguf.rs.
+// Extractive: LexRank sentence ranking (textrank.rs). Used when no GGUF model is installed or it
+// fails, and in Endurance mode where a 0.5B decode would heat the phone further.
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod gguf;
+
+use super::budget::{BudgetReport, PromptParts};
+use super::locale;
+use super::prompt;
+use super::textrank;
+use std::path::Path;
+
+pub const DEFAULT_LLM_FILE: &str = "qwen2.5-0.5b-instruct-q4_k_m.gguf";
+const DEFAULT_MAX_TOKENS: usize = 320;
+// Unclear segments listed in the prompt; more just crowds out the transcript
+pub(crate) const MAX_UNSURE_HINTS: usize = 8;
+// English averages ~0.75 words per token
+const WORDS_PER_100_TOKENS: usize = 75;
+// ChatML end-of-turn, as used by the Qwen2.5 instruct models
+pub(crate) const CHAT_END: &str = "<|im_end|>";
+
+#[derive(Debug, Clone)]
+pub struct GenerationOptions {
+    pub max_tokens: usize,
+    pub stop: Vec<String>, // Output ends before the first match
+    // F2.3: Folder's prompt template, variables not filled in yet (None: default)
+    pub prompt: Option<String>,
+    // F2.3: Recording subject for {{subject}}
+    pub subject: String,
+    // F2.5: RAG context for {{context}}, kept apart so the prompt budget can trim it first
+    pub context: String,
+    // F2.8: Summary language code; the extractive summary's header is written in it (None: English)
+    pub language: Option<String>,
+}
+
+impl Default for GenerationOptions {
+    fn default() -> Self {
+        Self { max_tokens: DEFAULT_MAX_TOKENS, stop: Vec::new(), prompt: None, subject: String::new(), context: String::new(), language: None }
+    }
+}
+
+// `on_token` receives text as it is produced and returns false to stop early.
+pub trait Summarizer: Send {
+    fn name(&self) -> &'static str;
+    fn summarize(
+        &self,
+        text: &str,
+        low_confidence: &[String],
+        options: &GenerationOptions,
+        on_token: &mut dyn FnMut(&str) -> bool,
+    ) -> anyhow::Result<String>;
+
+    // F2.5: How the last prompt was fitted into the context window (models without a window: None)
+    fn last_budget(&self) -> Option<BudgetReport> {
+        None
+    }
+}
+
+pub struct ExtractiveSummarizer;
+
+impl Summarizer for ExtractiveSummarizer {
+    fn name(&self) -> &'static str {
+        "extractive"
+    }
+
+    fn summarize(
+        &self,
+        text: &str,
+        low_confidence: &[String],
+        options: &GenerationOptions,
+        on_token: &mut dyn FnMut(&str) -> bool,
+    ) -> anyhow::Result<String> {
+        let locale = options.language.as_deref().map(locale::get_or_english).unwrap_or_else(locale::english);
+        let mut summary = extractive_summary(text, low_confidence, options.max_tokens, locale);
+        if let Some(end) = find_stop(&summary, &options.stop) {
+            // Also drop the dangling bullet the cut leaves behind
+            let kept = summary[..end].trim_end_matches(|c: char| c.is_whitespace() || c == '-').len();
+            summary.truncate(kept);
+        }
+        on_token(&summary);
+        Ok(summary)
+    }
+}
 
 pub struct LlmModel {
     loaded: bool,
+    generative: Option<Box<dyn Summarizer>>,
 }
 
 impl LlmModel {
     pub fn load(_models_dir: &str) -> Self {
         println!("Loading Extractive Summarizer (Native)...");
         // Lightweight logic, no heavy model file required.
-        Self { loaded: true }
+        Self { loaded: true, generative: None }
+    }
+
+    // Falls back to the extractive summarizer if the GGUF file cannot be loaded
+    pub fn load_generative(model_path: &Path) -> Self {
+        #[cfg(not(target_arch = "wasm32"))]
+        match gguf::GgufSummarizer::load(model_path) {
+            Ok(model) => return Self { loaded: true, generative: Some(Box::new(model)) },
+            Err(e) => println!("LLM: {}, using extractive summaries.", e),
+        }
+        #[cfg(target_arch = "wasm32")]
+        println!("LLM: llama.cpp is not available in the web build ({:?}).", model_path);
+        Self::load("")
+    }
+
+    pub fn backend_name(&self) -> &'static str {
+        self.generative.as_ref().map(|g| g.name()).unwrap_or("extractive")
+    }
+
+    pub fn last_budget(&self) -> Option<BudgetReport> {
+        self.generative.as_ref().and_then(|g| g.last_budget())
     }
 
     pub fn summarize(&self, text: &str) -> String {
+        self.summarize_weighted(text, &[])
+    }
+
+    pub fn summarize_weighted(&self, text: &str, low_confidence: &[String]) -> String {
+        self.summarize_stream(text, low_confidence, &GenerationOptions::default(), &mut |_| true)
+    }
+
+    // If the generative model fails part-way, the extractive summary is streamed after
+    // whatever was already sent; the returned string is the one to keep.
+    pub fn summarize_stream(
+        &self,
+        text: &str,
+        low_confidence: &[String],
+        options: &GenerationOptions,
+        on_token: &mut dyn FnMut(&str) -> bool,
+    ) -> String {
         if !self.loaded || text.trim().is_empty() { return String::new(); }
 
-        let sentences: Vec<&str> = text.split(|c| c == '.' || c == '?' || c == '!').collect();
-        let mut seen_sentences = std::collections::HashSet::new();
-        
-        let significant_sentences: Vec<String> = sentences.iter()
-            .filter(|s| {
-                let s = s.trim();
-                if s.len() < 15 { return false; }
-                if s.contains("[BLANK_AUDIO]") { return false; }
-                
-                // Deduplication Filter
-                let s_lower = s.to_lowercase();
-                if seen_sentences.contains(&s_lower) { return false; }
-                seen_sentences.insert(s_lower);
-
-                // Key phrases filter
-                let key_phrases = ["important", "remember", "note", "summary", "conclusion", "idea", "concept", "key"];
-                // Relaxation: Accept if > 25 chars OR has key phrase. 50 was too strict.
-                key_phrases.iter().any(|&k| s.to_lowercase().contains(k)) || s.len() > 25 
-            })
-            .map(|s| s.trim().to_string())
-            .collect();
-
-        if significant_sentences.is_empty() {
-             // Fallback: Just take the first few meaningful sentences
-             let fallback: Vec<String> = sentences.iter()
-                .filter(|s| s.len() > 10)
-                .take(3)
-                .map(|s| s.trim().to_string())
-                .collect();
-             
-             if fallback.is_empty() {
-                 return format!("Main points: {}", text);
-             }
-             return format!("Summary:\n- {}", fallback.join(".\n- "));
+        if let Some(model) = &self.generative {
+            match model.summarize(text, low_confidence, options, on_token) {
+                Ok(summary) if !summary.trim().is_empty() => return summary,
+                Ok(_) => println!("LLM: {} produced no text, using extractive summary.", model.name()),
+                Err(e) => println!("LLM: {} failed ({}), using extractive summary.", model.name(), e),
+            }
         }
+        ExtractiveSummarizer.summarize(text, low_confidence, options, on_token).unwrap_or_default()
+    }
+
+    // The generative model's output only, for callers with a fallback of their own (answers, titles)
+    pub fn generate_only(
+        &self,
+        text: &str,
+        options: &GenerationOptions,
+        on_token: &mut dyn FnMut(&str) -> bool,
+    ) -> anyhow::Result<String> {
+        let model = self.generative.as_ref().filter(|_| self.loaded)
+            .ok_or_else(|| anyhow::anyhow!("No generative model is loaded"))?;
+        model.summarize(text, &[], options, on_token)
+    }
+}
+
+// F2.1: LexRank + MMR (see textrank.rs), sized from the token budget
+fn extractive_summary(text: &str, low_confidence: &[String], max_tokens: usize, locale: &locale::Locale) -> String {
+    let max_words = (max_tokens * WORDS_PER_100_TOKENS / 100).max(1);
+    let sentences = textrank::summarize(text, low_confidence, max_words);
+    if sentences.is_empty() {
+        return format!("{}: {}", locale.main_points, text.trim());
+    }
+    format!("{}:\n- {}", locale.key_points, sentences.join("\n- "))
+}
 
-        format!("Key Takeaways:\n- {}", significant_sentences.join(".\n- "))
+// Every template variable is filled in here, in one pass (prompt.rs)
+pub(crate) fn summary_prompt(parts: PromptParts) -> String {
+    let mut user = prompt::render(
+        parts.template.unwrap_or(prompt::DEFAULT_TEMPLATE),
+        &[
+            ("transcript", parts.transcript.trim()),
+            ("context", parts.context),
+            ("subject", parts.subject),
+            ("language", parts.language),
+        ],
+    );
+    let unsure: Vec<&str> = parts.hints.iter()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .take(MAX_UNSURE_HINTS)
+        .collect();
+    if !unsure.is_empty() {
+        user.push_str("\n\nThese parts were hard to hear and may be wrong, do not rely on them:");
+        for phrase in unsure {
+            user.push_str(&format!("\n- {}", phrase));
+        }
     }
+    format!(
+        "<|im_start|>system\nYou are a concise study assistant.{end}\n<|im_start|>user\n{user}{end}\n<|im_start|>assistant\n",
+        end = CHAT_END,
+        user = user,
+    )
+}
+
+// Byte offset of the earliest stop sequence
+pub(crate) fn find_stop(text: &str, stop: &[String]) -> Option<usize> {
+    stop.iter()
+        .filter(|s| !s.is_empty())
+        .filter_map(|s| text.find(s.as_str()))
+        .min()
+}
+
+// How much of `text` can be streamed without sending the beginning of a stop sequence
+pub(crate) fn streamable_len(text: &str, stop: &[String]) -> usize {
+    let mut safe = text.len();
+    for s in stop.iter().filter(|s| !s.is_empty()) {
+        // Longest proper prefix of `s` that `text` ends with
+        for k in (1..s.len().min(text.len() + 1)).rev() {
+            if s.is_char_boundary(k) && text.ends_with(&s[..k]) {
+                safe = safe.min(text.len() - k);
+                break;
+            }
+        }
+    }
+    safe
 }
 
 impl Drop for LlmModel {
     fn drop(&mut self) {
-        println!("Unloading Extractive Summarizer... (Freed 0MB)");
+        println!("Unloading {} summarizer...", self.backend_name());
         self.loaded = false;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stop_sequences_are_held_back_while_streaming() {
+        let stop = vec!["\n\nTranscript".to_string(), CHAT_END.to_string()];
+        assert_eq!(find_stop("- Entropy rises.<|im_end|>junk", &stop), Some(16));
+        assert_eq!(find_stop("- Entropy rises.", &stop), None);
+
+        // "<|im" could still become "<|im_end|>"
+        assert_eq!(streamable_len("- Entropy rises.<|im", &stop), 16);
+        assert_eq!(streamable_len("- Entropy rises.\n", &stop), 16);
+        assert_eq!(streamable_len("- Entropy rises.", &stop), 16);
+        assert_eq!(streamable_len("- Café", &stop), "- Café".len());
+    }
+
+    #[test]
+    fn test_extractive_fallback_streams_and_stops() {
+        let llm = LlmModel::load("./models");
+        assert_eq!(llm.backend_name(), "extractive");
+
+        let text = "Remember that entropy always increases in a closed system. The exam covers chapter four.";
+        let options = GenerationOptions { max_tokens: 64, stop: vec!["The exam".to_string()], ..Default::default() };
+        let mut streamed = String::new();
+        let summary = llm.summarize_stream(text, &[], &options, &mut |t| { streamed.push_str(t); true });
+
+        assert_eq!(summary, streamed);
+        assert!(summary.starts_with("Key Takeaways:\n- Remember that entropy"));
+        assert!(!summary.contains("The exam"));
+        // Answers and titles have fallbacks of their own
+        assert!(llm.generate_only(text, &options, &mut |_| true).is_err());
+    }
+
+    #[test]
+    fn test_prompt_variables_are_filled_in_once() {
+        let parts = PromptParts {
+            template: Some("{{subject}} ({{language}})\n{{context}}\n{{transcript}}"),
+            subject: "Thermo {{transcript}}",
+            language: "fr",
+            transcript: "Entropy {{subject}} rises.",
+            context: "- Past note quoting {{context}}",
+            hints: &[],
+        };
+        let prompt = summary_prompt(parts);
+        assert!(prompt.contains("Thermo {{transcript}} (fr)\n- Past note quoting {{context}}\nEntropy {{subject}} rises."));
+    }
+}
@@ -0,0 +1,169 @@
+// Feature F2: Generative summaries via llama.cpp (llama-cpp-2 bindings)
+// llama.cpp maps the GGUF file itself (use_mmap is on by default), so the weights live in the page
+// cache like the other models (F8.2). The context (KV cache) is created per summary and freed after.
+
+use super::{find_stop, streamable_len, GenerationOptions, Summarizer, CHAT_END};
+use crate::ai::budget::{self, BudgetReport, PromptParts};
+use llama_cpp_2::context::params::LlamaContextParams;
+use llama_cpp_2::llama_backend::LlamaBackend;
+use llama_cpp_2::llama_batch::LlamaBatch;
+use llama_cpp_2::model::params::LlamaModelParams;
+use llama_cpp_2::model::{AddBos, LlamaModel, Special};
+use llama_cpp_2::sampling::LlamaSampler;
+use llama_cpp_2::token::LlamaToken;
+use std::num::NonZeroU32;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+const CONTEXT_TOKENS: u32 = 4096;
+const BATCH_TOKENS: usize = 512;
+const THREADS: i32 = 4; // Big cores only; the little ones keep the UI and ASR responsive
+
+// llama.cpp's backend can only be initialised once per process
+fn backend() -> anyhow::Result<&'static LlamaBackend> {
+    static BACKEND: OnceLock<Result<LlamaBackend, String>> = OnceLock::new();
+    BACKEND
+        .get_or_init(|| {
+            LlamaBackend::init()
+                .map(|mut backend| {
+                    backend.void_logs();
+                    backend
+                })
+                .map_err(|e| e.to_string())
+        })
+        .as_ref()
+        .map_err(|e| anyhow::anyhow!("llama.cpp backend unavailable: {}", e))
+}
+
+pub struct GgufSummarizer {
+    model: LlamaModel,
+    n_ctx: u32,
+    last_budget: Mutex<Option<BudgetReport>>,
+}
+
+impl GgufSummarizer {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Err(anyhow::anyhow!("GGUF model not found at {:?}", path));
+        }
+        println!("Loading LLM from {:?}...", path);
+        let model = LlamaModel::load_from_file(backend()?, path, &LlamaModelParams::default())
+            .map_err(|e| anyhow::anyhow!("Failed to load GGUF model: {}", e))?;
+        let n_ctx = match model.n_ctx_train() {
+            0 => CONTEXT_TOKENS,
+            trained => trained.min(CONTEXT_TOKENS),
+        };
+        println!("LLM Loaded Successfully ({} token context).", n_ctx);
+        Ok(Self { model, n_ctx, last_budget: Mutex::new(None) })
+    }
+
+    // F2.5: Trims RAG context, hints and transcript by priority until prompt + answer fit the window
+    fn tokenize_prompt(&self, text: &str, low_confidence: &[String], options: &GenerationOptions) -> anyhow::Result<Vec<LlamaToken>> {
+        let parts = PromptParts {
+            template: options.prompt.as_deref(),
+            subject: &options.subject,
+            language: options.language.as_deref().unwrap_or_default(),
+            transcript: text,
+            context: &options.context,
+            hints: low_confidence,
+        };
+        let (prompt, report) = budget::fit(parts, self.n_ctx as usize, options.max_tokens, &mut |prompt| {
+            Ok(self.model.str_to_token(prompt, AddBos::Always)?.len())
+        })?;
+        if !report.dropped.is_empty() {
+            println!("LLM: Prompt fitted to {} tokens, left out: {}.", report.prompt_tokens, report.dropped.join(", "));
+        }
+        if let Ok(mut last) = self.last_budget.lock() {
+            *last = Some(report);
+        }
+        Ok(self.model.str_to_token(&prompt, AddBos::Always)?)
+    }
+}
+
+impl Summarizer for GgufSummarizer {
+    fn name(&self) -> &'static str {
+        "llama.cpp"
+    }
+
+    fn last_budget(&self) -> Option<BudgetReport> {
+        self.last_budget.lock().ok()?.clone()
+    }
+
+    fn summarize(
+        &self,
+        text: &str,
+        low_confidence: &[String],
+        options: &GenerationOptions,
+        on_token: &mut dyn FnMut(&str) -> bool,
+    ) -> anyhow::Result<String> {
+        let prompt = self.tokenize_prompt(text, low_confidence, options)?;
+        // The budget may have shortened the answer to leave room for the prompt
+        let max_tokens = self.last_budget().map(|b| b.output_tokens).unwrap_or(options.max_tokens).max(1);
+        let params = LlamaContextParams::default()
+            .with_n_ctx(NonZeroU32::new(self.n_ctx))
+            .with_n_batch(BATCH_TOKENS as u32)
+            .with_n_threads(THREADS)
+            .with_n_threads_batch(THREADS);
+        let mut ctx = self.model.new_context(backend()?, params)
+            .map_err(|e| anyhow::anyhow!("Failed to create LLM context: {}", e))?;
+
+        // 1. Prompt, BATCH_TOKENS at a time; logits only for the last token
+        let mut batch = LlamaBatch::new(BATCH_TOKENS, 1);
+        let last = prompt.len() - 1;
+        for (chunk_index, chunk) in prompt.chunks(BATCH_TOKENS).enumerate() {
+            batch.clear();
+            for (i, token) in chunk.iter().enumerate() {
+                let pos = chunk_index * BATCH_TOKENS + i;
+                batch.add(*token, pos as i32, &[0], pos == last)?;
+            }
+            ctx.decode(&mut batch)?;
+        }
+
+        // 2. Greedy decode: repeatable notes, and sampling buys little on a 0.5B model
+        let mut stop = options.stop.clone();
+        stop.push(CHAT_END.to_string());
+        let mut sampler = LlamaSampler::greedy();
+        let mut pending: Vec<u8> = Vec::new(); // A token can end mid UTF-8 character
+        let mut output = String::new();
+        let mut streamed = 0;
+
+        for pos in (prompt.len() as i32..).take(max_tokens) {
+            let token = sampler.sample(&ctx, batch.n_tokens() - 1);
+            sampler.accept(token);
+            if self.model.is_eog_token(token) {
+                break;
+            }
+
+            pending.extend(self.model.token_to_bytes(token, Special::Tokenize)?);
+            let valid = match std::str::from_utf8(&pending) {
+                Ok(_) => pending.len(),
+                Err(e) => e.valid_up_to(),
+            };
+            output.push_str(std::str::from_utf8(&pending[..valid]).unwrap_or_default());
+            pending.drain(..valid);
+
+            if let Some(end) = find_stop(&output, &stop) {
+                output.truncate(end);
+                break;
+            }
+            let safe = streamable_len(&output, &stop);
+            if safe > streamed {
+                let keep_going = on_token(&output[streamed..safe]);
+                streamed = safe;
+                if !keep_going {
+                    output.truncate(streamed);
+                    return Ok(output.trim().to_string());
+                }
+            }
+
+            batch.clear();
+            batch.add(token, pos, &[0], true)?;
+            ctx.decode(&mut batch)?;
+        }
+
+        if output.len() > streamed {
+            on_token(&output[streamed..]);
+        }
+        Ok(output.trim().to_string())
+    }
+}
@@ -0,0 +1,188 @@
+// Feature F2.8: Summary language
+// Summaries are written in the lecture's language (detected, or chosen by the user) with section
+// headers in that language. The prompt templates stay in English, which the 0.5B model follows
+// best: their quoted headers ("Key Takeaways:") are swapped for the localized ones and a closing
+// instruction in the target language asks for notes in it. The languages are ones the multilingual
+// Whisper models transcribe well; anything else gets English headers.
+
+pub struct Locale {
+    pub code: &'static str,   // ISO 639-1, as the language detector returns it
+    pub name: &'static str,   // English name
+    pub native: &'static str, // Name in the language itself
+    pub key_points: &'static str,
+    pub action_items: &'static str,
+    pub definitions: &'static str,
+    pub questions: &'static str,
+    pub dates: &'static str,
+    // Label of a one-line extractive summary ("Main points: ...")
+    pub main_points: &'static str,
+    // Appended to the summary prompt; empty for English, which the templates are written in
+    pub instruction: &'static str,
+    // F2.6: Title of a note with no subject or topic, and the date format of titles (chrono, numeric
+    // except in English: month names would come out in English)
+    pub lecture: &'static str,
+    pub date_format: &'static str,
+    pub and: &'static str, // Joins the two phrases of a title's topic
+}
+
+pub const LOCALES: &[Locale] = &[
+    Locale {
+        code: "en", name: "English", native: "English",
+        key_points: "Key Takeaways", action_items: "Action Items", definitions: "Definitions",
+        questions: "Open Questions", dates: "Dates", main_points: "Main points",
+        instruction: "",
+        lecture: "Lecture", date_format: "%-d %b %Y", and: "and",
+    },
+    Locale {
+        code: "fr", name: "French", native: "Français",
+        key_points: "Points clés", action_items: "À faire", definitions: "Définitions",
+        questions: "Questions ouvertes", dates: "Dates", main_points: "Points principaux",
+        instruction: "Rédige les notes en français.",
+        lecture: "Cours", date_format: "%d/%m/%Y", and: "et",
+    },
+    Locale {
+        code: "es", name: "Spanish", native: "Español",
+        key_points: "Puntos clave", action_items: "Tareas", definitions: "Definiciones",
+        questions: "Preguntas abiertas", dates: "Fechas", main_points: "Ideas principales",
+        instruction: "Escribe las notas en español.",
+        lecture: "Clase", date_format: "%d/%m/%Y", and: "y",
+    },
+    Locale {
+        code: "de", name: "German", native: "Deutsch",
+        key_points: "Kernaussagen", action_items: "Aufgaben", definitions: "Definitionen",
+        questions: "Offene Fragen", dates: "Termine", main_points: "Hauptpunkte",
+        instruction: "Schreibe die Notizen auf Deutsch.",
+        lecture: "Vorlesung", date_format: "%d.%m.%Y", and: "und",
+    },
+    Locale {
+        code: "it", name: "Italian", native: "Italiano",
+        key_points: "Punti chiave", action_items: "Cose da fare", definitions: "Definizioni",
+        questions: "Domande aperte", dates: "Date", main_points: "Punti principali",
+        instruction: "Scrivi gli appunti in italiano.",
+        lecture: "Lezione", date_format: "%d/%m/%Y", and: "e",
+    },
+    Locale {
+        code: "pt", name: "Portuguese", native: "Português",
+        key_points: "Pontos-chave", action_items: "Tarefas", definitions: "Definições",
+        questions: "Perguntas em aberto", dates: "Datas", main_points: "Pontos principais",
+        instruction: "Escreva as notas em português.",
+        lecture: "Aula", date_format: "%d/%m/%Y", and: "e",
+    },
+    Locale {
+        code: "nl", name: "Dutch", native: "Nederlands",
+        key_points: "Belangrijkste punten", action_items: "Actiepunten", definitions: "Definities",
+        questions: "Open vragen", dates: "Data", main_points: "Hoofdpunten",
+        instruction: "Schrijf de aantekeningen in het Nederlands.",
+        lecture: "College", date_format: "%d-%m-%Y", and: "en",
+    },
+    Locale {
+        code: "ru", name: "Russian", native: "Русский",
+        key_points: "Главное", action_items: "Задания", definitions: "Определения",
+        questions: "Открытые вопросы", dates: "Даты", main_points: "Основные моменты",
+        instruction: "Напиши конспект на русском языке.",
+        lecture: "Лекция", date_format: "%d.%m.%Y", and: "и",
+    },
+    Locale {
+        code: "zh", name: "Chinese", native: "中文",
+        key_points: "要点", action_items: "待办事项", definitions: "定义",
+        questions: "待解决的问题", dates: "日期", main_points: "主要内容",
+        instruction: "请用中文写笔记。",
+        lecture: "讲座", date_format: "%Y年%-m月%-d日", and: "与",
+    },
+    Locale {
+        code: "ja", name: "Japanese", native: "日本語",
+        key_points: "要点", action_items: "やること", definitions: "定義",
+        questions: "未解決の問題", dates: "日付", main_points: "主なポイント",
+        instruction: "ノートは日本語で書いてください。",
+        lecture: "講義", date_format: "%Y年%-m月%-d日", and: "と",
+    },
+    Locale {
+        code: "hi", name: "Hindi", native: "हिन्दी",
+        key_points: "मुख्य बातें", action_items: "करने योग्य कार्य", definitions: "परिभाषाएँ",
+        questions: "खुले प्रश्न", dates: "तिथियाँ", main_points: "मुख्य बिंदु",
+        instruction: "नोट्स हिंदी में लिखें।",
+        lecture: "व्याख्यान", date_format: "%d/%m/%Y", and: "और",
+    },
+    Locale {
+        code: "ar", name: "Arabic", native: "العربية",
+        key_points: "أهم النقاط", action_items: "المهام", definitions: "التعريفات",
+        questions: "أسئلة مفتوحة", dates: "التواريخ", main_points: "النقاط الرئيسية",
+        instruction: "اكتب الملاحظات باللغة العربية.",
+        lecture: "محاضرة", date_format: "%d/%m/%Y", and: "و",
+    },
+    Locale {
+        code: "sw", name: "Swahili", native: "Kiswahili",
+        key_points: "Mambo muhimu", action_items: "Kazi za kufanya", definitions: "Ufafanuzi",
+        questions: "Maswali wazi", dates: "Tarehe", main_points: "Hoja kuu",
+        instruction: "Andika maelezo kwa Kiswahili.",
+        lecture: "Mhadhara", date_format: "%d/%m/%Y", and: "na",
+    },
+];
+
+pub fn english() -> &'static Locale {
+    &LOCALES[0]
+}
+
+// "fr", "FR" and "fr-CA" all find French; None if the language has no localized headers
+pub fn get(code: &str) -> Option<&'static Locale> {
+    let base = code.trim().split(['-', '_']).next().unwrap_or_default().to_lowercase();
+    LOCALES.iter().find(|l| l.code == base)
+}
+
+pub fn get_or_english(code: &str) -> &'static Locale {
+    get(code).unwrap_or_else(english)
+}
+
+impl Locale {
+    // Headers in the order the note renders them
+    pub fn headers(&self) -> [&'static str; 5] {
+        [self.key_points, self.action_items, self.definitions, self.questions, self.dates]
+    }
+
+    // The template's quoted English headers in this language, then the instruction to write in it
+    pub fn localize_prompt(&self, template: &str) -> String {
+        let mut out = template.to_string();
+        for (english, localized) in english().headers().iter().zip(self.headers()) {
+            out = out.replace(&format!("\"{}:\"", english), &format!("\"{}:\"", localized));
+        }
+        if !self.instruction.is_empty() {
+            out.push_str("\n\n");
+            out.push_str(self.instruction);
+        }
+        out
+    }
+}
+
+// F17.2: Prompt line for a lecture spoken in several languages (codes, largest share first)
+pub fn mixed_language_note(codes: &[&str]) -> String {
+    let names: Vec<&str> = codes.iter().map(|c| get(c).map(|l| l.name).unwrap_or(c)).collect();
+    format!("The lecturer switches between {}. Keep terms in the language they were said in.", names.join(" and "))
+}
+
+// "Main points: ..." in any language: the text after the label
+pub fn strip_main_points(line: &str) -> Option<&str> {
+    LOCALES.iter().find_map(|l| line.strip_prefix(l.main_points)?.strip_prefix([':', '：']))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::prompt;
+
+    #[test]
+    fn test_prompts_ask_for_localized_headers() {
+        assert_eq!(get("fr-CA").map(|l| l.name), Some("French"));
+        assert!(get("xx").is_none());
+        assert_eq!(get_or_english("").code, "en");
+
+        assert_eq!(english().localize_prompt(prompt::DEFAULT_TEMPLATE), prompt::DEFAULT_TEMPLATE);
+        let french = get_or_english("fr").localize_prompt(prompt::DEFAULT_TEMPLATE);
+        assert!(french.contains("Start with \"Points clés:\""));
+        assert!(french.contains("\"À faire:\" (homework and deadlines)"));
+        assert!(french.ends_with("{{transcript}}\n\nRédige les notes en français."));
+        assert!(prompt::validate(&french).is_ok());
+
+        assert_eq!(strip_main_points("Hoja kuu: Nishati"), Some(" Nishati"));
+        assert_eq!(strip_main_points("Mainly this"), None);
+    }
+}
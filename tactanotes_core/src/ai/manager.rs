@@ -1,25 +1,63 @@
-use super::asr::WhisperModel;
-use super::llm::LlmModel;
+use super::asr::{self, AsrBackend, AsrBackendKind, AsrCapabilities, AsrSegment, DecodeOptions, MockAsr};
+use super::budget::BudgetReport;
+use super::llm::{self, GenerationOptions, LlmModel};
+use super::mmap;
+use super::punct::Punctuator;
 use super::rag::VectorStore;
+use super::registry::{self, ModelEntry, ModelKind, ModelRegistry};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
 
 // Feature 8.1: Inference Interleaving & Memory Management
 // Critical: Only one model type should be Some(...) at a time.
 
+// F17: What the UI shows about the models. Kept current by the manager and read without its lock,
+// which a Whisper or LLM pass holds for seconds.
+#[derive(Debug, Clone, Default)]
+pub struct ModelStatus {
+    pub last_error: Option<String>,      // Why the last load failed
+    pub models: Vec<(ModelEntry, bool)>, // Manifest entries with their install state
+}
+
 pub struct ModelManager {
-    asr: Option<WhisperModel>,
+    asr: Option<Box<dyn AsrBackend>>,
+    asr_kind: AsrBackendKind,
+    multilingual: bool, // F1.2: Translate mode, so only models that know other languages will do
+    punct: Option<Punctuator>, // F1.3: Lives and dies with ASR; run on the worker thread
     llm: Option<LlmModel>,
     rag: Option<VectorStore>,
     models_dir: String,
+
+    // F17: Manifest-driven model selection
+    registry: ModelRegistry,
+    low_power: Arc<AtomicBool>,   // Endurance mode caps the memory budget; set by the engine without the lock
+    status: Arc<RwLock<ModelStatus>>, // For the UI, readable while a pass holds the manager
+    last_budget: Option<BudgetReport>, // F2.5: Kept when the LLM is unloaded
 }
 
 impl ModelManager {
     pub fn new(models_dir: &str) -> Self {
         console_log(&format!("Initializing AI Model Manager in {}", models_dir));
+        let (registry, last_error) = match ModelRegistry::load(models_dir) {
+            Ok(registry) => (registry, None),
+            Err(e) => {
+                console_log(&format!("Model manifest ignored: {}", e));
+                (ModelRegistry::from_manifest(models_dir, Default::default()), Some(e.to_string()))
+            }
+        };
         Self {
             asr: None,
+            asr_kind: AsrBackendKind::platform_default(),
+            multilingual: false,
+            punct: None,
             llm: None,
             rag: None,
             models_dir: models_dir.to_string(),
+            low_power: Arc::new(AtomicBool::new(false)),
+            status: Arc::new(RwLock::new(ModelStatus { last_error, models: installed_models(&registry) })),
+            registry,
+            last_budget: None,
         }
     }
 
@@ -31,14 +69,182 @@ impl ModelManager {
             self.unload_rag(); // Unload RAG if switching back to recording
         }
         if self.asr.is_none() {
-            self.asr = Some(WhisperModel::load(&self.models_dir));
+            let loaded = match self.load_registered_asr() {
+                Some(result) => result,
+                None => {
+                    let mut backend = match asr::multilingual_file(self.asr_kind) {
+                        Some(file) if self.multilingual => asr::create_backend_for_file(self.asr_kind, file),
+                        _ => asr::create_backend(self.asr_kind),
+                    };
+                    backend.load(&self.models_dir).map(|_| backend)
+                }
+            };
+            let mut backend = match loaded {
+                Ok(backend) => {
+                    self.set_last_error(None);
+                    backend
+                }
+                Err(e) => {
+                    println!("ModelManager: ASR backend '{}' failed to load: {}", self.asr_kind.as_str(), e);
+                    self.set_last_error(Some(format!("ASR: {}", e)));
+                    asr::create_backend(self.asr_kind) // Unloaded: transcribes nothing
+                }
+            };
+            if !backend.is_loaded() && cfg!(target_arch = "wasm32") {
+                // Keep the web demo alive without model files
+                let mut mock = MockAsr::new().with_fallback("Simulated Transcription (WASM)");
+                let _ = mock.load(&self.models_dir);
+                backend = Box::new(mock);
+            }
+            self.asr = Some(backend);
+            log_memory("ASR loaded");
+        }
+        if self.punct.is_none() {
+            self.punct = Some(Punctuator::load(&self.models_dir));
+        }
+    }
+
+    // F17: Best verified manifest entry for the current backend and memory budget.
+    // None if the manifest lists no model for this backend (built-in file names apply).
+    fn load_registered_asr(&mut self) -> Option<anyhow::Result<Box<dyn AsrBackend>>> {
+        let backend_name = self.asr_kind.as_str();
+        if !self.registry.has_kind(ModelKind::Asr, Some(backend_name)) {
+            return None;
+        }
+
+        let budget = registry::memory_budget_mb(self.low_power());
+        let mut last_error = anyhow::anyhow!("No '{}' ASR model from {} is installed", backend_name, registry::MANIFEST_FILE);
+        for entry in self.registry.candidates(ModelKind::Asr, Some(backend_name), budget) {
+            if self.multilingual && !asr::is_multilingual_file(&entry.file) {
+                continue;
+            }
+            if let Err(e) = self.registry.verify(&entry) {
+                console_log(&format!("Skipping {}: {}", entry.id, e));
+                last_error = e;
+                continue;
+            }
+            let mut backend = asr::create_backend_for_file(self.asr_kind, &entry.file);
+            match backend.load(&self.models_dir) {
+                Ok(()) => {
+                    console_log(&format!("ASR model '{}' loaded (~{}MB, budget {:?}MB)", entry.id, entry.ram_mb, budget));
+                    return Some(Ok(backend));
+                }
+                Err(e) => last_error = e,
+            }
+        }
+        Some(Err(last_error))
+    }
+
+    // F17: Endurance mode limits models to registry::ENDURANCE_BUDGET_MB from the next load on
+    pub fn set_low_power(&self, low_power: bool) {
+        self.low_power.store(low_power, Ordering::Relaxed);
+    }
+
+    // F8.2: Shared flag, so tick can switch modes while the ASR worker holds the manager
+    pub fn low_power_flag(&self) -> Arc<AtomicBool> {
+        self.low_power.clone()
+    }
+
+    fn low_power(&self) -> bool {
+        self.low_power.load(Ordering::Relaxed)
+    }
+
+    pub fn registry(&mut self) -> &mut ModelRegistry {
+        &mut self.registry
+    }
+
+    // Picks up a manifest written after startup (e.g. by a model download)
+    pub fn reload_registry(&mut self) -> anyhow::Result<()> {
+        self.registry = ModelRegistry::load(&self.models_dir)?;
+        if let Ok(mut status) = self.status.write() {
+            status.models = installed_models(&self.registry);
+        }
+        Ok(())
+    }
+
+    pub fn last_error(&self) -> Option<String> {
+        self.status.read().ok().and_then(|status| status.last_error.clone())
+    }
+
+    // Shared with the engine, like the low power flag
+    pub fn status_handle(&self) -> Arc<RwLock<ModelStatus>> {
+        self.status.clone()
+    }
+
+    fn set_last_error(&self, error: Option<String>) {
+        if let Ok(mut status) = self.status.write() {
+            status.last_error = error;
+        }
+    }
+
+    // Backend is picked from configuration; takes effect on the next load_asr()
+    pub fn set_asr_backend(&mut self, kind: AsrBackendKind) {
+        if self.asr_kind != kind {
+            println!("ModelManager: Switching ASR backend {} -> {}", self.asr_kind.as_str(), kind.as_str());
+            self.asr_kind = kind;
+            self.unload_asr();
+        }
+    }
+
+    pub fn asr_backend(&self) -> AsrBackendKind {
+        self.asr_kind
+    }
+
+    // Injects a ready backend (e.g. a scripted MockAsr in tests). Replaced on the next
+    // unload/load cycle by whatever `asr_kind` selects.
+    pub fn install_asr_backend(&mut self, backend: Box<dyn AsrBackend>) {
+        self.unload_llm();
+        self.unload_rag();
+        self.asr = Some(backend);
+    }
+
+    // F1.2: Translate mode needs a multilingual model. A loaded English-only one is swapped out;
+    // if none is installed the mode stays off and the English model stays loaded.
+    pub fn set_multilingual(&mut self, multilingual: bool) -> anyhow::Result<()> {
+        self.multilingual = multilingual;
+        if !multilingual {
+            return Ok(()); // English-only models are welcome again from the next load on
+        }
+        if !self.multilingual_installed() {
+            self.multilingual = false;
+            return Err(anyhow::anyhow!("ASR backend '{}' cannot translate: no multilingual model is installed", self.asr_kind.as_str()));
+        }
+        if self.asr_capabilities().is_some_and(|c| !c.translation) {
+            self.unload_asr();
+            self.load_asr();
+            if let Some(capabilities) = self.asr_capabilities().filter(|c| !c.translation) {
+                self.multilingual = false;
+                self.unload_asr();
+                self.load_asr();
+                return Err(anyhow::anyhow!("ASR backend '{}' cannot translate (needs a multilingual model)", capabilities.name));
+            }
+        }
+        Ok(())
+    }
+
+    fn multilingual_installed(&mut self) -> bool {
+        if self.asr_kind == AsrBackendKind::Mock {
+            return true;
+        }
+        let backend_name = self.asr_kind.as_str();
+        if self.registry.has_kind(ModelKind::Asr, Some(backend_name)) {
+            let budget = registry::memory_budget_mb(self.low_power());
+            return self.registry.candidates(ModelKind::Asr, Some(backend_name), budget)
+                .iter()
+                .any(|entry| asr::is_multilingual_file(&entry.file));
         }
+        asr::multilingual_file(self.asr_kind).is_some_and(|file| Path::new(&self.models_dir).join(file).exists())
+    }
+
+    pub fn asr_capabilities(&self) -> Option<AsrCapabilities> {
+        self.asr.as_ref().map(|asr| asr.capabilities())
     }
 
     pub fn unload_asr(&mut self) {
         if self.asr.is_some() {
             self.asr = None; // Drop trait will function here
         }
+        self.punct = None;
     }
 
     pub fn load_llm(&mut self) {
@@ -46,15 +252,53 @@ impl ModelManager {
             self.unload_asr(); // Safety enforcement
         }
         if self.llm.is_none() {
-            self.llm = Some(LlmModel::load(&self.models_dir));
+            let llm = if self.low_power() {
+                console_log("Endurance: generative summaries disabled, using extractive summarizer.");
+                LlmModel::load(&self.models_dir)
+            } else {
+                match self.registered_llm_path() {
+                    Some(path) => LlmModel::load_generative(&path),
+                    None => LlmModel::load(&self.models_dir),
+                }
+            };
+            self.llm = Some(llm);
+            log_memory("LLM loaded");
+        }
+    }
+
+    // F2: Best verified GGUF from the manifest, else the built-in file name
+    fn registered_llm_path(&mut self) -> Option<PathBuf> {
+        if self.registry.has_kind(ModelKind::Llm, None) {
+            let budget = registry::memory_budget_mb(self.low_power());
+            for entry in self.registry.candidates(ModelKind::Llm, None, budget) {
+                match self.registry.verify(&entry) {
+                    Ok(()) => return Some(self.registry.path_of(&entry)),
+                    Err(e) => console_log(&format!("Skipping {}: {}", entry.id, e)),
+                }
+            }
+            return None;
         }
+        let legacy = Path::new(&self.models_dir).join(llm::DEFAULT_LLM_FILE);
+        legacy.exists().then_some(legacy)
+    }
+
+    pub fn llm_backend(&self) -> Option<&'static str> {
+        self.llm.as_ref().map(|llm| llm.backend_name())
+    }
+
+    // F2.5: How the last generative prompt was fitted into the context window
+    pub fn last_budget(&self) -> Option<BudgetReport> {
+        self.llm.as_ref().and_then(|llm| llm.last_budget()).or_else(|| self.last_budget.clone())
     }
 
     pub fn unload_llm(&mut self) {
+        if let Some(report) = self.llm.as_ref().and_then(|llm| llm.last_budget()) {
+            self.last_budget = Some(report);
+        }
         if self.llm.is_some() {
             println!("ModelManager: Unloading LLM tensors...");
             self.llm = None; // Drop trait will function here
-            self.force_gc();
+            Self::force_gc();
         }
     }
 
@@ -66,14 +310,35 @@ impl ModelManager {
         }
         if self.rag.is_none() {
              println!("ModelManager: Loading RAG Embedding Model...");
-             if let Ok(store) = VectorStore::new() {
+             let loaded = match self.load_registered_embed() {
+                 Some(store) => Ok(store),
+                 None => VectorStore::new(),
+             };
+             if let Ok(store) = loaded {
                  self.rag = Some(store);
+                 log_memory("RAG loaded");
              } else {
                  println!("ModelManager: Failed to load RAG model.");
              }
         }
     }
 
+    // F17: Installed embedding export, else fastembed's own MiniLM download
+    fn load_registered_embed(&mut self) -> Option<VectorStore> {
+        let budget = registry::memory_budget_mb(self.low_power());
+        for entry in self.registry.candidates(ModelKind::Embed, None, budget) {
+            if let Err(e) = self.registry.verify(&entry) {
+                console_log(&format!("Skipping {}: {}", entry.id, e));
+                continue;
+            }
+            match VectorStore::from_dir(&self.registry.path_of(&entry)) {
+                Ok(store) => return Some(store),
+                Err(e) => console_log(&format!("Embedding model '{}' failed to load: {}", entry.id, e)),
+            }
+        }
+        None
+    }
+
     pub fn unload_rag(&mut self) {
         if self.rag.is_some() {
             println!("ModelManager: Unloading RAG model...");
@@ -89,6 +354,21 @@ impl ModelManager {
         }
     }
 
+    pub fn transcribe_segments(&self, audio: &[f32], options: &DecodeOptions) -> Vec<AsrSegment> {
+        if let Some(asr) = &self.asr {
+            asr.segments(audio, options)
+        } else {
+            Vec::new()
+        }
+    }
+
+    // F1.3: Punctuation while ASR is loaded (see Punctuator::punctuate_segments); unchanged otherwise
+    pub fn punctuate_segments(&self, segments: &mut [AsrSegment], end_ms: i64) {
+        if let Some(punct) = &self.punct {
+            punct.punctuate_segments(segments, end_ms);
+        }
+    }
+
     pub fn summarize(&self, text: &str) -> String {
         if let Some(llm) = &self.llm {
             llm.summarize(text)
@@ -97,6 +377,43 @@ impl ModelManager {
         }
     }
 
+    // F1.4: `low_confidence` holds transcript segments the decoder was unsure about
+    pub fn summarize_weighted(&self, text: &str, low_confidence: &[String]) -> String {
+        if let Some(llm) = &self.llm {
+            llm.summarize_weighted(text, low_confidence)
+        } else {
+            String::new()
+        }
+    }
+
+    // F2: Streams generated text through `on_token` (return false to stop early)
+    pub fn summarize_stream(
+        &self,
+        text: &str,
+        low_confidence: &[String],
+        options: &GenerationOptions,
+        on_token: &mut dyn FnMut(&str) -> bool,
+    ) -> String {
+        if let Some(llm) = &self.llm {
+            llm.summarize_stream(text, low_confidence, options, on_token)
+        } else {
+            String::new()
+        }
+    }
+
+    // No extractive fallback: fails without a GGUF model loaded
+    pub fn generate_only(
+        &self,
+        text: &str,
+        options: &GenerationOptions,
+        on_token: &mut dyn FnMut(&str) -> bool,
+    ) -> anyhow::Result<String> {
+        match &self.llm {
+            Some(llm) => llm.generate_only(text, options, on_token),
+            None => Err(anyhow::anyhow!("The LLM is not loaded")),
+        }
+    }
+
     pub fn embed(&self, text: &str) -> Option<Vec<f32>> {
         if let Some(rag) = &self.rag {
             rag.embed(text).ok()
@@ -105,13 +422,44 @@ impl ModelManager {
         }
     }
 
+    // F18: Embeds for a job running beside the engine's own phases: the RAG model is loaded for the
+    // call if it is not already. Refused while the live ASR holds the slot; swapping it out would
+    // stall the recording for every call.
+    pub fn embed_borrowed(&mut self, texts: &[String]) -> anyhow::Result<Vec<Option<Vec<f32>>>> {
+        if self.asr.is_some() {
+            return Err(anyhow::anyhow!("The embedding model is unavailable while recording"));
+        }
+        let had_rag = self.rag.is_some();
+        self.load_rag();
+        let vectors = texts.iter().map(|text| self.embed(text)).collect();
+        if !had_rag {
+            self.unload_rag();
+        }
+        Ok(vectors)
+    }
+
     // Feature v5.3: Manual Memory Collection
-    pub fn force_gc(&self) {
+    // Needs no model state, so callers do not have to take the manager's lock
+    pub fn force_gc() {
         println!("ModelManager: Triggering manual heap collection (mi_collect).");
         // unsafe { libmimalloc_sys::mi_collect(true) };
     }
 }
 
+fn installed_models(registry: &ModelRegistry) -> Vec<(ModelEntry, bool)> {
+    registry.entries().iter()
+        .map(|m| (m.clone(), registry.is_installed(m)))
+        .collect()
+}
+
 fn console_log(msg: &str) {
     println!("[AI Manager] {}", msg);
 }
+
+// F8.2: Heap vs page-cache split after each swap (mapped weights count as file pages)
+fn log_memory(stage: &str) {
+    if let Some(usage) = mmap::memory_usage() {
+        console_log(&format!("{}: RSS {}MB (heap {}MB, mapped files {}MB)",
+            stage, usage.total_kb() / 1024, usage.anon_kb / 1024, usage.file_kb / 1024));
+    }
+}
@@ -0,0 +1,164 @@
+// Feature F2.2: Hierarchical (map-reduce) summaries for long sessions
+// RollingBuffer only holds the last ~8000 characters, so a 3-hour lecture cannot be summarized in
+// one pass. Map: every transcript window of ~WINDOW_CHARS is summarized on its own while recording
+// (at the 2-minute checkpoints) and persisted. Reduce: at stop the partial summaries are joined in
+// time order; if they still exceed the budget, neighbouring groups are summarized again, level by
+// level, until the whole session fits one summarizer call.
+
+use super::llm::GenerationOptions;
+use super::locale;
+use super::transcript::{TranscriptSegment, TranscriptTrack};
+
+// ~1.5k tokens of English, well inside the 4096-token context with prompt and answer
+pub const WINDOW_CHARS: usize = 6000;
+pub const REDUCE_BUDGET_CHARS: usize = 8000;
+// Length of one window's summary
+const PARTIAL_MAX_TOKENS: usize = 160;
+// Enough for 8000^4 characters; stops a summarizer that does not shorten from looping
+const MAX_LEVELS: usize = 4;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartialSummary {
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub summary: String,
+}
+
+impl PartialSummary {
+    // "[12:00-18:30]" then the bullets, without the "Key Takeaways:" header (in any language)
+    pub fn render(&self) -> String {
+        let body: Vec<&str> = self.summary.lines()
+            .map(|l| l.trim())
+            .filter(|l| !l.is_empty() && !locale::LOCALES.iter().any(|loc| l.strip_suffix(':') == Some(loc.key_points)))
+            .map(|l| locale::strip_main_points(l).map(|r| r.trim()).unwrap_or(l))
+            .collect();
+        format!("[{}-{}]\n{}", clock(self.start_ms), clock(self.end_ms), body.join("\n"))
+    }
+}
+
+// Map and intermediate reduce calls: short, and with the default prompt
+pub fn window_options() -> GenerationOptions {
+    GenerationOptions { max_tokens: PARTIAL_MAX_TOKENS, ..Default::default() }
+}
+
+fn clock(ms: i64) -> String {
+    let seconds = ms.max(0) / 1000;
+    if seconds >= 3600 {
+        format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+    } else {
+        format!("{:02}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+// Consecutive segments of `track` grouped into windows of at most `max_chars`
+// (a single longer segment gets a window of its own): (start_ms, end_ms, text)
+pub fn windows(segments: &[TranscriptSegment], track: TranscriptTrack, max_chars: usize) -> Vec<(i64, i64, String)> {
+    let mut windows: Vec<(i64, i64, String)> = Vec::new();
+    for segment in segments.iter().filter(|s| s.track == track) {
+        let text = segment.text.trim();
+        if text.is_empty() { continue; }
+        match windows.last_mut() {
+            Some((_, end, window)) if window.len() + 1 + text.len() <= max_chars => {
+                window.push(' ');
+                window.push_str(text);
+                *end = segment.end_ms;
+            }
+            _ => windows.push((segment.start_ms, segment.end_ms, text.to_string())),
+        }
+    }
+    windows
+}
+
+// Summarizer input covering every partial summary within `budget_chars`.
+// `summarize` shortens one group of rendered partials (intermediate levels only).
+pub fn reduce(partials: &[PartialSummary], budget_chars: usize, summarize: &mut dyn FnMut(&str) -> String) -> String {
+    let mut level: Vec<String> = partials.iter().map(|p| p.render()).collect();
+    for depth in 0..MAX_LEVELS {
+        let joined = level.join("\n\n");
+        if joined.len() <= budget_chars || level.len() <= 1 {
+            return joined;
+        }
+        let groups = group(&level, budget_chars);
+        println!("Map-Reduce: Level {} reduces {} summaries in {} groups.", depth + 1, level.len(), groups.len());
+        level = groups.iter().map(|g| summarize(g)).filter(|s| !s.trim().is_empty()).collect();
+    }
+    // Summaries that would not shrink: keep the first `budget_chars`
+    let mut joined = level.join("\n\n");
+    let mut cut = budget_chars.min(joined.len());
+    while !joined.is_char_boundary(cut) {
+        cut -= 1;
+    }
+    joined.truncate(cut);
+    joined
+}
+
+// Neighbours joined while they fit `budget_chars`; at least two per group so every level shrinks
+fn group(items: &[String], budget_chars: usize) -> Vec<String> {
+    let mut groups: Vec<(String, usize)> = Vec::new();
+    for item in items {
+        match groups.last_mut() {
+            Some((group, count)) if *count < 2 || group.len() + 2 + item.len() <= budget_chars => {
+                group.push_str("\n\n");
+                group.push_str(item);
+                *count += 1;
+            }
+            _ => groups.push((item.clone(), 1)),
+        }
+    }
+    groups.into_iter().map(|(group, _)| group).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(start_ms: i64, text: &str) -> TranscriptSegment {
+        TranscriptSegment {
+            track: TranscriptTrack::Original,
+            start_ms,
+            end_ms: start_ms + 1000,
+            text: text.to_string(),
+            avg_token_prob: 1.0,
+            min_token_prob: 1.0,
+            language: None,
+        }
+    }
+
+    #[test]
+    fn test_windows_follow_segment_boundaries() {
+        let segments = vec![segment(0, "Entropy rises."), segment(1000, "Heat flows."), segment(2000, "Engines lose work.")];
+        let windows = windows(&segments, TranscriptTrack::Original, 30);
+        assert_eq!(windows, vec![
+            (0, 2000, "Entropy rises. Heat flows.".to_string()),
+            (2000, 3000, "Engines lose work.".to_string()),
+        ]);
+        assert!(super::windows(&segments, TranscriptTrack::Translation, 30).is_empty());
+    }
+
+    #[test]
+    fn test_reduce_shrinks_until_the_session_fits() {
+        // 3 hours of 6-minute windows
+        let partials: Vec<PartialSummary> = (0..30)
+            .map(|i| PartialSummary {
+                start_ms: i * 360_000,
+                end_ms: (i + 1) * 360_000,
+                summary: format!("Key Takeaways:\n- Point {} of the lecture, with enough words to take up room.", i),
+            })
+            .collect();
+        assert!(partials[12].render().starts_with("[1:12:00-1:18:00]\n- Point 12"));
+
+        let mut calls = 0;
+        // Keeps the first bullet of each group
+        let reduced = reduce(&partials, 600, &mut |group| {
+            calls += 1;
+            group.lines().find(|l| l.starts_with("- ")).unwrap_or_default().to_string()
+        });
+        assert!(reduced.len() <= 600, "{} chars", reduced.len());
+        assert!(reduced.starts_with("- Point 0 "));
+        assert!(calls > 1);
+
+        // A short session goes through untouched
+        let short = reduce(&partials[..2], REDUCE_BUDGET_CHARS, &mut |_| unreachable!());
+        assert_eq!(short, format!("{}\n\n{}", partials[0].render(), partials[1].render()));
+    }
+}
@@ -0,0 +1,117 @@
+// Feature 8.2: Memory-Mapped Model Weights
+// Model files are mapped read-only instead of read into the heap. whisper.cpp, tract and ONNX
+// Runtime still build their own tensors from the bytes, but the file itself lives in the page
+// cache: no 75MB Vec during load, and an ASR -> LLM -> RAG swap re-reads from cache, not flash.
+// Files are only ever replaced by rename (see install.rs), never rewritten in place, so a live
+// mapping keeps pointing at the old, unchanged inode.
+
+use memmap2::Mmap;
+use std::fs::File;
+use std::io::Cursor;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+enum Backing {
+    Mapped(Mmap),
+    Heap(Vec<u8>), // Empty files, filesystems without mmap (some SD card FUSE mounts, wasm)
+}
+
+pub struct MappedModel {
+    path: PathBuf,
+    backing: Backing,
+}
+
+impl MappedModel {
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let file = File::open(path).map_err(|e| anyhow::anyhow!("Model file not found at {:?}: {}", path, e))?;
+        let len = file.metadata()?.len();
+
+        // SAFETY: read-only mapping of a file that is never modified in place (see header)
+        let mapped = if len > 0 { unsafe { Mmap::map(&file) }.ok() } else { None };
+        let backing = match mapped {
+            Some(map) => {
+                #[cfg(unix)]
+                let _ = map.advise(memmap2::Advice::Sequential); // Loaders read front to back
+                Backing::Mapped(map)
+            }
+            None => Backing::Heap(std::fs::read(path)?),
+        };
+        Ok(Self { path: path.to_path_buf(), backing })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn is_mapped(&self) -> bool {
+        matches!(self.backing, Backing::Mapped(_))
+    }
+
+    // For loaders that only take `Read` (tract)
+    pub fn reader(&self) -> Cursor<&[u8]> {
+        Cursor::new(&self[..])
+    }
+}
+
+impl Deref for MappedModel {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match &self.backing {
+            Backing::Mapped(map) => map,
+            Backing::Heap(bytes) => bytes,
+        }
+    }
+}
+
+// Resident set split into heap (anonymous) and file-backed pages, in KB.
+// Mapped weights show up under file_kb and can be dropped by the kernel under pressure.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MemoryUsage {
+    pub anon_kb: u64,
+    pub file_kb: u64,
+}
+
+impl MemoryUsage {
+    pub fn total_kb(&self) -> u64 {
+        self.anon_kb + self.file_kb
+    }
+}
+
+// Linux / Android only
+pub fn memory_usage() -> Option<MemoryUsage> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let field = |name: &str| {
+        status.lines()
+            .find(|l| l.starts_with(name))
+            .and_then(|l| l.split_whitespace().nth(1))
+            .and_then(|v| v.parse::<u64>().ok())
+    };
+    Some(MemoryUsage { anon_kb: field("RssAnon:")?, file_kb: field("RssFile:")? })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mapped_and_empty_files_read_back() {
+        let dir = std::env::temp_dir().join(format!("tactanotes_mmap_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let full = dir.join("weights.bin");
+        let empty = dir.join("empty.bin");
+        std::fs::write(&full, b"ggml weights").unwrap();
+        std::fs::write(&empty, b"").unwrap();
+
+        let model = MappedModel::open(&full).unwrap();
+        assert!(model.is_mapped());
+        assert_eq!(&model[..], b"ggml weights");
+
+        let model = MappedModel::open(&empty).unwrap();
+        assert!(!model.is_mapped());
+        assert!(model.is_empty());
+
+        assert!(MappedModel::open(&dir.join("missing.bin")).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,6 +1,22 @@
 pub mod asr;
+pub mod filter;
+pub mod punct;
 pub mod manager;
 pub mod llm;
+pub mod textrank;
+pub mod structured;
+pub mod mapreduce;
+pub mod prompt;
+pub mod locale;
+pub mod budget;
 pub mod text;
+pub mod transcript;
 pub mod lang;
 pub mod rag;
+pub mod qa;
+pub mod title;
+pub mod keyphrase;
+pub mod registry;
+pub mod install;
+pub mod mmap;
+pub mod worker;
@@ -0,0 +1,103 @@
+// Feature F2.3: Prompt templates
+// Courses want different summaries (case holdings for law, differentials for medicine, algorithms
+// and complexity for CS). A template is the user message sent to the generative summarizer, with
+// {{variables}}; templates live in the DB and are attached to folders.
+//   {{subject}}     recording subject
+//   {{language}}    summary language code ("en", "fr", ...): detected, or chosen by the user (F2.8)
+//   {{context}}     "Context from past notes" block from RAG, empty if none matched
+//   {{transcript}}  transcript (or reduced window summaries); filled in by the summarizer so it can
+//                   trim it to the model's context window
+
+pub const VARIABLES: &[&str] = &["subject", "language", "context", "transcript"];
+
+pub const DEFAULT_TEMPLATE: &str = "Summarize this lecture transcript for a student's notes. \
+Start with \"Key Takeaways:\" and list the main points as short bullets starting with \"- \". \
+Keep formulas. If the lecture has them, add the sections \"Action Items:\" (homework and deadlines), \
+\"Definitions:\" (\"term: meaning\"), \"Open Questions:\" and \"Dates:\" with the same bullets.\n\
+{{context}}\nTranscript:\n{{transcript}}";
+
+// Seeded on first start; users edit or add their own
+pub const BUILTIN_TEMPLATES: &[(&str, &str)] = &[
+    ("General", DEFAULT_TEMPLATE),
+    ("Law", "Summarize this {{subject}} lecture for a law student's notes. Start with \"Key Takeaways:\" \
+and short bullets starting with \"- \". For every case discussed add a \"Cases:\" bullet with the \
+name, the facts in one line, the issue, the holding and the reasoning. List statutes and doctrines \
+under \"Definitions:\" (\"term: meaning\") and readings under \"Action Items:\".\n\
+{{context}}\nTranscript:\n{{transcript}}"),
+    ("Medicine", "Summarize this {{subject}} lecture for a medical student's notes. Start with \"Key Takeaways:\" \
+and short bullets starting with \"- \". For each condition give the presentation, the differential \
+diagnosis, key investigations and first-line treatment. Put drug names, doses and red flags in \
+their own bullets and terms under \"Definitions:\" (\"term: meaning\").\n\
+{{context}}\nTranscript:\n{{transcript}}"),
+    ("Computer Science", "Summarize this {{subject}} lecture for a computer science student's notes. Start with \
+\"Key Takeaways:\" and short bullets starting with \"- \". For each algorithm or data structure give \
+the idea in one line, its time and space complexity, and when to use it. Keep definitions under \
+\"Definitions:\" (\"term: meaning\") and assignments under \"Action Items:\".\n\
+{{context}}\nTranscript:\n{{transcript}}"),
+];
+
+// Replaces the `{{name}}` placeholders listed in `vars` in one pass, so a value containing
+// "{{...}}" (e.g. a transcript) is never substituted again. Others are left as they are.
+pub fn render(template: &str, vars: &[(&str, &str)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find("{{") {
+        let Some(close) = rest[open..].find("}}") else { break };
+        let name = rest[open + 2..open + close].trim();
+        out.push_str(&rest[..open]);
+        match vars.iter().find(|(var, _)| *var == name) {
+            Some((_, value)) => out.push_str(value),
+            None => out.push_str(&rest[open..open + close + 2]),
+        }
+        rest = &rest[open + close + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+// Only known variables, and the transcript must be in there somewhere
+pub fn validate(template: &str) -> anyhow::Result<()> {
+    let mut rest = template;
+    let mut has_transcript = false;
+    while let Some(open) = rest.find("{{") {
+        let close = rest[open..].find("}}")
+            .ok_or_else(|| anyhow::anyhow!("Unclosed '{{{{' in template"))?;
+        let name = rest[open + 2..open + close].trim();
+        if !VARIABLES.contains(&name) {
+            return Err(anyhow::anyhow!("Unknown template variable '{{{{{}}}}}', expected one of {:?}", name, VARIABLES));
+        }
+        has_transcript |= name == "transcript";
+        rest = &rest[open + close + 2..];
+    }
+    if !has_transcript {
+        return Err(anyhow::anyhow!("Template must contain {{{{transcript}}}}"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_substitutes_once_and_keeps_unknown_placeholders() {
+        let template = "Subject: {{ subject }}\n{{context}}{{transcript}}";
+        let partial = render(template, &[("subject", "Torts"), ("context", "")]);
+        assert_eq!(partial, "Subject: Torts\n{{transcript}}");
+
+        // The transcript itself mentions a placeholder; it stays literal
+        let full = render(&partial, &[("transcript", "Say {{subject}} twice."), ("subject", "X")]);
+        assert_eq!(full, "Subject: Torts\nSay {{subject}} twice.");
+    }
+
+    #[test]
+    fn test_templates_are_validated() {
+        for (name, template) in BUILTIN_TEMPLATES {
+            assert!(validate(template).is_ok(), "{}", name);
+        }
+        assert!(validate("Summarize {{transcript}} in {{language}}").is_ok());
+        assert!(validate("Summarize the lecture").is_err());
+        assert!(validate("Summarize {{transcript}} for {{student}}").is_err());
+        assert!(validate("Summarize {{transcript").is_err());
+    }
+}
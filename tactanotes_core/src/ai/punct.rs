@@ -0,0 +1,388 @@
+// Feature F1.3: Punctuation & Truecasing Restoration
+// Whisper-tiny on 3-second chunks often returns lower-case runs without sentence punctuation.
+// LlmModel::summarize splits sentences on `.?!`, so an unpunctuated lecture becomes one giant
+// "sentence". Split in two:
+//   - punctuate: the model pass, on the ASR worker thread next to Whisper (ModelManager loads the
+//     model with ASR and drops it with ASR)
+//   - truecase: cheap casing fix-up on the engine side, once the transcript so far is known
+// Optional model (BERT-style token classification export):
+//   {models_dir}/punct/model.onnx   input_ids [1, L] i64, attention_mask [1, L] i64
+//                                   -> punct logits [1, L, P] (+ optional case logits [1, L, 2])
+//   {models_dir}/punct/vocab.txt    WordPiece vocab, one piece per line (line number = id)
+//   {models_dir}/punct/labels.txt   P punctuation labels, e.g. O / COMMA / PERIOD / QUESTION
+// Without it Whisper's own marks are kept, a segment followed by a pause closes its sentence,
+// and casing is fixed.
+
+use super::asr::AsrSegment;
+use super::mmap::MappedModel;
+use std::collections::HashMap;
+use std::path::Path;
+use tract_onnx::prelude::*;
+
+type OnnxPlan = TypedRunnableModel<TypedModel>;
+
+const MODEL_DIR: &str = "punct";
+// Keeps the WordPiece sequence well under BERT's 512 positions
+const MAX_WORDS_PER_PASS: usize = 96;
+const MAX_WORD_CHARS: usize = 100;
+// Rule-based pass: this much silence after a segment ends its sentence
+const PAUSE_MS: i64 = 700;
+// Shorter fragments are usually a sentence cut mid-way by the chunking; leave them open
+const MIN_SENTENCE_WORDS: usize = 4;
+
+const QUESTION_STARTERS: &[&str] = &[
+    "what", "why", "how", "who", "whom", "whose", "where", "when", "which",
+    "do", "does", "did",
+];
+
+struct PunctModel {
+    plan: OnnxPlan,
+    vocab: HashMap<String, i64>,
+    labels: Vec<String>,
+    cls: i64,
+    sep: i64,
+    unk: i64,
+}
+
+pub struct Punctuator {
+    model: Option<PunctModel>,
+}
+
+impl Punctuator {
+    pub fn rule_based() -> Self {
+        Self { model: None }
+    }
+
+    // Never fails: a missing or broken model just means the rule-based pass.
+    pub fn load(models_dir: &str) -> Self {
+        let dir = Path::new(models_dir).join(MODEL_DIR);
+        if !dir.join("model.onnx").exists() {
+            println!("Punctuator: No model at {:?}, using rule-based restoration.", dir);
+            return Self::rule_based();
+        }
+        match PunctModel::load(&dir) {
+            Ok(model) => {
+                println!("Punctuator: ONNX model loaded ({} labels).", model.labels.len());
+                Self { model: Some(model) }
+            }
+            Err(e) => {
+                println!("Punctuator: Failed to load model ({}), using rule-based restoration.", e);
+                Self::rule_based()
+            }
+        }
+    }
+
+    pub fn has_model(&self) -> bool {
+        self.model.is_some()
+    }
+
+    // Model pass only; without a model the text comes back as it is (whitespace tidied).
+    // A 3s chunk rarely ends where the sentence does, so no mark is guessed for it.
+    pub fn punctuate(&self, text: &str) -> String {
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if text.is_empty() { return text; }
+
+        if let Some(model) = &self.model {
+            match model.restore(&text) {
+                Ok(restored) => return restored,
+                Err(e) => println!("Punctuator: Inference failed ({:?}), keeping ASR punctuation.", e),
+            }
+        }
+        text
+    }
+
+    // The segments of one decode, whose audio ends at `end_ms`. Without a model, a segment followed
+    // by a pause (before the next one, or before the end of the audio) closes its sentence.
+    pub fn punctuate_segments(&self, segments: &mut [AsrSegment], end_ms: i64) {
+        let next_starts: Vec<i64> = segments.iter().skip(1).map(|s| s.start_ms).chain([end_ms]).collect();
+        for (segment, next_start) in segments.iter_mut().zip(next_starts) {
+            segment.text = self.punctuate(&segment.text);
+            if self.model.is_none() && next_start.saturating_sub(segment.end_ms) >= PAUSE_MS {
+                segment.text = close_sentence(&segment.text);
+            }
+        }
+    }
+
+    // `sentence_start`: the transcript so far is empty or ends a sentence (see `ends_sentence`).
+    pub fn restore(&self, text: &str, sentence_start: bool) -> String {
+        truecase(&self.punctuate(text), sentence_start)
+    }
+}
+
+impl Default for Punctuator {
+    fn default() -> Self {
+        Self::rule_based()
+    }
+}
+
+pub fn ends_sentence(context: &str) -> bool {
+    match context.trim_end().chars().last() {
+        None => true,
+        Some(c) => matches!(c, '.' | '?' | '!'),
+    }
+}
+
+// Rule: a segment of a few words without a closing mark is treated as a full sentence.
+fn close_sentence(text: &str) -> String {
+    let trimmed = text.trim_end();
+    match trimmed.chars().last() {
+        Some('.') | Some('?') | Some('!') | Some(',') | Some(';') | Some(':') | Some('-') | None => {
+            return trimmed.to_string();
+        }
+        _ => {}
+    }
+    let words: Vec<&str> = trimmed.split_whitespace().collect();
+    if words.len() < MIN_SENTENCE_WORDS {
+        return trimmed.to_string();
+    }
+    let question = QUESTION_STARTERS.contains(&bare_word(words[0]).to_lowercase().as_str());
+    format!("{}{}", trimmed, if question { "?" } else { "." })
+}
+
+// Capitalizes sentence starts and the pronoun "I". Mid-sentence casing is left alone:
+// Whisper usually gets proper nouns right, and the model handles the rest.
+pub fn truecase(text: &str, sentence_start: bool) -> String {
+    let mut capitalize_next = sentence_start;
+    let mut out = Vec::new();
+    for word in text.split_whitespace() {
+        let lower = bare_word(word).to_lowercase();
+        let fixed = if capitalize_next || matches!(lower.as_str(), "i" | "i'm" | "i've" | "i'll" | "i'd") {
+            capitalize(word)
+        } else {
+            word.to_string()
+        };
+        capitalize_next = ends_sentence(word);
+        out.push(fixed);
+    }
+    out.join(" ")
+}
+
+fn capitalize(word: &str) -> String {
+    let mut out = String::with_capacity(word.len());
+    let mut done = false;
+    for c in word.chars() {
+        if !done && c.is_alphabetic() {
+            out.extend(c.to_uppercase());
+            done = true;
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+// Strips surrounding punctuation but keeps inner apostrophes ("don't")
+fn bare_word(word: &str) -> &str {
+    word.trim_matches(|c: char| !c.is_alphanumeric() && c != '\'')
+        .trim_matches('\'')
+}
+
+fn label_mark(label: &str) -> &'static str {
+    match label.trim().to_uppercase().as_str() {
+        "COMMA" | "," => ",",
+        "PERIOD" | "." => ".",
+        "QUESTION" | "?" => "?",
+        "EXCLAMATION" | "!" => "!",
+        _ => "",
+    }
+}
+
+fn argmax(row: &[f32]) -> usize {
+    row.iter().enumerate()
+        .fold((0, f32::NEG_INFINITY), |acc, (i, &v)| if v > acc.1 { (i, v) } else { acc })
+        .0
+}
+
+// BERT basic tokenization: alphanumeric runs, every other character on its own
+fn basic_split(word: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut run = String::new();
+    for c in word.chars() {
+        if c.is_alphanumeric() {
+            run.push(c);
+        } else {
+            if !run.is_empty() { parts.push(std::mem::take(&mut run)); }
+            parts.push(c.to_string());
+        }
+    }
+    if !run.is_empty() { parts.push(run); }
+    parts
+}
+
+// Greedy longest-match WordPiece over one basic token
+fn wordpiece(vocab: &HashMap<String, i64>, token: &str, unk: i64) -> Vec<i64> {
+    let chars: Vec<char> = token.to_lowercase().chars().collect();
+    if chars.len() > MAX_WORD_CHARS { return vec![unk]; }
+
+    let mut ids = Vec::new();
+    let mut start = 0;
+    while start < chars.len() {
+        let mut end = chars.len();
+        let mut found = None;
+        while start < end {
+            let piece: String = chars[start..end].iter().collect();
+            let piece = if start > 0 { format!("##{}", piece) } else { piece };
+            if let Some(&id) = vocab.get(&piece) {
+                found = Some(id);
+                break;
+            }
+            end -= 1;
+        }
+        match found {
+            Some(id) => {
+                ids.push(id);
+                start = end;
+            }
+            None => return vec![unk],
+        }
+    }
+    ids
+}
+
+impl PunctModel {
+    fn load(dir: &Path) -> anyhow::Result<Self> {
+        let weights = MappedModel::open(&dir.join("model.onnx"))?;
+        let plan = tract_onnx::onnx()
+            .model_for_read(&mut weights.reader())?
+            .into_optimized()?
+            .into_runnable()?;
+
+        let vocab_text = std::fs::read_to_string(dir.join("vocab.txt"))
+            .map_err(|e| anyhow::anyhow!("vocab.txt: {}", e))?;
+        let vocab: HashMap<String, i64> = vocab_text.lines()
+            .enumerate()
+            .map(|(id, piece)| (piece.to_string(), id as i64))
+            .collect();
+
+        let labels: Vec<String> = std::fs::read_to_string(dir.join("labels.txt"))
+            .map_err(|e| anyhow::anyhow!("labels.txt: {}", e))?
+            .lines()
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty())
+            .collect();
+        if labels.is_empty() {
+            return Err(anyhow::anyhow!("labels.txt is empty"));
+        }
+
+        let special = |name: &str| vocab.get(name).copied()
+            .ok_or_else(|| anyhow::anyhow!("vocab.txt is missing {}", name));
+        let (cls, sep, unk) = (special("[CLS]")?, special("[SEP]")?, special("[UNK]")?);
+
+        Ok(Self { plan, vocab, labels, cls, sep, unk })
+    }
+
+    fn restore(&self, text: &str) -> anyhow::Result<String> {
+        // The model re-predicts the marks, so drop what Whisper put at word ends
+        let words: Vec<&str> = text.split_whitespace()
+            .map(|w| w.trim_end_matches([',', '.', '?', '!', ';', ':']))
+            .filter(|w| !w.is_empty())
+            .collect();
+
+        let mut out = Vec::with_capacity(words.len());
+        for window in words.chunks(MAX_WORDS_PER_PASS) {
+            out.extend(self.restore_window(window)?);
+        }
+        Ok(out.join(" "))
+    }
+
+    fn restore_window(&self, words: &[&str]) -> anyhow::Result<Vec<String>> {
+        // Remember each word's first and last piece: case is read from the first, punctuation from the last
+        let mut ids = vec![self.cls];
+        let mut spans = Vec::with_capacity(words.len());
+        for word in words {
+            let first = ids.len();
+            for token in basic_split(word) {
+                ids.extend(wordpiece(&self.vocab, &token, self.unk));
+            }
+            spans.push((first, ids.len() - 1));
+        }
+        ids.push(self.sep);
+
+        let mask = vec![1i64; ids.len()];
+        let input_ids = Tensor::from_shape(&[1, ids.len()], &ids)?;
+        let attention_mask = Tensor::from_shape(&[1, mask.len()], &mask)?;
+        let outputs = self.plan.run(tvec!(input_ids.into(), attention_mask.into()))?;
+
+        let punct: Vec<f32> = outputs[0].to_array_view::<f32>()?.iter().cloned().collect();
+        let n_labels = self.labels.len();
+        if punct.len() < ids.len() * n_labels {
+            return Err(anyhow::anyhow!("Unexpected logits size {}", punct.len()));
+        }
+        let mut case: Option<Vec<f32>> = None;
+        if let Some(t) = outputs.get(1) {
+            let logits: Vec<f32> = t.to_array_view::<f32>()?.iter().cloned().collect();
+            if logits.len() >= ids.len() * 2 { case = Some(logits); }
+        }
+
+        let mut restored = Vec::with_capacity(words.len());
+        for (word, &(first, last)) in words.iter().zip(spans.iter()) {
+            let label = argmax(&punct[last * n_labels..(last + 1) * n_labels]);
+            let upper = case.as_ref().map(|c| argmax(&c[first * 2..first * 2 + 2]) == 1).unwrap_or(false);
+            let word = if upper { capitalize(word) } else { word.to_string() };
+            restored.push(format!("{}{}", word, label_mark(&self.labels[label])));
+        }
+        Ok(restored)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rules_capitalize_without_closing() {
+        let p = Punctuator::rule_based();
+        // Chunks are cut every 3s, mid-sentence as often as not: no mark is added
+        assert_eq!(
+            p.restore("so the mitochondria is the powerhouse of the cell", true),
+            "So the mitochondria is the powerhouse of the cell"
+        );
+        assert_eq!(p.restore("why does the membrane need energy?", true), "Why does the membrane need energy?");
+        // Fragment mid-sentence: no capital
+        assert_eq!(p.restore("and then", false), "and then");
+        assert_eq!(p.restore("i think. this part matters", false), "I think. This part matters");
+        assert_eq!(p.punctuate("  the   cell "), "the cell");
+    }
+
+    #[test]
+    fn test_rules_close_sentences_at_pauses() {
+        let p = Punctuator::rule_based();
+        let segment = |text: &str, start_ms: i64, end_ms: i64| AsrSegment { text: text.to_string(), start_ms, end_ms, ..Default::default() };
+        let mut segments = vec![
+            segment("so the mitochondria is", 0, 2000), // Runs on: no pause before the next one
+            segment("the powerhouse of the cell", 2100, 3000),
+            segment("why does the membrane need energy", 4000, 6000),
+            segment("and then", 7000, 7500),              // Too short to be a sentence
+            segment("ATP is made in the matrix", 7600, 9200),
+        ];
+        p.punctuate_segments(&mut segments, 9500);
+        let texts: Vec<&str> = segments.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, vec![
+            "so the mitochondria is",
+            "the powerhouse of the cell.",
+            "why does the membrane need energy?",
+            "and then",
+            "ATP is made in the matrix",
+        ]);
+        // The end of the audio is a pause too
+        p.punctuate_segments(&mut segments[4..], 10_000);
+        assert_eq!(segments[4].text, "ATP is made in the matrix.");
+    }
+
+    #[test]
+    fn test_ends_sentence() {
+        assert!(ends_sentence(""));
+        assert!(ends_sentence("That is the key idea. "));
+        assert!(!ends_sentence("and the key idea "));
+    }
+
+    #[test]
+    fn test_wordpiece_longest_match() {
+        let vocab: HashMap<String, i64> = [("[UNK]", 0), ("photo", 1), ("##syn", 2), ("##thesis", 3), ("don", 4), ("'", 5), ("t", 6)]
+            .iter().map(|(p, id)| (p.to_string(), *id)).collect();
+        assert_eq!(wordpiece(&vocab, "Photosynthesis", 0), vec![1, 2, 3]);
+        assert_eq!(wordpiece(&vocab, "xyz", 0), vec![0]);
+        let ids: Vec<i64> = basic_split("don't").iter().flat_map(|t| wordpiece(&vocab, t, 0)).collect();
+        assert_eq!(ids, vec![4, 5, 6]);
+    }
+}
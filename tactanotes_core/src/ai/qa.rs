@@ -0,0 +1,202 @@
+// Feature F2.4: Ask your notes
+// Every note is split into passages: the summary text, and windows of its transcript (with their
+// timestamps). Passages are embedded once and kept in `note_chunks`; a question is embedded and
+// matched against all of them (or by shared words when no embedding model is installed). The best
+// passages are numbered and handed to the LLM, which answers citing them as [1], [2]...; without a
+// generative model the answer is the passages' sentences that best match the question.
+
+use super::mapreduce;
+use super::textrank::{content_terms, split_sentences};
+use super::transcript::{TranscriptSegment, TranscriptTrack};
+
+// Small enough that one passage is about one topic, and six fit the prompt easily
+pub const CHUNK_CHARS: usize = 800;
+pub const TOP_K: usize = 6;
+// Cosine similarity below this is noise for MiniLM
+pub const MIN_SCORE: f32 = 0.25;
+const EXTRACTIVE_SENTENCES: usize = 3;
+pub const NO_ANSWER: &str = "Nothing in your notes matches this question.";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chunk {
+    pub note_id: i64,
+    pub start_ms: Option<i64>, // Transcript window; None for the note text
+    pub end_ms: Option<i64>,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Citation {
+    pub note_id: i64,
+    pub title: String,
+    pub start_ms: Option<i64>,
+    pub end_ms: Option<i64>,
+    pub snippet: String,
+    pub score: f32,
+}
+
+// Note text in paragraphs of at most CHUNK_CHARS, then each transcript track in windows
+pub fn chunk_note(note_id: i64, content: &str, segments: &[TranscriptSegment]) -> Vec<Chunk> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    for line in content.lines().map(|l| l.trim()).filter(|l| !l.is_empty() && *l != "---") {
+        if !current.is_empty() && current.len() + 1 + line.len() > CHUNK_CHARS {
+            chunks.push(Chunk { note_id, start_ms: None, end_ms: None, text: std::mem::take(&mut current) });
+        }
+        if !current.is_empty() { current.push('\n'); }
+        current.push_str(line);
+    }
+    if !current.is_empty() {
+        chunks.push(Chunk { note_id, start_ms: None, end_ms: None, text: current });
+    }
+    for track in [TranscriptTrack::Original, TranscriptTrack::Translation] {
+        for (start_ms, end_ms, text) in mapreduce::windows(segments, track, CHUNK_CHARS) {
+            chunks.push(Chunk { note_id, start_ms: Some(start_ms), end_ms: Some(end_ms), text });
+        }
+    }
+    chunks
+}
+
+// Fallback retrieval without embeddings: share of the question's words found in the passage
+pub fn lexical_rank(question: &str, chunks: Vec<Chunk>, limit: usize) -> Vec<(Chunk, f32)> {
+    let wanted = unique_terms(question);
+    if wanted.is_empty() { return Vec::new(); }
+    let mut scored: Vec<(Chunk, f32)> = chunks.into_iter()
+        .map(|chunk| {
+            let found = unique_terms(&chunk.text);
+            let score = wanted.iter().filter(|t| found.contains(t)).count() as f32 / wanted.len() as f32;
+            (chunk, score)
+        })
+        .filter(|(_, score)| *score > 0.0)
+        .collect();
+    scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    scored.truncate(limit);
+    scored
+}
+
+fn unique_terms(text: &str) -> Vec<String> {
+    let mut terms = content_terms(text);
+    terms.sort();
+    terms.dedup();
+    terms
+}
+
+// Prompt template (ai/prompt.rs); the numbered passages go in as the transcript
+pub fn answer_prompt(question: &str) -> String {
+    // The question is user text: keep it from reading as a placeholder
+    let question = question.replace("{{", "{ {").replace("}}", "} }");
+    format!(
+        "Answer the question using only these passages from a student's lecture notes. Cite the \
+         passages you used by number, like [1]. If the passages do not answer it, say so in one \
+         sentence.\n\nQuestion: {}\n\nPassages:\n{{{{transcript}}}}\n\nAnswer:",
+        question,
+    )
+}
+
+// "[1] Thermodynamics (12:30): ..." for each passage, in rank order
+pub fn numbered_passages(hits: &[(Chunk, f32)], titles: &[String]) -> String {
+    hits.iter().enumerate()
+        .map(|(i, (chunk, _))| {
+            let title = titles.get(i).map(|t| t.as_str()).unwrap_or_default();
+            match chunk.start_ms {
+                Some(ms) => format!("[{}] {} ({}): {}", i + 1, title, clock(ms), chunk.text),
+                None => format!("[{}] {}: {}", i + 1, title, chunk.text),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn clock(ms: i64) -> String {
+    let seconds = ms.max(0) / 1000;
+    format!("{:02}:{:02}", seconds / 60, seconds % 60)
+}
+
+// The passages' sentences sharing the most words with the question, best first, each cited
+pub fn extractive_answer(question: &str, hits: &[(Chunk, f32)]) -> String {
+    let wanted = unique_terms(question);
+    let mut sentences: Vec<(usize, String, usize)> = Vec::new();
+    for (i, (chunk, _)) in hits.iter().enumerate() {
+        for sentence in split_sentences(&chunk.text) {
+            let found = unique_terms(&sentence);
+            let overlap = wanted.iter().filter(|t| found.contains(t)).count();
+            if overlap > 0 && !sentences.iter().any(|(_, s, _)| *s == sentence) {
+                sentences.push((overlap, sentence, i));
+            }
+        }
+    }
+    // Stable: ties keep passage rank order
+    sentences.sort_by_key(|(overlap, _, _)| std::cmp::Reverse(*overlap));
+    if sentences.is_empty() {
+        return NO_ANSWER.to_string();
+    }
+    sentences.into_iter()
+        .take(EXTRACTIVE_SENTENCES)
+        .map(|(_, sentence, i)| format!("{} [{}]", sentence.trim_start_matches(['-', '*', ' ']).trim(), i + 1))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Passages (0-based) the answer cites as [n], in order of first mention; all of them if it cites none
+pub fn cited(answer: &str, count: usize) -> Vec<usize> {
+    let mut cited = Vec::new();
+    let mut rest = answer;
+    while let Some(open) = rest.find('[') {
+        rest = &rest[open + 1..];
+        let Some(close) = rest.find(']') else { break };
+        for n in rest[..close].split(',').filter_map(|n| n.trim().parse::<usize>().ok()) {
+            if (1..=count).contains(&n) && !cited.contains(&(n - 1)) {
+                cited.push(n - 1);
+            }
+        }
+    }
+    if cited.is_empty() { (0..count).collect() } else { cited }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(start_ms: i64, text: &str) -> TranscriptSegment {
+        TranscriptSegment {
+            track: TranscriptTrack::Original,
+            start_ms,
+            end_ms: start_ms + 5000,
+            text: text.to_string(),
+            avg_token_prob: 1.0,
+            min_token_prob: 1.0,
+            language: None,
+        }
+    }
+
+    #[test]
+    fn test_notes_are_chunked_and_matched_without_embeddings() {
+        let segments = vec![segment(750_000, "The Carnot efficiency depends only on the reservoir temperatures.")];
+        let chunks = chunk_note(4, "Key Takeaways:\n- Entropy never decreases in an isolated system.\n---\n- Heat engines lose work.", &segments);
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].text, "Key Takeaways:\n- Entropy never decreases in an isolated system.\n- Heat engines lose work.");
+        assert_eq!((chunks[1].start_ms, chunks[1].end_ms), (Some(750_000), Some(755_000)));
+
+        let hits = lexical_rank("What does the Carnot efficiency depend on?", chunks, TOP_K);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].0.start_ms, Some(750_000));
+        let passages = numbered_passages(&hits, &["Thermo".to_string()]);
+        assert!(passages.starts_with("[1] Thermo (12:30): The Carnot"));
+        assert!(crate::ai::prompt::validate(&answer_prompt("Why {{transcript}}?")).is_ok());
+    }
+
+    #[test]
+    fn test_extractive_answers_cite_their_passages() {
+        let hits = vec![
+            (Chunk { note_id: 1, start_ms: None, end_ms: None, text: "Entropy measures disorder. It is measured in J/K.".to_string() }, 0.8),
+            (Chunk { note_id: 2, start_ms: Some(0), end_ms: Some(5000), text: "Entropy of an isolated system never decreases.".to_string() }, 0.6),
+        ];
+        let answer = extractive_answer("What is entropy a measure of?", &hits);
+        // "It is measured in J/K." shares no word with the question
+        assert_eq!(answer, "Entropy measures disorder. [1] Entropy of an isolated system never decreases. [2]");
+        assert_eq!(cited(&answer, 2), vec![0, 1]);
+        assert_eq!(cited("See [2] and [1, 2], not [7] or [x].", 2), vec![1, 0]);
+        assert_eq!(cited("No citations.", 2), vec![0, 1]);
+        assert_eq!(extractive_answer("Quantum chromodynamics?", &hits), NO_ANSWER);
+    }
+}
@@ -1,24 +1,69 @@
 use fastembed::{TextEmbedding, InitOptions, EmbeddingModel};
+use super::mmap::MappedModel;
+use ort::session::Session;
+use ort::value::Tensor;
+use std::path::Path;
 use std::sync::Arc;
+use tokenizers::{Tokenizer, TruncationParams};
 
 pub struct VectorStore {
-    model: Arc<TextEmbedding>,
+    model: Arc<Embedder>,
+}
+
+enum Embedder {
+    Hub(TextEmbedding),
+    Export(ExportedModel),
+}
+
+// F17: Registry-installed export, run on ONNX Runtime directly. fastembed only takes the model
+// as an owned Vec, which would put a full copy of model.onnx on the heap during load.
+struct ExportedModel {
+    session: Session,
+    tokenizer: Tokenizer,
+    token_type_ids: bool,
 }
 
 impl VectorStore {
     pub fn new() -> anyhow::Result<Self> {
         println!("Loading Embedding Model (MiniLM-L6-v2)...");
         let model = TextEmbedding::try_new(InitOptions::new(EmbeddingModel::AllMiniLML6V2).with_show_download_progress(true))?;
-        
+
+        Ok(Self {
+            model: Arc::new(Embedder::Hub(model)),
+        })
+    }
+
+    // F17: Registry-installed export (model.onnx + Hugging Face tokenizer files), read through
+    // mmap. The session is built straight from the mapped bytes; the mapping is dropped once
+    // ONNX Runtime has its own tensors, so the file pages can go back to the page cache.
+    pub fn from_dir(dir: &Path) -> anyhow::Result<Self> {
+        println!("Loading Embedding Model from {:?}...", dir);
+        let weights = MappedModel::open(&dir.join("model.onnx"))?;
+        let session = Session::builder()?.commit_from_memory(&weights)?;
+        drop(weights);
+
+        let tokenizer_file = MappedModel::open(&dir.join("tokenizer.json"))?;
+        let mut tokenizer = Tokenizer::from_bytes(&*tokenizer_file).map_err(anyhow::Error::msg)?;
+        let config: serde_json::Value = serde_json::from_slice(&MappedModel::open(&dir.join("tokenizer_config.json"))?)?;
+        let max_length = config["model_max_length"].as_f64().map_or(512, |len| (len as usize).min(512));
+        tokenizer
+            .with_truncation(Some(TruncationParams { max_length, ..Default::default() }))
+            .map_err(anyhow::Error::msg)?;
+
+        let token_type_ids = session.inputs.iter().any(|input| input.name == "token_type_ids");
         Ok(Self {
-            model: Arc::new(model),
+            model: Arc::new(Embedder::Export(ExportedModel { session, tokenizer, token_type_ids })),
         })
     }
 
     pub fn embed(&self, text: &str) -> anyhow::Result<Vec<f32>> {
+        let model = match self.model.as_ref() {
+            Embedder::Hub(model) => model,
+            Embedder::Export(export) => return export.embed(text),
+        };
         let documents = vec![text.to_string()];
-        let embeddings = self.model.embed(documents, None)?;
-        
+        let embeddings = model.embed(documents, None)?;
+
         // Return the first embedding (since we only requested one document)
         if let Some(embedding) = embeddings.first() {
             Ok(embedding.clone())
@@ -27,3 +72,54 @@ impl VectorStore {
         }
     }
 }
+
+impl ExportedModel {
+    // Single document, so no padding: every token is attended to
+    fn embed(&self, text: &str) -> anyhow::Result<Vec<f32>> {
+        let encoding = self.tokenizer.encode(text, true).map_err(anyhow::Error::msg)?;
+        let len = encoding.get_ids().len();
+        let ids: Vec<i64> = encoding.get_ids().iter().map(|&id| id as i64).collect();
+        let mask: Vec<i64> = encoding.get_attention_mask().iter().map(|&m| m as i64).collect();
+
+        let mut inputs = ort::inputs![
+            "input_ids" => Tensor::from_array(([1, len], ids))?,
+            "attention_mask" => Tensor::from_array(([1, len], mask))?,
+        ]?;
+        if self.token_type_ids {
+            let types: Vec<i64> = encoding.get_type_ids().iter().map(|&t| t as i64).collect();
+            inputs.push(("token_type_ids".into(), Tensor::from_array(([1, len], types))?.into()));
+        }
+        let outputs = self.session.run(inputs)?;
+
+        // Same output precedence as fastembed
+        let output = if outputs.len() == 1 {
+            &outputs[0]
+        } else {
+            outputs
+                .get("last_hidden_state")
+                .or_else(|| outputs.get("sentence_embedding"))
+                .ok_or_else(|| anyhow::anyhow!("Embedding model has no usable output"))?
+        };
+        let (shape, values) = output.try_extract_raw_tensor::<f32>()?;
+        let pooled = match shape {
+            // Token embeddings: mean pooling, as fastembed does for MiniLM
+            [1, tokens, hidden] if *tokens > 0 => {
+                let hidden = *hidden as usize;
+                let mut sum = vec![0.0f32; hidden];
+                for token in values.chunks(hidden) {
+                    sum.iter_mut().zip(token).for_each(|(s, v)| *s += v);
+                }
+                sum.iter().map(|s| s / *tokens as f32).collect::<Vec<f32>>()
+            }
+            // Already pooled by the export
+            [1, _] => values.to_vec(),
+            _ => return Err(anyhow::anyhow!("Unexpected embedding output shape {:?}", shape)),
+        };
+        Ok(normalize(&pooled))
+    }
+}
+
+fn normalize(vector: &[f32]) -> Vec<f32> {
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    vector.iter().map(|v| v / (norm + 1e-12)).collect()
+}
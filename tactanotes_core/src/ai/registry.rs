@@ -0,0 +1,347 @@
+// Feature F17: Model Registry
+// `{models_dir}/manifest.json` lists every model the app can use:
+//   { "models": [ { "id": "whisper-base-en", "kind": "asr", "backend": "whisper",
+//                   "file": "ggml-base.en.bin", "sha256": "…", "quantization": "f16",
+//                   "ram_mb": 210, "priority": 20 } ] }
+// Files are checked against their SHA-256 before loading, and ModelManager asks for the best
+// entry that fits the current memory budget. Without a manifest the old hard-coded names apply.
+// `file` may name a directory (multi-graph ONNX models); the hash then covers its files in
+// name order, concatenated.
+
+use ring::digest::{Context, SHA256};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+// Newest manifest layout this build understands
+pub const MANIFEST_FORMAT: u32 = 1;
+
+// Leave room for the app, Flutter and the OS
+const BUDGET_PERCENT_OF_AVAILABLE: u64 = 60;
+// Endurance mode: stick to the small models whatever RAM is free
+pub const ENDURANCE_BUDGET_MB: u64 = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModelKind {
+    Asr,
+    Llm,
+    Embed,
+    Vad,
+    Ocr,
+}
+
+impl ModelKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Asr => "asr",
+            Self::Llm => "llm",
+            Self::Embed => "embed",
+            Self::Vad => "vad",
+            Self::Ocr => "ocr",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelEntry {
+    pub id: String,
+    pub kind: ModelKind,
+    pub file: String, // Relative to models_dir
+    pub sha256: String,
+    #[serde(default)]
+    pub quantization: Option<String>,
+    pub ram_mb: u64, // Estimated resident size once loaded
+    #[serde(default)]
+    pub backend: Option<String>, // e.g. "whisper" / "onnx" for kind "asr"
+    #[serde(default)]
+    pub priority: i32, // Higher wins among entries that fit
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    #[serde(default)]
+    pub format: u32, // 0 (absent) reads as 1
+    #[serde(default)]
+    pub models: Vec<ModelEntry>,
+}
+
+impl Manifest {
+    // Entries from `other` replace same-id entries here
+    pub fn merge(&mut self, other: &Manifest) {
+        for entry in &other.models {
+            match self.models.iter_mut().find(|m| m.id == entry.id) {
+                Some(existing) => *existing = entry.clone(),
+                None => self.models.push(entry.clone()),
+            }
+        }
+        self.format = self.format.max(other.format);
+    }
+}
+
+pub struct ModelRegistry {
+    models_dir: PathBuf,
+    manifest: Manifest,
+    // Files already hashed, keyed by (path, expected hash) with the (len, mtime) seen at the time
+    verified: HashMap<(PathBuf, String), (u64, Option<SystemTime>)>,
+}
+
+impl ModelRegistry {
+    // A missing manifest is not an error: the registry is just empty.
+    pub fn load(models_dir: &str) -> anyhow::Result<Self> {
+        let path = Path::new(models_dir).join(MANIFEST_FILE);
+        let manifest = if path.exists() {
+            let content = std::fs::read_to_string(&path)?;
+            serde_json::from_str(&content)
+                .map_err(|e| anyhow::anyhow!("Invalid {}: {}", MANIFEST_FILE, e))?
+        } else {
+            Manifest::default()
+        };
+        if manifest.format > MANIFEST_FORMAT {
+            return Err(anyhow::anyhow!("{} format {} is newer than this app supports ({})",
+                MANIFEST_FILE, manifest.format, MANIFEST_FORMAT));
+        }
+        Ok(Self::from_manifest(models_dir, manifest))
+    }
+
+    pub fn from_manifest(models_dir: &str, manifest: Manifest) -> Self {
+        Self {
+            models_dir: PathBuf::from(models_dir),
+            manifest,
+            verified: HashMap::new(),
+        }
+    }
+
+    pub fn manifest(&self) -> &Manifest {
+        &self.manifest
+    }
+
+    pub fn entries(&self) -> &[ModelEntry] {
+        &self.manifest.models
+    }
+
+    pub fn get(&self, id: &str) -> Option<&ModelEntry> {
+        self.manifest.models.iter().find(|m| m.id == id)
+    }
+
+    pub fn has_kind(&self, kind: ModelKind, backend: Option<&str>) -> bool {
+        self.manifest.models.iter().any(|m| matches_filter(m, kind, backend))
+    }
+
+    pub fn path_of(&self, entry: &ModelEntry) -> PathBuf {
+        self.models_dir.join(&entry.file)
+    }
+
+    pub fn is_installed(&self, entry: &ModelEntry) -> bool {
+        self.path_of(entry).exists()
+    }
+
+    // Installed entries of `kind`, best first. Entries over `budget_mb` are left out; if none fit,
+    // the smallest one is returned alone so the feature still works (slowly) on tight devices.
+    pub fn candidates(&self, kind: ModelKind, backend: Option<&str>, budget_mb: Option<u64>) -> Vec<ModelEntry> {
+        let mut installed: Vec<&ModelEntry> = self.manifest.models.iter()
+            .filter(|m| matches_filter(m, kind, backend) && self.is_installed(m))
+            .collect();
+        installed.sort_by(|a, b| b.priority.cmp(&a.priority).then(b.ram_mb.cmp(&a.ram_mb)));
+
+        let fitting: Vec<ModelEntry> = installed.iter()
+            .filter(|m| budget_mb.map(|budget| m.ram_mb <= budget).unwrap_or(true))
+            .map(|m| (*m).clone())
+            .collect();
+        if !fitting.is_empty() {
+            return fitting;
+        }
+
+        match installed.iter().min_by_key(|m| m.ram_mb) {
+            Some(smallest) => {
+                println!("Registry: No {} model fits {:?}MB, falling back to {} ({}MB).",
+                    kind.as_str(), budget_mb, smallest.id, smallest.ram_mb);
+                vec![(*smallest).clone()]
+            }
+            None => Vec::new(),
+        }
+    }
+
+    pub fn verify(&mut self, entry: &ModelEntry) -> anyhow::Result<()> {
+        let path = self.path_of(entry);
+        if !path.exists() {
+            return Err(anyhow::anyhow!("Model '{}' is not installed (expected {:?})", entry.id, path));
+        }
+
+        let stamp = file_stamp(&path);
+        let key = (path, entry.sha256.trim().to_lowercase());
+        if self.verified.get(&key) == Some(&stamp) {
+            return Ok(());
+        }
+
+        check_sha256(entry, &key.0)?;
+        self.verified.insert(key, stamp);
+        Ok(())
+    }
+
+    // Manifest entry for a file name; None for unlisted files (pre-manifest installs)
+    pub fn entry_for_file(&self, file: &str) -> Option<ModelEntry> {
+        self.manifest.models.iter().find(|m| m.file == file).cloned()
+    }
+}
+
+// Hashes the file every time; `ModelRegistry::verify` remembers files it has already checked.
+// For threads that cannot hold the registry for as long as hashing takes.
+pub fn check_sha256(entry: &ModelEntry, path: &Path) -> anyhow::Result<()> {
+    let actual = sha256_of(path)?;
+    if !actual.eq_ignore_ascii_case(entry.sha256.trim()) {
+        return Err(anyhow::anyhow!(
+            "Model '{}' failed checksum: expected {}, found {}. Re-download it.",
+            entry.id, entry.sha256, actual
+        ));
+    }
+    Ok(())
+}
+
+// Write-then-rename so a crash never leaves a half-written manifest
+pub fn write_manifest(models_dir: &Path, manifest: &Manifest) -> anyhow::Result<()> {
+    let path = models_dir.join(MANIFEST_FILE);
+    let tmp = models_dir.join(format!("{}.part", MANIFEST_FILE));
+    std::fs::write(&tmp, serde_json::to_string_pretty(manifest)?)?;
+    std::fs::rename(&tmp, &path)?;
+    Ok(())
+}
+
+fn matches_filter(entry: &ModelEntry, kind: ModelKind, backend: Option<&str>) -> bool {
+    entry.kind == kind && match backend {
+        Some(backend) => entry.backend.as_deref().map(|b| b.eq_ignore_ascii_case(backend)).unwrap_or(false),
+        None => true,
+    }
+}
+
+fn file_stamp(path: &Path) -> (u64, Option<SystemTime>) {
+    match std::fs::metadata(path) {
+        Ok(meta) if meta.is_dir() => {
+            // Newest file inside decides
+            let newest = std::fs::read_dir(path).ok()
+                .map(|entries| entries.flatten().filter_map(|e| e.metadata().ok()?.modified().ok()).max())
+                .unwrap_or(None);
+            (0, newest)
+        }
+        Ok(meta) => (meta.len(), meta.modified().ok()),
+        Err(_) => (0, None),
+    }
+}
+
+pub fn sha256_of(path: &Path) -> anyhow::Result<String> {
+    let mut files = Vec::new();
+    if path.is_dir() {
+        for entry in std::fs::read_dir(path)?.flatten() {
+            if entry.path().is_file() {
+                files.push(entry.path());
+            }
+        }
+        files.sort();
+    } else {
+        files.push(path.to_path_buf());
+    }
+
+    let mut context = Context::new(&SHA256);
+    let mut buffer = vec![0u8; 1 << 16];
+    for file in files {
+        let mut reader = std::fs::File::open(&file)?;
+        loop {
+            let n = reader.read(&mut buffer)?;
+            if n == 0 { break; }
+            context.update(&buffer[..n]);
+        }
+    }
+    Ok(context.finish().as_ref().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+// MemAvailable from /proc/meminfo (Linux/Android); None elsewhere
+pub fn available_memory_mb() -> Option<u64> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    meminfo.lines()
+        .find(|l| l.starts_with("MemAvailable:"))
+        .and_then(|l| l.split_whitespace().nth(1))
+        .and_then(|kb| kb.parse::<u64>().ok())
+        .map(|kb| kb / 1024)
+}
+
+// RAM a single model may take right now. None = unknown, no limit.
+pub fn memory_budget_mb(low_power: bool) -> Option<u64> {
+    let budget = available_memory_mb().map(|mb| mb * BUDGET_PERCENT_OF_AVAILABLE / 100);
+    if low_power {
+        Some(budget.map(|b| b.min(ENDURANCE_BUDGET_MB)).unwrap_or(ENDURANCE_BUDGET_MB))
+    } else {
+        budget
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_models_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tactanotes_registry_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn entry(id: &str, file: &str, sha256: &str, ram_mb: u64) -> ModelEntry {
+        ModelEntry {
+            id: id.to_string(),
+            kind: ModelKind::Asr,
+            file: file.to_string(),
+            sha256: sha256.to_string(),
+            quantization: None,
+            ram_mb,
+            backend: Some("whisper".to_string()),
+            priority: 0,
+        }
+    }
+
+    #[test]
+    fn test_candidates_respect_budget() {
+        let dir = temp_models_dir("budget");
+        for file in ["tiny.bin", "base.bin", "small.bin"] {
+            std::fs::write(dir.join(file), file).unwrap();
+        }
+        let manifest: Manifest = serde_json::from_str(r#"{ "models": [
+            { "id": "tiny", "kind": "asr", "backend": "whisper", "file": "tiny.bin", "sha256": "", "ram_mb": 80 },
+            { "id": "base", "kind": "asr", "backend": "whisper", "file": "base.bin", "sha256": "", "ram_mb": 210 },
+            { "id": "small", "kind": "asr", "backend": "whisper", "file": "small.bin", "sha256": "", "ram_mb": 600 },
+            { "id": "missing", "kind": "asr", "backend": "whisper", "file": "none.bin", "sha256": "", "ram_mb": 100 },
+            { "id": "minilm", "kind": "embed", "file": "tiny.bin", "sha256": "", "ram_mb": 90 }
+        ] }"#).unwrap();
+        let registry = ModelRegistry::from_manifest(dir.to_str().unwrap(), manifest);
+
+        let ids = |budget| registry.candidates(ModelKind::Asr, Some("whisper"), budget)
+            .into_iter().map(|m| m.id).collect::<Vec<_>>();
+        assert_eq!(ids(None), vec!["small", "base", "tiny"]);
+        assert_eq!(ids(Some(300)), vec!["base", "tiny"]);
+        // Nothing fits: smallest installed model alone
+        assert_eq!(ids(Some(10)), vec!["tiny"]);
+        assert!(registry.candidates(ModelKind::Asr, Some("onnx"), None).is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_verify_checksum() {
+        let dir = temp_models_dir("verify");
+        std::fs::write(dir.join("model.bin"), b"abc").unwrap();
+        // SHA-256("abc")
+        let good = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        let mut registry = ModelRegistry::from_manifest(dir.to_str().unwrap(), Manifest {
+            format: MANIFEST_FORMAT,
+            models: vec![entry("good", "model.bin", good, 1), entry("bad", "model.bin", "00", 1)],
+        });
+
+        assert!(registry.verify(&registry.get("good").unwrap().clone()).is_ok());
+        let err = registry.verify(&registry.get("bad").unwrap().clone()).unwrap_err();
+        assert!(err.to_string().contains("failed checksum"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
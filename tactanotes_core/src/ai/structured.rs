@@ -0,0 +1,452 @@
+// Feature F2.1: Structured summaries
+// Splits a summary into sections the UI can show on their own (key points, homework, terms,
+// open questions, dates) and that the app can query across notes ("all my homework").
+// Sections the summarizer wrote are parsed from its text; sections it left out are mined from
+// the transcript with cue phrases. Stored as JSON next to the rendered text (notes.content).
+// F2.8: Headers are rendered in the summary's language and read back in any supported one.
+
+use super::locale::{self, Locale};
+use super::textrank::split_sentences;
+use serde::{Deserialize, Serialize};
+
+const MAX_SECTION_ITEMS: usize = 12;
+const MAX_TERM_WORDS: usize = 4;
+const MIN_DEFINITION_WORDS: usize = 3;
+const MIN_QUESTION_WORDS: usize = 4;
+
+const ACTION_CUES: &[&str] = &[
+    "homework", "assignment", "assignments", "due", "deadline", "submit", "hand in", "turn in",
+    "problem set", "problem sets", "worksheet", "essay", "lab report", "read chapter", "read pages",
+    "reading for", "exercises",
+];
+// "X is defined as Y": the term comes first
+const DEFINES_FORWARD: &[&str] = &[" is defined as ", " are defined as ", " refers to ", " refer to ", " stands for ", " means "];
+// "Y is called X": the term comes last
+const DEFINES_REVERSE: &[&str] = &[" is called ", " are called ", " is known as ", " are known as "];
+const NOT_A_TERM: &[&str] = &["this", "that", "it", "which", "what", "there", "he", "she", "they", "we", "you", "i"];
+const TERM_FILLERS: &[&str] = &["so", "and", "now", "okay", "ok", "well", "basically", "a", "an", "the", "term", "word"];
+// Questions to the room that nobody needs to look up later
+const RHETORICAL_ENDINGS: &[&str] = &["right", "okay", "ok", "yeah", "correct", "make sense", "any questions", "everyone", "everybody"];
+const OPEN_QUESTION_CUES: &[&str] = &["open question", "open problem", "unsolved", "nobody knows", "still unknown"];
+
+const MONTHS: &[&str] = &[
+    "january", "february", "march", "april", "may", "june", "july", "august", "september",
+    "october", "november", "december",
+];
+const MONTH_PREPOSITIONS: &[&str] = &["in", "of", "since", "until", "by", "early", "late", "mid"];
+const WEEKDAYS: &[&str] = &["monday", "tuesday", "wednesday", "thursday", "friday", "saturday", "sunday"];
+const RELATIVE_DAYS: &[&str] = &["today", "tonight", "tomorrow"];
+const NEXT_UNITS: &[&str] = &["week", "month", "class", "lecture", "session", "time"];
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StructuredSummary {
+    pub key_points: Vec<String>,
+    pub action_items: Vec<ActionItem>,
+    pub definitions: Vec<Definition>,
+    pub questions: Vec<String>,
+    pub dates: Vec<DateMention>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActionItem {
+    pub text: String,
+    pub due: Option<String>, // Date phrase as spoken ("next Friday", "March 3")
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Definition {
+    pub term: String,
+    pub definition: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DateMention {
+    pub date: String,
+    pub context: String, // Sentence the date came from
+}
+
+// A saved note's summary with the note it belongs to (folder = course)
+#[derive(Debug, Clone)]
+pub struct NoteSummary {
+    pub note_id: i64,
+    pub title: String,
+    pub folder_id: Option<i64>,
+    pub summary: StructuredSummary,
+}
+
+#[derive(Clone, Copy)]
+enum Section {
+    KeyPoints,
+    ActionItems,
+    Definitions,
+    Questions,
+    Dates,
+}
+
+impl StructuredSummary {
+    // `summary` is the summarizer output, `transcript` the text it summarized
+    pub fn build(summary: &str, transcript: &str) -> Self {
+        let mut structured = Self::parse(summary);
+        let mined = Self::mine(transcript);
+        if structured.action_items.is_empty() { structured.action_items = mined.action_items; }
+        if structured.definitions.is_empty() { structured.definitions = mined.definitions; }
+        if structured.questions.is_empty() { structured.questions = mined.questions; }
+        if structured.dates.is_empty() { structured.dates = mined.dates; }
+        structured
+    }
+
+    // Reads "Key Takeaways:" style text: a header line per section, one "- " bullet per item
+    pub fn parse(summary: &str) -> Self {
+        let mut structured = Self::default();
+        let mut section = Section::KeyPoints;
+        for line in summary.lines() {
+            let line = line.trim();
+            if line.is_empty() { continue; }
+            if let Some(rest) = locale::strip_main_points(line) {
+                structured.push(Section::KeyPoints, rest.trim());
+                continue;
+            }
+            if let Some(header) = parse_header(line) {
+                section = header;
+                continue;
+            }
+            structured.push(section, strip_bullet(line));
+        }
+        structured
+    }
+
+    // Classifies transcript sentences by cue phrases
+    pub fn mine(transcript: &str) -> Self {
+        let mut structured = Self::default();
+        for sentence in split_sentences(transcript) {
+            if sentence.contains("[BLANK_AUDIO]") { continue; }
+            let normalized = normalize(&sentence);
+            if contains_phrase(&normalized, ACTION_CUES) {
+                structured.push(Section::ActionItems, &sentence);
+            }
+            if let Some(definition) = find_definition(&sentence) {
+                structured.push_definition(definition);
+            }
+            if is_open_question(&sentence, &normalized) {
+                structured.push(Section::Questions, &sentence);
+            }
+            for date in find_dates(&sentence) {
+                structured.push_date(DateMention { date, context: sentence.clone() });
+            }
+        }
+        structured
+    }
+
+    // Appending a recording to a note keeps the sections of both
+    pub fn merge(&mut self, other: StructuredSummary) {
+        for point in other.key_points { self.push(Section::KeyPoints, &point); }
+        for item in other.action_items { self.push_action_item(item); }
+        for definition in other.definitions { self.push_definition(definition); }
+        for question in other.questions { self.push(Section::Questions, &question); }
+        for date in other.dates { self.push_date(date); }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.key_points.is_empty()
+            && self.action_items.is_empty()
+            && self.definitions.is_empty()
+            && self.questions.is_empty()
+            && self.dates.is_empty()
+    }
+
+    // Every item without the section headers, one per line (keyphrases and tags: the headers are
+    // in the summary language and not content)
+    pub fn items_text(&self) -> String {
+        let mut lines: Vec<String> = self.key_points.clone();
+        lines.extend(self.action_items.iter().map(|a| a.text.clone()));
+        lines.extend(self.definitions.iter().map(|d| format!("{}: {}", d.term, d.definition)));
+        lines.extend(self.questions.iter().cloned());
+        lines.extend(self.dates.iter().map(|d| d.context.clone()));
+        lines.join("\n")
+    }
+
+    // Note text with English headers; `parse` reads it back
+    pub fn render(&self) -> String {
+        self.render_in(locale::english())
+    }
+
+    pub fn render_in(&self, locale: &Locale) -> String {
+        let mut sections = Vec::new();
+        let mut add = |header: &str, items: Vec<String>| {
+            if !items.is_empty() {
+                sections.push(format!("{}:\n- {}", header, items.join("\n- ")));
+            }
+        };
+        add(locale.key_points, self.key_points.clone());
+        add(locale.action_items, self.action_items.iter().map(|a| a.text.clone()).collect());
+        add(locale.definitions, self.definitions.iter().map(|d| format!("{}: {}", d.term, d.definition)).collect());
+        add(locale.questions, self.questions.clone());
+        add(locale.dates, self.dates.iter().map(|d| format!("{}: {}", d.date, d.context)).collect());
+        sections.join("\n\n")
+    }
+
+    fn push(&mut self, section: Section, text: &str) {
+        let text = text.trim();
+        if text.is_empty() { return; }
+        match section {
+            Section::KeyPoints => push_unique(&mut self.key_points, text),
+            Section::Questions => push_unique(&mut self.questions, text),
+            Section::ActionItems => {
+                let due = find_dates(text).into_iter().find(|d| !is_year(d));
+                self.push_action_item(ActionItem { text: text.to_string(), due });
+            }
+            Section::Definitions => {
+                let split = text.split_once(": ").or_else(|| text.split_once(" - "));
+                let definition = match split {
+                    Some((term, definition)) => Some(Definition { term: term.trim().to_string(), definition: definition.trim().to_string() }),
+                    None => find_definition(text),
+                };
+                if let Some(definition) = definition {
+                    self.push_definition(definition);
+                }
+            }
+            Section::Dates => {
+                let (date, context) = match text.split_once(": ") {
+                    Some((date, context)) => (date.trim().to_string(), context.trim().to_string()),
+                    None => (find_dates(text).into_iter().next().unwrap_or_else(|| text.to_string()), text.to_string()),
+                };
+                self.push_date(DateMention { date, context });
+            }
+        }
+    }
+
+    fn push_action_item(&mut self, item: ActionItem) {
+        let key = normalize(&item.text);
+        if self.action_items.len() < MAX_SECTION_ITEMS && !self.action_items.iter().any(|a| normalize(&a.text) == key) {
+            self.action_items.push(item);
+        }
+    }
+
+    fn push_definition(&mut self, definition: Definition) {
+        let key = definition.term.to_lowercase();
+        if self.definitions.len() < MAX_SECTION_ITEMS && !self.definitions.iter().any(|d| d.term.to_lowercase() == key) {
+            self.definitions.push(definition);
+        }
+    }
+
+    fn push_date(&mut self, date: DateMention) {
+        let key = date.date.to_lowercase();
+        if self.dates.len() < MAX_SECTION_ITEMS && !self.dates.iter().any(|d| d.date.to_lowercase() == key) {
+            self.dates.push(date);
+        }
+    }
+}
+
+fn push_unique(items: &mut Vec<String>, text: &str) {
+    let key = normalize(text);
+    if items.len() < MAX_SECTION_ITEMS && !items.iter().any(|i| normalize(i) == key) {
+        items.push(text.to_string());
+    }
+}
+
+fn parse_header(line: &str) -> Option<Section> {
+    let name = line.strip_suffix([':', '：'])?.trim_start_matches('#').trim().to_lowercase();
+    let section = match name.as_str() {
+        "key takeaways" | "key points" | "main points" | "summary" => Section::KeyPoints,
+        "action items" | "homework" | "assignments" | "to do" | "todo" => Section::ActionItems,
+        "definitions" | "key terms" | "terms" | "glossary" => Section::Definitions,
+        "open questions" | "questions" => Section::Questions,
+        "dates" | "important dates" | "deadlines" => Section::Dates,
+        _ => {
+            let sections = [Section::KeyPoints, Section::ActionItems, Section::Definitions, Section::Questions, Section::Dates];
+            return locale::LOCALES.iter()
+                .find_map(|l| l.headers().iter().position(|h| h.to_lowercase() == name))
+                .map(|i| sections[i]);
+        }
+    };
+    Some(section)
+}
+
+// "- x", "* x", "• x" and "1. x" all become "x"
+fn strip_bullet(line: &str) -> &str {
+    let line = line.trim_start_matches(['-', '*', '•']).trim_start();
+    let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    if digits > 0 {
+        if let Some(rest) = line[digits..].strip_prefix(['.', ')']) {
+            return rest.trim_start();
+        }
+    }
+    line
+}
+
+// Lowercase words separated by single spaces
+fn normalize(text: &str) -> String {
+    text.to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn contains_phrase(normalized: &str, phrases: &[&str]) -> bool {
+    let padded = format!(" {} ", normalized);
+    phrases.iter().any(|p| padded.contains(&format!(" {} ", p)))
+}
+
+fn is_open_question(sentence: &str, normalized: &str) -> bool {
+    if contains_phrase(normalized, OPEN_QUESTION_CUES) {
+        return true;
+    }
+    sentence.ends_with('?')
+        && normalized.split_whitespace().count() >= MIN_QUESTION_WORDS
+        && !RHETORICAL_ENDINGS.iter().any(|e| normalized.ends_with(e))
+}
+
+fn find_definition(sentence: &str) -> Option<Definition> {
+    let body = sentence.trim().trim_end_matches(['.', '!', ';']);
+    // ASCII lowercasing keeps byte offsets, so positions carry over to `body`
+    let lower = body.to_ascii_lowercase();
+    for pattern in DEFINES_FORWARD {
+        if let Some(at) = lower.find(pattern) {
+            return make_definition(&body[..at], &body[at + pattern.len()..]);
+        }
+    }
+    for pattern in DEFINES_REVERSE {
+        if let Some(at) = lower.find(pattern) {
+            return make_definition(&body[at + pattern.len()..], &body[..at]);
+        }
+    }
+    None
+}
+
+fn make_definition(term: &str, definition: &str) -> Option<Definition> {
+    let mut words: Vec<&str> = term.split_whitespace().collect();
+    // Drop leading fillers and articles ("So the term entropy" -> "entropy")
+    while let Some(first) = words.first() {
+        if TERM_FILLERS.contains(&normalize(first).as_str()) {
+            words.remove(0);
+        } else {
+            break;
+        }
+    }
+    let term = words.join(" ").trim_matches(|c: char| !c.is_alphanumeric()).to_string();
+    let definition = definition.trim().trim_start_matches(',').trim().to_string();
+    let term_words = term.split_whitespace().count();
+    if term_words == 0 || term_words > MAX_TERM_WORDS || NOT_A_TERM.contains(&term.to_lowercase().as_str()) {
+        return None;
+    }
+    if definition.split_whitespace().count() < MIN_DEFINITION_WORDS {
+        return None;
+    }
+    Some(Definition { term, definition })
+}
+
+// Date phrases in the order they are spoken: "next Friday", "March 3", "3/14", "1789", "tomorrow"
+pub fn find_dates(sentence: &str) -> Vec<String> {
+    let words: Vec<&str> = sentence.split_whitespace()
+        .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric() && c != '/'))
+        .filter(|w| !w.is_empty())
+        .collect();
+    let lower: Vec<String> = words.iter().map(|w| w.to_lowercase()).collect();
+    let is_day = |i: usize| lower.get(i).and_then(|w| day_number(w)).is_some();
+
+    let mut dates = Vec::new();
+    let mut i = 0;
+    while i < words.len() {
+        let word = lower[i].as_str();
+        let dated = is_day(i + 1) || lower.get(i + 1).is_some_and(|w| is_year(w));
+        // A bare month only after a preposition, so "may" and "march" the verbs are skipped
+        let bare_month = i > 0 && MONTH_PREPOSITIONS.contains(&lower[i - 1].as_str());
+        let taken = if MONTHS.contains(&word) && (dated || bare_month) {
+            // "March 3" / "March 2024" / "in March"; "3rd of March" is caught at the number
+            if dated { 2 } else { 1 }
+        } else if is_day(i) && lower.get(i + 1).is_some_and(|w| w == "of") && lower.get(i + 2).is_some_and(|w| MONTHS.contains(&w.as_str())) {
+            3
+        } else if WEEKDAYS.contains(&word) || RELATIVE_DAYS.contains(&word) || is_numeric_date(word) || is_year(word) {
+            1
+        } else if matches!(word, "next" | "this") && lower.get(i + 1).is_some_and(|w| WEEKDAYS.contains(&w.as_str()) || NEXT_UNITS.contains(&w.as_str())) {
+            2
+        } else {
+            0
+        };
+        if taken > 0 {
+            dates.push(words[i..i + taken].join(" "));
+            i += taken;
+        } else {
+            i += 1;
+        }
+    }
+    dates
+}
+
+fn day_number(word: &str) -> Option<u32> {
+    let digits = word.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let suffix = &word[digits.len()..];
+    if !matches!(suffix, "" | "st" | "nd" | "rd" | "th") { return None; }
+    digits.parse::<u32>().ok().filter(|d| (1..=31).contains(d))
+}
+
+fn is_year(word: &str) -> bool {
+    word.len() == 4 && word.parse::<u32>().is_ok_and(|y| (1000..=2100).contains(&y))
+}
+
+// "3/14", "3/14/2025", "2025/03/14"
+fn is_numeric_date(word: &str) -> bool {
+    let parts: Vec<&str> = word.split('/').collect();
+    (2..=3).contains(&parts.len()) && parts.iter().all(|p| !p.is_empty() && p.len() <= 4 && p.chars().all(|c| c.is_ascii_digit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sections_are_mined_from_the_transcript() {
+        let transcript = "Entropy is defined as a measure of the disorder of a system. This means heat flows \
+            from hot to cold. The energy stored in a stretched spring is called elastic potential energy. \
+            Does that make sense, right? Why does time only run forwards? For homework, do problems one \
+            to five from chapter four, due next Friday. Carnot published his engine paper in 1824. \
+            The midterm is on March 3rd.";
+        let structured = StructuredSummary::mine(transcript);
+
+        assert_eq!(structured.action_items.len(), 1);
+        assert!(structured.action_items[0].text.starts_with("For homework"));
+        assert_eq!(structured.action_items[0].due.as_deref(), Some("next Friday"));
+
+        let terms: Vec<&str> = structured.definitions.iter().map(|d| d.term.as_str()).collect();
+        assert_eq!(terms, vec!["Entropy", "elastic potential energy"]);
+        assert_eq!(structured.definitions[0].definition, "a measure of the disorder of a system");
+
+        assert_eq!(structured.questions, vec!["Why does time only run forwards?".to_string()]);
+        let dates: Vec<&str> = structured.dates.iter().map(|d| d.date.as_str()).collect();
+        assert_eq!(dates, vec!["next Friday", "1824", "March 3rd"]);
+    }
+
+    #[test]
+    fn test_written_sections_are_parsed_and_rendered_back() {
+        let summary = "Key Takeaways:\n- Entropy never decreases in a closed system.\n\n\
+            Action Items:\n1. Read chapter 5 by Monday\n\nDefinitions:\n- Entropy: disorder of a system";
+        let transcript = "Nobody knows why the early universe had such low entropy. See you on Monday.";
+        let structured = StructuredSummary::build(summary, transcript);
+
+        assert_eq!(structured.key_points, vec!["Entropy never decreases in a closed system.".to_string()]);
+        assert_eq!(structured.action_items[0].due.as_deref(), Some("Monday"));
+        assert_eq!(structured.definitions[0].term, "Entropy");
+        // Not written by the summarizer, so taken from the transcript
+        assert_eq!(structured.questions.len(), 1);
+        assert_eq!(structured.dates[0].date, "Monday");
+
+        let rendered = structured.render();
+        assert!(rendered.starts_with("Key Takeaways:\n- Entropy never decreases"));
+        assert_eq!(StructuredSummary::parse(&rendered), structured);
+
+        let french = structured.render_in(locale::get_or_english("fr"));
+        assert!(french.starts_with("Points clés:\n- Entropy never decreases"));
+        assert!(french.contains("\n\nÀ faire:\n- Read chapter 5"));
+        assert_eq!(StructuredSummary::parse(&french), structured);
+        // Tags and titles see the items only, never the localized headers
+        let items = StructuredSummary::parse(&french).items_text();
+        assert!(items.starts_with("Entropy never decreases") && items.contains("\nEntropy: disorder of a system"));
+        assert!(!items.contains("Points clés") && !items.contains("À faire"));
+
+        let json = serde_json::to_string(&structured).unwrap();
+        assert_eq!(serde_json::from_str::<StructuredSummary>(&json).unwrap(), structured);
+    }
+}
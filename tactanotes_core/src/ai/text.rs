@@ -1,6 +1,7 @@
 // Feature F2: Real-time Summarization (Rolling Buffer)
 // Holds the last N tokens/characters to feed into the LLM.
 
+#[derive(Clone)]
 pub struct RollingBuffer {
     buffer: String,
     max_length: usize, // e.g., 8000 chars (~2048 tokens)
@@ -0,0 +1,345 @@
+// Feature F2.1: Extractive Summaries (LexRank + MMR)
+// Fallback when the GGUF model is missing, fails, or Endurance mode rules out generation.
+//   1. Each sentence becomes a TF-IDF vector; cosine similarity above MIN_EDGE_SIMILARITY is an
+//      edge of the sentence graph.
+//   2. PageRank over the weighted graph (continuous LexRank) scores how central a sentence is.
+//      Lecture cue words ("remember", "exam", ...) raise the score, low-confidence ASR lowers it (F1.4).
+//   3. Maximal Marginal Relevance picks central sentences that do not repeat earlier picks, until
+//      the target length; near-restatements (MAX_REDUNDANCY) are skipped outright.
+//      The picks are returned in lecture order.
+
+use std::collections::{HashMap, HashSet};
+
+const DAMPING: f32 = 0.85;
+const MAX_ITERATIONS: usize = 100;
+const CONVERGENCE: f32 = 1e-6;
+const MIN_EDGE_SIMILARITY: f32 = 0.08;
+// 1.0 = pure centrality, 0.0 = pure novelty
+const MMR_LAMBDA: f32 = 0.7;
+// Closer than this to a picked sentence counts as a restatement and is never picked
+const MAX_REDUNDANCY: f32 = 0.6;
+
+// Target: a fifth of the lecture, within these bounds
+const SUMMARY_RATIO: f32 = 0.2;
+const MIN_SENTENCES: usize = 3;
+const MAX_SENTENCES: usize = 12;
+
+const MIN_SENTENCE_CHARS: usize = 15;
+const MIN_SENTENCE_WORDS: usize = 4;
+const CUE_BOOST: f32 = 1.5;
+const UNSURE_PENALTY: f32 = 0.4;
+
+const CUE_WORDS: &[&str] = &[
+    "important", "remember", "note", "summary", "conclusion", "key", "concept", "idea",
+    "definition", "defined", "means", "exam", "homework", "assignment", "deadline",
+];
+
+const ABBREVIATIONS: &[&str] = &["e.g", "i.e", "etc", "vs", "dr", "mr", "mrs", "ms", "prof", "fig", "eq", "approx"];
+
+const STOPWORDS: &[&str] = &[
+    "a", "about", "after", "again", "all", "also", "am", "an", "and", "any", "are", "as", "at",
+    "be", "because", "been", "before", "being", "but", "by", "can", "could", "did", "do", "does",
+    "doing", "for", "from", "get", "got", "had", "has", "have", "he", "her", "here", "him", "his",
+    "how", "i", "if", "in", "into", "is", "it", "its", "just", "kind", "let", "like", "me", "more",
+    "most", "my", "no", "not", "now", "of", "ok", "okay", "on", "one", "only", "or", "other", "our",
+    "out", "over", "really", "right", "said", "say", "see", "she", "so", "some", "sort", "than",
+    "that", "the", "their", "them", "then", "there", "these", "they", "thing", "things", "this",
+    "those", "through", "to", "too", "uh", "um", "up", "very", "was", "way", "we", "well", "were",
+    "what", "when", "where", "which", "while", "who", "why", "will", "with", "would", "yeah",
+    "you", "your",
+];
+
+struct Sentence {
+    text: String,
+    vector: HashMap<String, f32>, // TF-IDF weights
+    norm: f32,
+    words: usize,
+    unsure: bool,
+    cue: bool,
+}
+
+// Picks the summary sentences of `text`, in their original order. Output stays within
+// `max_words` (at least one sentence is always returned if there is any).
+pub fn summarize(text: &str, low_confidence: &[String], max_words: usize) -> Vec<String> {
+    select(text, low_confidence, max_words, |count| {
+        ((count as f32 * SUMMARY_RATIO).round() as usize).clamp(MIN_SENTENCES, MAX_SENTENCES).min(count)
+    })
+}
+
+// F2.5: Shortens `text` to `max_words` by leaving out its least central sentences, with no cap
+// on the sentence count (for fitting a transcript into a prompt). Lecture order is kept.
+pub(crate) fn condense(text: &str, low_confidence: &[String], max_words: usize) -> Vec<String> {
+    select(text, low_confidence, max_words, |count| count)
+}
+
+// `target` maps the sentence count to how many to pick at most
+fn select(text: &str, low_confidence: &[String], max_words: usize, target: impl Fn(usize) -> usize) -> Vec<String> {
+    let sentences = build_sentences(text, low_confidence);
+    if sentences.is_empty() {
+        return Vec::new();
+    }
+
+    let similarity = similarity_matrix(&sentences);
+    let mut scores = lexrank(&similarity);
+    for (score, sentence) in scores.iter_mut().zip(&sentences) {
+        if sentence.cue { *score *= CUE_BOOST; }
+        if sentence.unsure { *score *= UNSURE_PENALTY; }
+    }
+
+    let mut picked = mmr_select(&scores, &similarity, &sentences, target(sentences.len()), max_words);
+    picked.sort_unstable();
+    picked.into_iter().map(|i| sentences[i].text.clone()).collect()
+}
+
+fn build_sentences(text: &str, low_confidence: &[String]) -> Vec<Sentence> {
+    let unreliable: Vec<String> = low_confidence.iter()
+        .map(|s| normalize(s))
+        .filter(|s| !s.is_empty())
+        .collect();
+
+    let mut seen = HashSet::new();
+    let mut kept: Vec<(String, Vec<String>, bool, bool)> = Vec::new();
+    for raw in split_sentences(text) {
+        if raw.len() < MIN_SENTENCE_CHARS || raw.contains("[BLANK_AUDIO]") { continue; }
+        let normalized = normalize(&raw);
+        if normalized.split_whitespace().count() < MIN_SENTENCE_WORDS { continue; }
+        if !seen.insert(normalized.clone()) { continue; } // Whisper repeats itself on loops
+
+        let unsure = unreliable.iter().any(|u| normalized.contains(u.as_str()) || u.contains(normalized.as_str()));
+        let cue = normalized.split_whitespace().any(|w| CUE_WORDS.contains(&w));
+        kept.push((raw, terms(&normalized), unsure, cue));
+    }
+
+    // Document frequency over this lecture only
+    let mut df: HashMap<&str, usize> = HashMap::new();
+    for (_, words, _, _) in &kept {
+        let unique: HashSet<&str> = words.iter().map(|w| w.as_str()).collect();
+        for word in unique {
+            *df.entry(word).or_insert(0) += 1;
+        }
+    }
+    let n = kept.len() as f32;
+
+    kept.iter()
+        .map(|(text, words, unsure, cue)| {
+            let mut vector: HashMap<String, f32> = HashMap::new();
+            for word in words {
+                *vector.entry(word.clone()).or_insert(0.0) += 1.0;
+            }
+            for (word, weight) in vector.iter_mut() {
+                let idf = ((1.0 + n) / (1.0 + df[word.as_str()] as f32)).ln() + 1.0;
+                *weight *= idf;
+            }
+            let norm = vector.values().map(|w| w * w).sum::<f32>().sqrt();
+            Sentence {
+                text: text.clone(),
+                vector,
+                norm,
+                words: text.split_whitespace().count(),
+                unsure: *unsure,
+                cue: *cue,
+            }
+        })
+        .collect()
+}
+
+// Splits after `.?!` followed by whitespace (so "3.14" stays whole) and on line breaks.
+// The closing mark stays with its sentence.
+pub(crate) fn split_sentences(text: &str) -> Vec<String> {
+    let mut sentences = Vec::new();
+    let mut current = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\n' {
+            push_sentence(&mut sentences, &mut current);
+            continue;
+        }
+        current.push(c);
+        let at_boundary = chars.peek().map(|next| next.is_whitespace()).unwrap_or(true);
+        if matches!(c, '.' | '?' | '!') && at_boundary && !ends_with_abbreviation(&current) {
+            push_sentence(&mut sentences, &mut current);
+        }
+    }
+    push_sentence(&mut sentences, &mut current);
+    sentences
+}
+
+fn push_sentence(sentences: &mut Vec<String>, current: &mut String) {
+    let sentence = current.trim().trim_start_matches(|c: char| c == '-' || c.is_whitespace()).trim();
+    if !sentence.is_empty() {
+        sentences.push(sentence.to_string());
+    }
+    current.clear();
+}
+
+fn ends_with_abbreviation(current: &str) -> bool {
+    let Some(stripped) = current.strip_suffix('.') else { return false };
+    let last = stripped.rsplit(|c: char| c.is_whitespace() || c == '(').next().unwrap_or("");
+    ABBREVIATIONS.contains(&last.to_lowercase().as_str())
+}
+
+fn normalize(text: &str) -> String {
+    text.to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c.is_whitespace() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// For keyphrases (F2.6); `word` in lower case
+pub(crate) fn is_stopword(word: &str) -> bool {
+    STOPWORDS.contains(&word)
+}
+
+// Content words of raw text (for matching questions against notes, F2.4)
+pub(crate) fn content_terms(text: &str) -> Vec<String> {
+    terms(&normalize(text))
+}
+
+// Content words, lightly stemmed so "forces" and "force" meet
+fn terms(normalized: &str) -> Vec<String> {
+    normalized.split_whitespace()
+        .filter(|w| w.chars().count() > 2 && !STOPWORDS.contains(w))
+        .map(stem)
+        .collect()
+}
+
+fn stem(word: &str) -> String {
+    if word.chars().count() <= 4 || !word.is_ascii() {
+        return word.to_string();
+    }
+    if let Some(base) = word.strip_suffix("ies") {
+        return format!("{}y", base);
+    }
+    for suffix in ["ing", "ed"] {
+        if let Some(base) = word.strip_suffix(suffix) {
+            if base.len() >= 4 { return base.to_string(); }
+        }
+    }
+    match word.strip_suffix('s') {
+        Some(base) if !base.ends_with('s') => base.to_string(),
+        _ => word.to_string(),
+    }
+}
+
+fn cosine(a: &Sentence, b: &Sentence) -> f32 {
+    if a.norm == 0.0 || b.norm == 0.0 { return 0.0; }
+    let (small, large) = if a.vector.len() <= b.vector.len() { (a, b) } else { (b, a) };
+    let dot: f32 = small.vector.iter()
+        .filter_map(|(word, w)| large.vector.get(word).map(|v| w * v))
+        .sum();
+    dot / (a.norm * b.norm)
+}
+
+fn similarity_matrix(sentences: &[Sentence]) -> Vec<Vec<f32>> {
+    let n = sentences.len();
+    let mut matrix = vec![vec![0.0; n]; n];
+    for i in 0..n {
+        for j in (i + 1)..n {
+            let similarity = cosine(&sentences[i], &sentences[j]);
+            matrix[i][j] = similarity;
+            matrix[j][i] = similarity;
+        }
+    }
+    matrix
+}
+
+// Power iteration over the thresholded, row-normalized similarity graph
+fn lexrank(similarity: &[Vec<f32>]) -> Vec<f32> {
+    let n = similarity.len();
+    let edges: Vec<Vec<f32>> = similarity.iter()
+        .map(|row| row.iter().map(|&s| if s >= MIN_EDGE_SIMILARITY { s } else { 0.0 }).collect())
+        .collect();
+    let row_sums: Vec<f32> = edges.iter().map(|row| row.iter().sum()).collect();
+
+    let uniform = 1.0 / n as f32;
+    let mut scores = vec![uniform; n];
+    for _ in 0..MAX_ITERATIONS {
+        // Isolated sentences spread their score evenly
+        let dangling: f32 = (0..n).filter(|&i| row_sums[i] == 0.0).map(|i| scores[i]).sum::<f32>() * uniform;
+        let next: Vec<f32> = (0..n)
+            .map(|j| {
+                let inbound: f32 = (0..n)
+                    .filter(|&i| row_sums[i] > 0.0)
+                    .map(|i| scores[i] * edges[i][j] / row_sums[i])
+                    .sum();
+                (1.0 - DAMPING) * uniform + DAMPING * (inbound + dangling)
+            })
+            .collect();
+        let delta: f32 = next.iter().zip(&scores).map(|(a, b)| (a - b).abs()).sum();
+        scores = next;
+        if delta < CONVERGENCE { break; }
+    }
+    scores
+}
+
+fn mmr_select(scores: &[f32], similarity: &[Vec<f32>], sentences: &[Sentence], target: usize, max_words: usize) -> Vec<usize> {
+    let max_score = scores.iter().cloned().fold(f32::MIN_POSITIVE, f32::max);
+    let mut picked: Vec<usize> = Vec::new();
+    let mut words = 0;
+    let mut remaining: Vec<usize> = (0..scores.len()).collect();
+
+    while picked.len() < target && !remaining.is_empty() {
+        let mmr = |i: usize| {
+            let redundancy = picked.iter().map(|&p| similarity[i][p]).fold(0.0, f32::max);
+            MMR_LAMBDA * scores[i] / max_score - (1.0 - MMR_LAMBDA) * redundancy
+        };
+        // Ties go to the earlier sentence
+        let Some((position, &best)) = remaining.iter()
+            .enumerate()
+            .max_by(|(_, &a), (_, &b)| mmr(a).total_cmp(&mmr(b)).then(b.cmp(&a)))
+        else { break };
+        remaining.remove(position);
+
+        if picked.iter().any(|&p| similarity[best][p] > MAX_REDUNDANCY) {
+            continue;
+        }
+        if !picked.is_empty() && words + sentences[best].words > max_words {
+            continue; // Too long for what is left; a shorter one may still fit
+        }
+        words += sentences[best].words;
+        picked.push(best);
+    }
+    picked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_keeps_decimals_and_abbreviations() {
+        let sentences = split_sentences("Pi is about 3.14 in most problems. Use units, e.g. meters! Why?\nNew line here");
+        assert_eq!(sentences, vec![
+            "Pi is about 3.14 in most problems.",
+            "Use units, e.g. meters!",
+            "Why?",
+            "New line here",
+        ]);
+    }
+
+    #[test]
+    fn test_mmr_skips_near_duplicates() {
+        let text = "Entropy measures the disorder of a closed system. \
+                    Entropy measures the disorder in any closed system. \
+                    The second law says entropy of a closed system never decreases. \
+                    Energy is conserved when a closed system does work. \
+                    Temperature is the average kinetic energy of the particles.";
+        let summary = summarize(text, &[], 200);
+        assert_eq!(summary.len(), MIN_SENTENCES);
+        let near_duplicates = summary.iter().filter(|s| s.starts_with("Entropy measures")).count();
+        assert_eq!(near_duplicates, 1, "{:?}", summary);
+    }
+
+    #[test]
+    fn test_word_budget_is_respected() {
+        let text = "Photosynthesis turns light energy into chemical energy in plants. \
+                    Chlorophyll absorbs mostly red and blue light for photosynthesis. \
+                    The light reactions happen in the thylakoid membranes of chloroplasts. \
+                    The Calvin cycle fixes carbon dioxide into sugar inside the stroma.";
+        let summary = summarize(text, &[], 12);
+        assert_eq!(summary.len(), 1);
+    }
+}
@@ -0,0 +1,140 @@
+// Feature F2.6: Note titles
+// A recorded note is titled "<subject>: <topic> (<date>)". The topic comes from the generative model
+// when one is loaded (a few words written from the summary), otherwise from the two best keyphrases
+// of the summary and transcript (keyphrase.rs). Notes saved before this feature carry
+// "Note <unix time>" and can be retitled.
+
+use super::keyphrase;
+use super::llm::GenerationOptions;
+use super::locale::Locale;
+use chrono::TimeZone;
+
+pub const MAX_TITLE_CHARS: usize = 60;
+const DEFAULT_PREFIX: &str = "Note ";
+const TITLE_MAX_TOKENS: usize = 24;
+const MAX_TOPIC_WORDS: usize = 8;
+const TOPIC_PHRASES: usize = 2;
+
+// The summary goes in as the transcript
+pub fn title_prompt() -> String {
+    "Write a title of at most six words for these lecture notes. Name the topic only, without \
+     the date or the word lecture. Write only the title.\n\nNotes:\n{{transcript}}\n\nTitle:"
+        .to_string()
+}
+
+pub fn title_options() -> GenerationOptions {
+    GenerationOptions {
+        max_tokens: TITLE_MAX_TOKENS,
+        stop: vec!["\n".to_string()],
+        prompt: Some(title_prompt()),
+        ..Default::default()
+    }
+}
+
+// The model's first line without quotes, "Title:" or a closing full stop; None if nothing usable
+pub fn clean_generated(output: &str) -> Option<String> {
+    let line = output.lines().map(|l| l.trim()).find(|l| !l.is_empty())?;
+    let quotes = ['"', '\'', '*', '#', '`'];
+    let line = line.trim_matches(quotes).trim();
+    let line = line.strip_prefix("Title:").or_else(|| line.strip_prefix("title:")).unwrap_or(line);
+    let line = line.trim().trim_matches(quotes).trim_end_matches('.').trim_matches(quotes).trim();
+    let words: Vec<&str> = line.split_whitespace().take(MAX_TOPIC_WORDS).collect();
+    if words.is_empty() { None } else { Some(words.join(" ")) }
+}
+
+// "Entropy and Carnot efficiency": the best keyphrases of `text` (in `lang`), joined in the title's
+// language; None if it has no content words
+pub fn topic(text: &str, lang: &str, locale: &Locale) -> Option<String> {
+    let mut picked: Vec<String> = keyphrase::extract(text, lang, TOPIC_PHRASES).into_iter().map(|k| k.phrase).collect();
+    let first = picked.first_mut()?;
+    if let Some(c) = first.chars().next() {
+        first.replace_range(..c.len_utf8(), &c.to_uppercase().to_string());
+    }
+    Some(picked.join(&format!(" {} ", locale.and)))
+}
+
+// "Thermodynamics: Entropy and heat engines (12 Mar 2026)", with the date written the locale's way.
+// The subject is left out when it is the catch-all "General" or the topic already names it.
+pub fn compose(subject: &str, topic: Option<&str>, timestamp: i64, locale: &Locale) -> String {
+    let date = chrono::Local.timestamp_opt(timestamp, 0).single()
+        .map(|t| t.format(locale.date_format).to_string())
+        .unwrap_or_default();
+    let subject = subject.trim();
+    let subject = if subject.is_empty() || subject.eq_ignore_ascii_case("general") { "" } else { subject };
+    let topic = topic.map(str::trim).filter(|t| !t.is_empty());
+
+    let budget = MAX_TITLE_CHARS.saturating_sub(date.chars().count() + 3);
+    let head = match topic {
+        Some(topic) if subject.is_empty() || topic.to_lowercase().contains(&subject.to_lowercase()) => shorten(topic, budget),
+        Some(topic) => {
+            let rest = budget.saturating_sub(subject.chars().count() + 2);
+            format!("{}: {}", subject, shorten(topic, rest))
+        }
+        None if subject.is_empty() => locale.lecture.to_string(),
+        None => subject.to_string(),
+    };
+    if date.is_empty() { head } else { format!("{} ({})", head, date) }
+}
+
+// Whole words while they fit `max_chars`
+fn shorten(text: &str, max_chars: usize) -> String {
+    let mut out = String::new();
+    for word in text.split_whitespace() {
+        let len = out.chars().count() + usize::from(!out.is_empty()) + word.chars().count();
+        if len > max_chars && !out.is_empty() { break; }
+        if !out.is_empty() { out.push(' '); }
+        out.push_str(word);
+    }
+    out.trim_end_matches([',', ';', ':', '-']).trim_end_matches(" and").to_string()
+}
+
+// Time in a "Note <unix time>" title, the only title notes had before F2.6
+pub fn default_title_time(title: &str) -> Option<i64> {
+    title.strip_prefix(DEFAULT_PREFIX)?.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::locale;
+
+    #[test]
+    fn test_topics_come_from_repeated_keyphrases() {
+        let transcript = "Okay so today we're going to talk about entropy. Entropy is a measure of disorder. \
+            The Carnot efficiency depends on the reservoir temperatures. Entropy of an isolated system never \
+            decreases, and the Carnot efficiency is the upper limit for heat engines.";
+        let english = locale::english();
+        assert_eq!(topic(transcript, "en", english).as_deref(), Some("Carnot efficiency and entropy"));
+        assert_eq!(topic("Um, so, yeah. Okay.", "en", english), None);
+
+        let french = "Aujourd'hui nous parlons de l'entropie. L'entropie d'un système isolé ne diminue jamais. \
+            Le rendement de Carnot est la limite des moteurs thermiques. Le rendement de Carnot dépend des températures.";
+        let topic = topic(french, "fr", locale::get("fr").unwrap()).unwrap();
+        assert!(topic.contains(" et ") && topic.contains("ntropie"), "{}", topic);
+
+        assert_eq!(clean_generated("\"Title: Entropy and Heat Engines.\"\nMore").as_deref(), Some("Entropy and Heat Engines"));
+        assert_eq!(clean_generated("  \n"), None);
+    }
+
+    #[test]
+    fn test_titles_combine_subject_topic_and_date() {
+        let noon = chrono::Local.with_ymd_and_hms(2026, 3, 12, 12, 0, 0).unwrap().timestamp();
+        let compose = |subject, topic, timestamp| compose(subject, topic, timestamp, locale::english());
+        assert_eq!(compose("Thermodynamics", Some("Entropy"), noon), "Thermodynamics: Entropy (12 Mar 2026)");
+        assert_eq!(compose("General", Some("Entropy"), noon), "Entropy (12 Mar 2026)");
+        assert_eq!(compose("Physics", Some("Physics of heat"), noon), "Physics of heat (12 Mar 2026)");
+        assert_eq!(compose("General", None, noon), "Lecture (12 Mar 2026)");
+
+        let long = compose("Thermodynamics", Some("A very long generated title that keeps going and going on"), noon);
+        assert!(long.chars().count() <= MAX_TITLE_CHARS, "{}", long);
+        assert!(long.starts_with("Thermodynamics: A very long") && long.ends_with("(12 Mar 2026)"));
+
+        // Neither the fallback nor the date stays English
+        assert_eq!(super::compose("General", None, noon, locale::get("fr").unwrap()), "Cours (12/03/2026)");
+        assert_eq!(super::compose("Physik", Some("Entropie"), noon, locale::get("de").unwrap()), "Physik: Entropie (12.03.2026)");
+        assert_eq!(super::compose("", None, noon, locale::get("ja").unwrap()), "講義 (2026年3月12日)");
+
+        assert_eq!(default_title_time("Note 1773316800"), Some(1773316800));
+        assert_eq!(default_title_time("Thermodynamics"), None);
+    }
+}
@@ -0,0 +1,67 @@
+// Feature F1.2: Parallel Transcript Tracks
+// In translate mode every chunk is decoded twice: once in the spoken language and once into
+// English. Both tracks are kept side by side so summaries and search can use either.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TranscriptTrack {
+    Original,    // Spoken language
+    Translation, // English (Whisper translate task)
+}
+
+impl TranscriptTrack {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Original => "original",
+            Self::Translation => "translation",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "original" | "source" => Some(Self::Original),
+            "translation" | "translated" | "english" => Some(Self::Translation),
+            _ => None,
+        }
+    }
+}
+
+// Session-relative segment as stored in `transcript_segments`
+#[derive(Debug, Clone)]
+pub struct TranscriptSegment {
+    pub track: TranscriptTrack,
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub text: String,
+    // F1.4: Token probabilities from the decoder (1.0 when the backend has none)
+    pub avg_token_prob: f32,
+    pub min_token_prob: f32,
+    // F17.2: Language of this segment's text (None: not detected, or saved before F17.2)
+    pub language: Option<String>,
+}
+
+// F17.2: Languages of a track's labelled segments with their share of its text, largest first
+pub fn language_shares(segments: &[TranscriptSegment], track: TranscriptTrack) -> Vec<(String, f32)> {
+    let mut chars: Vec<(String, usize)> = Vec::new();
+    for segment in segments.iter().filter(|s| s.track == track) {
+        let Some(language) = &segment.language else { continue };
+        let len = segment.text.chars().count();
+        match chars.iter_mut().find(|(code, _)| code == language) {
+            Some((_, total)) => *total += len,
+            None => chars.push((language.clone(), len)),
+        }
+    }
+    let total: usize = chars.iter().map(|(_, len)| len).sum();
+    let mut shares: Vec<(String, f32)> = chars.into_iter()
+        .map(|(code, len)| (code, len as f32 / total.max(1) as f32))
+        .collect();
+    shares.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    shares
+}
+
+pub fn join_track(segments: &[TranscriptSegment], track: TranscriptTrack) -> String {
+    segments.iter()
+        .filter(|s| s.track == track)
+        .map(|s| s.text.as_str())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
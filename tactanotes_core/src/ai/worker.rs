@@ -0,0 +1,262 @@
+use super::asr::{AsrSegment, DecodeOptions};
+use super::lang::LanguageDetector;
+use super::manager::ModelManager;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TrySendError};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+// Feature 8.2: Background ASR Worker
+// A Whisper pass takes hundreds of milliseconds. Running it inside Engine::tick blocked every
+// UI poll, because api::get_current_transcript holds the global ENGINE mutex while ticking.
+// The engine now only enqueues audio here and drains finished results.
+
+// F17.2: A chunk this surely English is its own translation
+const ENGLISH_CONFIDENCE: f32 = 0.8;
+
+pub struct AsrJob {
+    pub seq: u64,
+    pub session: u64,
+    pub audio: Vec<f32>,
+    pub offset_ms: i64, // Session time of the first sample
+    pub options: DecodeOptions,
+}
+
+pub struct AsrResult {
+    pub seq: u64,
+    pub session: u64,
+    pub segments: Vec<AsrSegment>,    // Session-relative timestamps
+    pub translation: Vec<AsrSegment>, // Only filled when options.translate is set
+    pub elapsed_ms: u128,
+}
+
+pub struct AsrWorker {
+    jobs: Option<SyncSender<AsrJob>>,
+    results: Receiver<AsrResult>,
+    handle: Option<JoinHandle<()>>,
+    next_seq: u64,
+    pending: usize, // Submitted but not yet collected
+    // Bumped by discard_pending(); results of older sessions are dropped, not handed to the engine
+    session: Arc<AtomicU64>,
+}
+
+impl AsrWorker {
+    // `queue_depth` bounds RAM: each queued job holds its own audio chunk.
+    pub fn spawn(models: Arc<Mutex<ModelManager>>, queue_depth: usize) -> Self {
+        let (job_tx, job_rx) = mpsc::sync_channel::<AsrJob>(queue_depth);
+        let (result_tx, result_rx) = mpsc::channel::<AsrResult>();
+        let session = Arc::new(AtomicU64::new(0));
+        let current_session = session.clone();
+
+        let handle = thread::Builder::new()
+            .name("tactanotes-asr".to_string())
+            .spawn(move || {
+                println!("AsrWorker: Inference thread started.");
+                let detector = LanguageDetector::new();
+                // Exits when the engine drops its sender
+                while let Ok(job) = job_rx.recv() {
+                    let started = Instant::now();
+                    let stale = job.session != current_session.load(Ordering::SeqCst);
+                    // Each pass takes the models lock on its own, so a summary, search or answer
+                    // waiting on it gets in between the decode, punctuation and translation
+                    let (segments, translation) = if stale {
+                        // Still answered so the engine's pending count adds up
+                        (Vec::new(), Vec::new())
+                    } else {
+                        // F1.2: Translate mode decodes the chunk a second time into English
+                        let original_options = DecodeOptions { translate: false, ..job.options.clone() };
+                        let mut segments = with_models(&models, |m| m.transcribe_segments(&job.audio, &original_options));
+                        // F1.3: Punctuation runs here too, never in Engine::tick
+                        let end_ms = (job.audio.len() as i64 * 1000) / 16000;
+                        with_models(&models, |m| m.punctuate_segments(&mut segments, end_ms));
+                        // F17.2: ...except the English stretches of a code-switched lecture, which
+                        // the translate task would only paraphrase
+                        let text = segments.iter().map(|s| s.text.as_str()).collect::<Vec<_>>().join(" ");
+                        let english = detector.rank(&text).first()
+                            .is_some_and(|s| s.code == "en" && s.confidence >= ENGLISH_CONFIDENCE);
+                        let translation = if !job.options.translate {
+                            Vec::new()
+                        } else if english {
+                            segments.clone()
+                        } else {
+                            let mut translation = with_models(&models, |m| m.transcribe_segments(&job.audio, &job.options));
+                            with_models(&models, |m| m.punctuate_segments(&mut translation, end_ms));
+                            translation
+                        };
+                        (segments, translation)
+                    };
+                    let result = AsrResult {
+                        seq: job.seq,
+                        session: job.session,
+                        segments: shift(segments, job.offset_ms),
+                        translation: shift(translation, job.offset_ms),
+                        elapsed_ms: started.elapsed().as_millis(),
+                    };
+                    if result_tx.send(result).is_err() {
+                        break;
+                    }
+                }
+                println!("AsrWorker: Inference thread stopped.");
+            })
+            .expect("Failed to spawn ASR worker thread");
+
+        Self {
+            jobs: Some(job_tx),
+            results: result_rx,
+            handle: Some(handle),
+            next_seq: 0,
+            pending: 0,
+            session,
+        }
+    }
+
+    // Never blocks. Hands the audio back if the queue is full so the caller can re-buffer it.
+    pub fn submit(&mut self, audio: Vec<f32>, offset_ms: i64, options: DecodeOptions) -> Result<u64, Vec<f32>> {
+        let Some(jobs) = &self.jobs else { return Err(audio) };
+        let seq = self.next_seq;
+        let session = self.session.load(Ordering::SeqCst);
+        match jobs.try_send(AsrJob { seq, session, audio, offset_ms, options }) {
+            Ok(()) => {
+                self.next_seq += 1;
+                self.pending += 1;
+                Ok(seq)
+            }
+            Err(TrySendError::Full(job)) | Err(TrySendError::Disconnected(job)) => Err(job.audio),
+        }
+    }
+
+    // Never blocks. Results arrive in submission order (single inference thread).
+    pub fn poll(&mut self) -> Vec<AsrResult> {
+        let mut finished = Vec::new();
+        while let Ok(result) = self.results.try_recv() {
+            self.pending -= 1;
+            finished.push(result);
+        }
+        self.keep_current(finished)
+    }
+
+    // Blocks until every submitted job is back (or the timeout passes).
+    // Used before swapping ASR out so the last seconds of speech are not lost.
+    pub fn drain(&mut self, timeout: Duration) -> Vec<AsrResult> {
+        let deadline = Instant::now() + timeout;
+        let mut finished = Vec::new();
+        while self.pending > 0 {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.results.recv_timeout(remaining) {
+                Ok(result) => {
+                    self.pending -= 1;
+                    finished.push(result);
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    println!("AsrWorker: Drain timed out with {} jobs pending.", self.pending);
+                    break;
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.pending = 0;
+                    break;
+                }
+            }
+        }
+        self.keep_current(finished)
+    }
+
+    // Whatever is still queued or decoding belongs to a session that has ended: the worker skips
+    // those jobs and poll/drain drop their results, so they never leak into the next session
+    pub fn discard_pending(&mut self) {
+        if self.pending > 0 {
+            println!("AsrWorker: Discarding {} jobs of the previous session.", self.pending);
+        }
+        self.session.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn pending(&self) -> usize {
+        self.pending
+    }
+
+    fn keep_current(&self, mut results: Vec<AsrResult>) -> Vec<AsrResult> {
+        let session = self.session.load(Ordering::SeqCst);
+        results.retain(|r| r.session == session);
+        results
+    }
+}
+
+// Poisoned: a previous pass panicked, so this one is skipped
+fn with_models<T: Default>(models: &Mutex<ModelManager>, pass: impl FnOnce(&ModelManager) -> T) -> T {
+    match models.lock() {
+        Ok(models) => pass(&models),
+        Err(_) => T::default(),
+    }
+}
+
+fn shift(mut segments: Vec<AsrSegment>, offset_ms: i64) -> Vec<AsrSegment> {
+    for segment in segments.iter_mut() {
+        segment.start_ms += offset_ms;
+        segment.end_ms += offset_ms;
+    }
+    segments
+}
+
+impl Drop for AsrWorker {
+    fn drop(&mut self) {
+        // Closing the queue ends the thread's recv loop
+        self.jobs = None;
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queue_is_bounded_and_drains_in_order() {
+        let models = Arc::new(Mutex::new(ModelManager::new("./models")));
+        let mut worker = AsrWorker::spawn(models.clone(), 2);
+
+        // Hold the models so the worker blocks on its first job
+        let guard = models.lock().unwrap();
+        let mut accepted = 0;
+        let mut rejected = 0;
+        for _ in 0..5 {
+            match worker.submit(vec![0.0; 160], 0, DecodeOptions::default()) {
+                Ok(_) => accepted += 1,
+                Err(audio) => {
+                    assert_eq!(audio.len(), 160);
+                    rejected += 1;
+                }
+            }
+        }
+        assert!(accepted <= 3, "queue depth 2 + one in-flight job");
+        assert!(rejected >= 2);
+        drop(guard);
+
+        let results = worker.drain(Duration::from_secs(5));
+        assert_eq!(results.len(), accepted);
+        assert_eq!(worker.pending(), 0);
+        for (i, result) in results.iter().enumerate() {
+            assert_eq!(result.seq, i as u64);
+        }
+    }
+
+    #[test]
+    fn test_discarded_jobs_do_not_reach_the_next_session() {
+        let models = Arc::new(Mutex::new(ModelManager::new("./models")));
+        let mut worker = AsrWorker::spawn(models.clone(), 2);
+
+        let guard = models.lock().unwrap();
+        let stale = worker.submit(vec![0.0; 160], 0, DecodeOptions::default()).unwrap();
+        // The drain timed out with the job still queued
+        assert!(worker.drain(Duration::from_millis(10)).is_empty());
+        worker.discard_pending();
+        drop(guard);
+
+        let fresh = worker.submit(vec![0.0; 160], 0, DecodeOptions::default()).unwrap();
+        let results = worker.drain(Duration::from_secs(5));
+        assert_eq!(worker.pending(), 0);
+        assert_eq!(results.iter().map(|r| r.seq).collect::<Vec<_>>(), vec![fresh]);
+        assert_ne!(stale, fresh);
+    }
+}
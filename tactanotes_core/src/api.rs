@@ -1,6 +1,11 @@
 use std::sync::Mutex;
 // use lazy_static::lazy_static; // Ensure this is in Cargo.toml or use std::sync::OnceLock if rust 1.70+
 use crate::engine::Engine;
+use crate::engine::jobs;
+use crate::ai::transcript::{TranscriptSegment, TranscriptTrack};
+use crate::ai::lang::LanguageDetector;
+use crate::ai::locale;
+use crate::frb_generated::StreamSink;
 
 // Global Engine Instance
 // In a real production app, consider using `RustOpaque` or a proper dependency injection
@@ -21,6 +26,32 @@ where
     }
 }
 
+// F18: Waits for a job started under the engine lock without holding it, then lets the engine take
+// the model slot back
+fn wait_for_job(job_id: i64) -> anyhow::Result<jobs::JobInfo> {
+    loop {
+        let job = jobs::wait(job_id, std::time::Duration::from_secs(1))
+            .ok_or_else(|| anyhow::anyhow!("Unknown job {}", job_id))?;
+        if job.status.is_finished() {
+            get_engine(|engine| {
+                engine.collect_model_job();
+                Ok(())
+            })?;
+            return Ok(job);
+        }
+    }
+}
+
+// The finished job's result, or why it has none
+fn job_result(job_id: i64) -> anyhow::Result<Option<String>> {
+    let job = wait_for_job(job_id)?;
+    match job.status {
+        jobs::JobStatus::Failed(error) => Err(anyhow::anyhow!(error)),
+        jobs::JobStatus::Cancelled => Err(jobs::Cancelled.into()),
+        _ => Ok(job.result),
+    }
+}
+
 pub fn init_app(db_path: String, models_dir: String) -> anyhow::Result<()> {
     let mut lock = ENGINE.lock().unwrap();
     if lock.is_none() {
@@ -39,6 +70,15 @@ pub fn init_app(db_path: String, models_dir: String) -> anyhow::Result<()> {
             }
         }
 
+        // F2.3: Built-in summary templates (General, Law, Medicine, Computer Science)
+        if let Ok(templates) = engine.get_prompt_templates() {
+            if templates.is_empty() {
+                for (name, body) in crate::ai::prompt::BUILTIN_TEMPLATES {
+                    let _ = engine.create_prompt_template(name, body);
+                }
+            }
+        }
+
         *lock = Some(engine);
         println!("Rust: App Initialized Successfully.");
     }
@@ -53,8 +93,354 @@ pub fn start_recording(subject: String) -> anyhow::Result<()> {
 }
 
 pub fn stop_recording(append_to: Option<i64>) -> anyhow::Result<String> {
+    let (job_id, summary) = get_engine(|engine| {
+        engine.start_summary(append_to, Box::new(|_| true))
+    })?;
+    summarized(job_id, summary)
+}
+
+// F18: The summary is written on a job thread; the engine stays free for ticks and job queries
+fn summarized(job_id: i64, summary: std::sync::mpsc::Receiver<String>) -> anyhow::Result<String> {
+    let job = wait_for_job(job_id)?;
+    summary.recv().map_err(|_| match job.status {
+        jobs::JobStatus::Failed(error) => anyhow::anyhow!(error),
+        _ => anyhow::anyhow!("The summary was not written"),
+    })
+}
+
+// F2: Same as stop_recording, sending summary text to the UI while it is generated.
+// The returned summary is what gets saved; if the model fails part-way the extractive
+// fallback is streamed after the partial text.
+pub fn stop_recording_streaming(append_to: Option<i64>, sink: StreamSink<String>) -> anyhow::Result<String> {
+    let (job_id, summary) = get_engine(|engine| {
+        engine.start_summary(append_to, Box::new(move |text| sink.add(text.to_string()).is_ok()))
+    })?;
+    summarized(job_id, summary)
+}
+
+// F2.4: A passage an answer is based on; start_ms/end_ms locate it in the recording (None = note text)
+pub struct CitationInfo {
+    pub note_id: i64,
+    pub note_title: String,
+    pub start_ms: Option<i64>,
+    pub end_ms: Option<i64>,
+    pub snippet: String,
+    pub score: f32,
+}
+
+pub struct AnswerInfo {
+    pub answer: String,
+    pub citations: Vec<CitationInfo>,
+}
+
+// F2.4: Ask a question over every note (or one folder). The answer text is streamed to `sink` while
+// it is written; the result repeats it with the cited passages.
+pub fn ask_notes(question: String, folder_id: Option<i64>, sink: StreamSink<String>) -> anyhow::Result<AnswerInfo> {
+    let (job_id, answer) = get_engine(|engine| {
+        engine.start_ask(&question, folder_id, Box::new(move |text| sink.add(text.to_string()).is_ok()))
+    })?;
+    job_result(job_id)?;
+    let (answer, citations) = answer.recv()?;
+    Ok(AnswerInfo {
+        answer,
+        citations: citations.into_iter()
+            .map(|c| CitationInfo { note_id: c.note_id, note_title: c.title, start_ms: c.start_ms, end_ms: c.end_ms, snippet: c.snippet, score: c.score })
+            .collect(),
+    })
+}
+
+// F2.5: How the last generated summary or answer was fitted into the model's context window.
+// `dropped` lists what was left out ("2 of 3 past-note excerpts", "transcript condensed ...").
+pub struct PromptBudgetInfo {
+    pub context_window: u32,
+    pub output_tokens: u32,
+    pub prompt_tokens: u32,
+    pub transcript_tokens: u32,
+    pub context_tokens: u32,
+    pub dropped: Vec<String>,
+}
+
+pub fn get_last_prompt_budget() -> anyhow::Result<Option<PromptBudgetInfo>> {
+    get_engine(|engine| {
+        Ok(engine.last_prompt_budget().map(|b| PromptBudgetInfo {
+            context_window: b.context_window as u32,
+            output_tokens: b.output_tokens as u32,
+            prompt_tokens: b.prompt_tokens as u32,
+            transcript_tokens: b.transcript_tokens as u32,
+            context_tokens: b.context_tokens as u32,
+            dropped: b.dropped,
+        }))
+    })
+}
+
+// F2: Longest generated summary in tokens, and extra strings that end it early
+pub fn set_summary_options(max_tokens: u32, stop_sequences: Vec<String>) -> anyhow::Result<()> {
     get_engine(|engine| {
-        Ok(engine.stop_recording_and_summarize(append_to))
+        engine.set_generation_options(max_tokens as usize, stop_sequences);
+        Ok(())
+    })
+}
+
+// F17: A detected language with its share of the probability (0..1)
+pub struct LanguageGuess {
+    pub code: String,
+    pub confidence: f32,
+}
+
+// F17: Most likely languages of `text` first, at most `limit`; empty if it has no letters
+pub fn detect_language(text: String, limit: u32) -> Vec<LanguageGuess> {
+    LanguageDetector::new().rank(&text)
+        .into_iter()
+        .take(limit as usize)
+        .map(|s| LanguageGuess { code: s.code.to_string(), confidence: s.confidence })
+        .collect()
+}
+
+// F2.8: A language summaries can be written in
+pub struct SummaryLanguage {
+    pub code: String,
+    pub name: String,
+    pub native_name: String,
+}
+
+pub fn get_summary_languages() -> Vec<SummaryLanguage> {
+    locale::LOCALES.iter()
+        .map(|l| SummaryLanguage { code: l.code.to_string(), name: l.name.to_string(), native_name: l.native.to_string() })
+        .collect()
+}
+
+// F2.8: Summaries in this language ("fr", ...) whatever the lecture's; None follows the lecture
+pub fn set_summary_language(code: Option<String>) -> anyhow::Result<()> {
+    get_engine(|engine| {
+        engine.set_summary_language(code)
+    })
+}
+
+pub fn get_summary_language() -> anyhow::Result<Option<String>> {
+    get_engine(|engine| {
+        Ok(engine.get_summary_language())
+    })
+}
+
+// F1: Pick the ASR engine ("whisper", "onnx" or "mock")
+pub fn set_asr_backend(name: String) -> anyhow::Result<()> {
+    get_engine(|engine| {
+        engine.set_asr_backend(&name)
+    })
+}
+
+fn parse_track(track: &str) -> anyhow::Result<TranscriptTrack> {
+    TranscriptTrack::parse(track).ok_or_else(|| anyhow::anyhow!("Unknown transcript track '{}'", track))
+}
+
+// F1.2: Translate the lecture into English alongside the original transcript
+// Swaps in the multilingual Whisper model (ggml-tiny.bin); fails if it is not installed.
+pub fn set_translation_mode(enabled: bool) -> anyhow::Result<()> {
+    get_engine(|engine| {
+        engine.set_translate_mode(enabled)
+    })
+}
+
+// F1.2: "original" or "translation"
+pub fn set_summary_track(track: String) -> anyhow::Result<()> {
+    get_engine(|engine| {
+        engine.set_summary_track(parse_track(&track)?);
+        Ok(())
+    })
+}
+
+pub fn get_current_translation() -> anyhow::Result<String> {
+    get_engine(|engine| {
+        engine.tick(); // Process pending audio
+        Ok(engine.get_current_translation())
+    })
+}
+
+// F1.4: One transcript segment with its decoder confidence. The UI dims low-probability segments.
+pub struct SegmentInfo {
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub text: String,
+    pub avg_token_prob: f32,
+    pub min_token_prob: f32,
+    pub language: Option<String>, // F17.2: None if not detected
+}
+
+fn segment_infos(segments: Vec<TranscriptSegment>) -> Vec<SegmentInfo> {
+    segments.into_iter()
+        .map(|s| SegmentInfo {
+            start_ms: s.start_ms,
+            end_ms: s.end_ms,
+            text: s.text,
+            avg_token_prob: s.avg_token_prob,
+            min_token_prob: s.min_token_prob,
+            language: s.language,
+        })
+        .collect()
+}
+
+// F17.2: A language of a transcript with its share of the text (0..1)
+pub struct LanguageShare {
+    pub code: String,
+    pub share: f32,
+}
+
+// F17.2: Languages spoken in a note's original transcript, largest share first
+pub fn get_transcript_languages(note_id: i64) -> anyhow::Result<Vec<LanguageShare>> {
+    get_engine(|engine| {
+        Ok(engine.get_transcript_languages(note_id)?
+            .into_iter()
+            .map(|(code, share)| LanguageShare { code, share })
+            .collect())
+    })
+}
+
+// One track of a saved note
+pub fn get_transcript(note_id: i64, track: String) -> anyhow::Result<Vec<SegmentInfo>> {
+    get_engine(|engine| {
+        let segments = engine.get_transcript_segments(note_id, parse_track(&track)?)?;
+        Ok(segment_infos(segments))
+    })
+}
+
+// F1.5: Re-transcription keeps every transcript version; get_transcript returns the latest
+pub fn get_transcript_versions(note_id: i64) -> anyhow::Result<Vec<i64>> {
+    get_engine(|engine| {
+        engine.get_transcript_versions(note_id)
+    })
+}
+
+pub fn get_transcript_version(note_id: i64, track: String, version: i64) -> anyhow::Result<Vec<SegmentInfo>> {
+    get_engine(|engine| {
+        let segments = engine.get_transcript_segments_version(note_id, parse_track(&track)?, version)?;
+        Ok(segment_infos(segments))
+    })
+}
+
+pub fn queue_retranscription(note_id: i64) -> anyhow::Result<i64> {
+    get_engine(|engine| {
+        engine.queue_retranscription(note_id)
+    })
+}
+
+// (job_id, status, attempts, updated_at); status is queued / running / done / failed
+pub fn get_retranscribe_jobs(note_id: i64) -> anyhow::Result<Vec<(i64, String, i64, i64)>> {
+    get_engine(|engine| {
+        engine.get_retranscribe_jobs(note_id)
+    })
+}
+
+// Summary that a finished re-transcription replaced
+pub fn get_previous_summary(job_id: i64) -> anyhow::Result<Option<String>> {
+    get_engine(|engine| {
+        engine.get_previous_summary(job_id)
+    })
+}
+
+// F2.2: Summary of one stretch of a long recording (session-relative times)
+pub struct PartialSummaryInfo {
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub summary: String,
+}
+
+pub fn get_partial_summaries(note_id: i64) -> anyhow::Result<Vec<PartialSummaryInfo>> {
+    get_engine(|engine| {
+        Ok(engine.get_partial_summaries(note_id)?.into_iter()
+            .map(|p| PartialSummaryInfo { start_ms: p.start_ms, end_ms: p.end_ms, summary: p.summary })
+            .collect())
+    })
+}
+
+// F2.1: One homework / assignment item
+pub struct ActionItemInfo {
+    pub text: String,
+    pub due: Option<String>, // As spoken: "next Friday", "March 3"
+}
+
+// Same, with the note it came from (folder = course)
+pub struct HomeworkInfo {
+    pub note_id: i64,
+    pub note_title: String,
+    pub folder_id: Option<i64>,
+    pub text: String,
+    pub due: Option<String>,
+}
+
+pub struct DefinitionInfo {
+    pub term: String,
+    pub definition: String,
+}
+
+pub struct DateInfo {
+    pub date: String,
+    pub context: String,
+}
+
+// F2.1: Sections of a note's summary; the rendered text stays in the note content
+pub struct StructuredSummaryInfo {
+    pub key_points: Vec<String>,
+    pub action_items: Vec<ActionItemInfo>,
+    pub definitions: Vec<DefinitionInfo>,
+    pub questions: Vec<String>,
+    pub dates: Vec<DateInfo>,
+}
+
+pub fn get_structured_summary(note_id: i64) -> anyhow::Result<Option<StructuredSummaryInfo>> {
+    get_engine(|engine| {
+        let Some(summary) = engine.get_structured_summary(note_id)? else { return Ok(None) };
+        Ok(Some(StructuredSummaryInfo {
+            key_points: summary.key_points,
+            action_items: summary.action_items.into_iter()
+                .map(|a| ActionItemInfo { text: a.text, due: a.due })
+                .collect(),
+            definitions: summary.definitions.into_iter()
+                .map(|d| DefinitionInfo { term: d.term, definition: d.definition })
+                .collect(),
+            questions: summary.questions,
+            dates: summary.dates.into_iter()
+                .map(|d| DateInfo { date: d.date, context: d.context })
+                .collect(),
+        }))
+    })
+}
+
+// F2.1: Homework across all courses (folder_id None) or one course, newest notes first
+pub fn get_action_items(folder_id: Option<i64>) -> anyhow::Result<Vec<HomeworkInfo>> {
+    get_engine(|engine| {
+        Ok(engine.get_action_items(folder_id)?.into_iter()
+            .flat_map(|note| {
+                note.summary.action_items.into_iter().map(move |item| HomeworkInfo {
+                    note_id: note.note_id,
+                    note_title: note.title.clone(),
+                    folder_id: note.folder_id,
+                    text: item.text,
+                    due: item.due,
+                })
+            })
+            .collect())
+    })
+}
+
+// Same as get_transcript, for the session being recorded
+pub fn get_current_segments(track: String) -> anyhow::Result<Vec<SegmentInfo>> {
+    get_engine(|engine| {
+        engine.tick(); // Process pending audio
+        Ok(segment_infos(engine.get_session_segments(parse_track(&track)?)))
+    })
+}
+
+pub fn search_notes_in_track(query: String, track: String) -> anyhow::Result<Vec<(i64, String, String, i64)>> {
+    get_engine(|engine| {
+        engine.search_notes_in_track(&query, parse_track(&track)?)
+    })
+}
+
+// F1.1: Replace the phantom-phrase blocklist used by the ASR hallucination filter
+pub fn set_phantom_phrases(phrases: Vec<String>) -> anyhow::Result<()> {
+    get_engine(|engine| {
+        engine.set_phantom_phrases(phrases);
+        Ok(())
     })
 }
 
@@ -66,6 +452,143 @@ pub fn update_thermal_status(battery_temp: f32) {
     });
 }
 
+// F17: Last model load failure (missing file, checksum mismatch, ...), None if all is well
+pub fn get_model_error() -> anyhow::Result<Option<String>> {
+    get_engine(|engine| {
+        Ok(engine.get_model_error())
+    })
+}
+
+// F17: One manifest.json entry
+pub struct ModelInfo {
+    pub id: String,
+    pub kind: String, // asr / llm / embed / vad / ocr
+    pub file: String,
+    pub quantization: Option<String>,
+    pub ram_mb: u64,
+    pub installed: bool,
+}
+
+pub fn list_models() -> anyhow::Result<Vec<ModelInfo>> {
+    get_engine(|engine| {
+        Ok(engine.list_models().into_iter()
+            .map(|(m, installed)| ModelInfo {
+                id: m.id,
+                kind: m.kind.as_str().to_string(),
+                file: m.file,
+                quantization: m.quantization,
+                ram_mb: m.ram_mb,
+                installed,
+            })
+            .collect())
+    })
+}
+
+// F18: A long-running operation. `kind` is summary, answer, flashcards, titles, search, reindex, import
+// or retranscribe; `status` is running, done, cancelled or failed (with `error`). `total` 0 = unknown.
+pub struct JobInfo {
+    pub id: i64,
+    pub kind: String,
+    pub status: String,
+    pub done: u64,
+    pub total: u64,
+    pub message: String,
+    pub result: Option<String>,
+    pub error: Option<String>,
+    pub started_at: i64,
+    pub finished_at: Option<i64>,
+}
+
+impl From<jobs::JobInfo> for JobInfo {
+    fn from(job: jobs::JobInfo) -> Self {
+        let error = match &job.status {
+            jobs::JobStatus::Failed(error) => Some(error.clone()),
+            _ => None,
+        };
+        Self {
+            id: job.id,
+            kind: job.kind.as_str().to_string(),
+            status: job.status.as_str().to_string(),
+            done: job.done,
+            total: job.total,
+            message: job.message,
+            result: job.result,
+            error,
+            started_at: job.started_at,
+            finished_at: job.finished_at,
+        }
+    }
+}
+
+// F18: Jobs are tracked outside the engine lock, so these answer while a job holds the engine
+pub fn list_jobs() -> Vec<JobInfo> {
+    jobs::list().into_iter().map(JobInfo::from).collect()
+}
+
+pub fn get_job(job_id: i64) -> Option<JobInfo> {
+    jobs::get(job_id).map(JobInfo::from)
+}
+
+// False if the job already finished
+pub fn cancel_job(job_id: i64) -> bool {
+    jobs::cancel(job_id)
+}
+
+// The job once finished, or as it is after `timeout_ms`
+pub fn wait_job(job_id: i64, timeout_ms: u32) -> anyhow::Result<JobInfo> {
+    jobs::wait(job_id, std::time::Duration::from_millis(timeout_ms as u64))
+        .map(JobInfo::from)
+        .ok_or_else(|| anyhow::anyhow!("Unknown job {}", job_id))
+}
+
+// Every job start, progress step and finish, until the UI closes the stream
+pub fn watch_jobs(sink: StreamSink<JobInfo>) {
+    let events = jobs::subscribe();
+    std::thread::spawn(move || {
+        for job in events {
+            if sink.add(JobInfo::from(job)).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+// F18: Search without holding the engine; results via get_search_job_results once the job is done
+pub fn start_search_job(query: String, track: String) -> anyhow::Result<i64> {
+    get_engine(|engine| {
+        Ok(engine.start_search(&query, parse_track(&track)?))
+    })
+}
+
+pub fn get_search_job_results(job_id: i64) -> anyhow::Result<Vec<(i64, String, String, i64)>> {
+    get_engine(|engine| {
+        engine.search_job_results(job_id)
+    })
+}
+
+// F18: Re-embed the notes of a folder (None = all notes), e.g. after installing an embedding model
+pub fn start_reindex_job(folder_id: Option<i64>) -> anyhow::Result<i64> {
+    get_engine(|engine| {
+        Ok(engine.start_reindex(folder_id))
+    })
+}
+
+// F17.1: Install a model pack (zip, tar or tar.gz) copied from USB / SD card, as an F18 job.
+// The result is the JSON list of installed model ids; errors name the corrupt or incompatible model.
+pub fn start_import_job(archive_path: String) -> anyhow::Result<i64> {
+    get_engine(|engine| {
+        Ok(engine.start_import(&archive_path))
+    })
+}
+
+// F1.5: Host reports charger and idle state (screen off, no active session)
+pub fn update_power_state(charging: bool, device_idle: bool) {
+    let _ = get_engine(|engine| {
+        engine.update_power_state(charging, device_idle);
+        Ok(())
+    });
+}
+
 pub fn create_folder(name: String) -> anyhow::Result<i64> {
     get_engine(|engine| {
         engine.create_folder(&name)
@@ -78,6 +601,211 @@ pub fn get_folders() -> anyhow::Result<Vec<(i64, String)>> {
     })
 }
 
+// F2.3: Summary prompt templates. Variables: {{subject}}, {{language}}, {{context}} (past notes)
+// and {{transcript}} (required). Returns (id, name, body).
+pub fn get_prompt_templates() -> anyhow::Result<Vec<(i64, String, String)>> {
+    get_engine(|engine| {
+        engine.get_prompt_templates()
+    })
+}
+
+pub fn create_prompt_template(name: String, body: String) -> anyhow::Result<i64> {
+    get_engine(|engine| {
+        engine.create_prompt_template(&name, &body)
+    })
+}
+
+pub fn update_prompt_template(template_id: i64, name: String, body: String) -> anyhow::Result<()> {
+    get_engine(|engine| {
+        engine.update_prompt_template(template_id, &name, &body)
+    })
+}
+
+pub fn delete_prompt_template(template_id: i64) -> anyhow::Result<()> {
+    get_engine(|engine| {
+        engine.delete_prompt_template(template_id)
+    })
+}
+
+// None goes back to the default template
+pub fn set_folder_template(folder_id: i64, template_id: Option<i64>) -> anyhow::Result<()> {
+    get_engine(|engine| {
+        engine.set_folder_template(folder_id, template_id)
+    })
+}
+
+// F3: One flashcard; kind is "cloze" (term blanked out) or "qa" (written by the LLM)
+pub struct FlashcardInfo {
+    pub id: i64,
+    pub note_id: i64,
+    pub kind: String,
+    pub front: String,
+    pub back: String,
+    pub due_at: i64, // Unix seconds; 0 = new
+    pub interval_days: u32,
+}
+
+impl From<crate::study::cards::Card> for FlashcardInfo {
+    fn from(c: crate::study::cards::Card) -> Self {
+        FlashcardInfo {
+            id: c.id,
+            note_id: c.note_id,
+            kind: c.kind.as_str().to_string(),
+            front: c.front,
+            back: c.back,
+            due_at: c.review.due_at,
+            interval_days: c.review.interval_days,
+        }
+    }
+}
+
+// Returns how many new cards were added (cards already on the note are kept)
+pub fn generate_flashcards(note_id: i64) -> anyhow::Result<u32> {
+    let job_id = get_engine(|engine| {
+        engine.start_flashcards(note_id)
+    })?;
+    Ok(job_result(job_id)?.unwrap_or_default().parse()?)
+}
+
+pub fn generate_folder_flashcards(folder_id: i64) -> anyhow::Result<u32> {
+    let job_id = get_engine(|engine| {
+        engine.start_folder_flashcards(folder_id)
+    })?;
+    Ok(job_result(job_id)?.unwrap_or_default().parse()?)
+}
+
+pub fn get_flashcards(note_id: i64) -> anyhow::Result<Vec<FlashcardInfo>> {
+    get_engine(|engine| {
+        Ok(engine.get_cards(note_id)?.into_iter().map(FlashcardInfo::from).collect())
+    })
+}
+
+// F3.2: Cards to review now, overdue first then new; folder_id None = every folder
+pub fn get_due_flashcards(folder_id: Option<i64>, limit: u32) -> anyhow::Result<Vec<FlashcardInfo>> {
+    get_engine(|engine| {
+        Ok(engine.get_due_cards(folder_id, limit as usize)?.into_iter().map(FlashcardInfo::from).collect())
+    })
+}
+
+pub struct StudyCountInfo {
+    pub due: u32,
+    pub new: u32,
+}
+
+pub fn count_due_flashcards(folder_id: Option<i64>) -> anyhow::Result<StudyCountInfo> {
+    get_engine(|engine| {
+        let (due, new) = engine.count_due_cards(folder_id)?;
+        Ok(StudyCountInfo { due: due as u32, new: new as u32 })
+    })
+}
+
+pub struct ReviewResultInfo {
+    pub due_at: i64,
+    pub interval_days: u32,
+    pub ease: f32,
+}
+
+// grade: 1 = Again, 2 = Hard, 3 = Good, 4 = Easy
+pub fn grade_flashcard(card_id: i64, grade: u8, duration_ms: u32) -> anyhow::Result<ReviewResultInfo> {
+    get_engine(|engine| {
+        let state = engine.review_card(card_id, grade, duration_ms as i64)?;
+        Ok(ReviewResultInfo { due_at: state.due_at, interval_days: state.interval_days, ease: state.ease })
+    })
+}
+
+pub fn delete_flashcard(card_id: i64) -> anyhow::Result<()> {
+    get_engine(|engine| {
+        engine.delete_card(card_id)
+    })
+}
+
+// F3.1: Anki export; the format follows the extension (.apkg or .csv). Returns the card count.
+pub fn export_flashcards(folder_id: Option<i64>, path: String) -> anyhow::Result<u32> {
+    get_engine(|engine| {
+        Ok(engine.export_cards(folder_id, &path)? as u32)
+    })
+}
+
+// F2.6: New "<subject>: <topic> (<date>)" title for one note; returns it
+pub fn regenerate_note_title(note_id: i64) -> anyhow::Result<String> {
+    let job_id = get_engine(|engine| {
+        engine.start_regenerate_note_title(note_id)
+    })?;
+    wait_for_job(job_id)?;
+    get_engine(|engine| engine.title_job_results(job_id))?
+        .pop()
+        .map(|(_, title)| title)
+        .ok_or_else(|| anyhow::anyhow!("Note {} not found", note_id))
+}
+
+// F2.6: Retitle a folder's notes (None = all notes); `only_untitled` keeps titles the user has set
+// and only replaces "Note <time>". Returns (note id, new title) pairs.
+pub fn regenerate_note_titles(folder_id: Option<i64>, only_untitled: bool) -> anyhow::Result<Vec<(i64, String)>> {
+    let job_id = get_engine(|engine| {
+        engine.start_regenerate_titles(folder_id, only_untitled)
+    })?;
+    wait_for_job(job_id)?;
+    get_engine(|engine| {
+        engine.title_job_results(job_id)
+    })
+}
+
+// F2.7: `automatic` tags were proposed from the note's keyphrases
+pub struct NoteTagInfo {
+    pub name: String,
+    pub automatic: bool,
+}
+
+pub struct TagInfo {
+    pub name: String,
+    pub note_count: u32,
+}
+
+// F2.7: Tags are stored lower case; returns false if the note already had it
+pub fn add_note_tag(note_id: i64, tag: String) -> anyhow::Result<bool> {
+    get_engine(|engine| {
+        engine.add_tag(note_id, &tag)
+    })
+}
+
+pub fn remove_note_tag(note_id: i64, tag: String) -> anyhow::Result<bool> {
+    get_engine(|engine| {
+        engine.remove_tag(note_id, &tag)
+    })
+}
+
+pub fn get_note_tags(note_id: i64) -> anyhow::Result<Vec<NoteTagInfo>> {
+    get_engine(|engine| {
+        Ok(engine.get_note_tags(note_id)?
+            .into_iter()
+            .map(|(name, automatic)| NoteTagInfo { name, automatic })
+            .collect())
+    })
+}
+
+// Every tag in use, most used first
+pub fn list_tags() -> anyhow::Result<Vec<TagInfo>> {
+    get_engine(|engine| {
+        Ok(engine.get_tags()?
+            .into_iter()
+            .map(|(name, count)| TagInfo { name, note_count: count as u32 })
+            .collect())
+    })
+}
+
+pub fn get_notes_by_tag(tag: String) -> anyhow::Result<Vec<(i64, String, String, i64)>> {
+    get_engine(|engine| {
+        engine.get_notes_by_tag(&tag)
+    })
+}
+
+// F2.7: Keyphrase tags for a note, not stored (add the ones the user picks with add_note_tag)
+pub fn suggest_note_tags(note_id: i64) -> anyhow::Result<Vec<String>> {
+    get_engine(|engine| {
+        engine.suggest_tags(note_id)
+    })
+}
+
 pub fn get_notes_by_folder(folder_id: i64) -> anyhow::Result<Vec<(i64, String, String, i64)>> {
     get_engine(|engine| {
         engine.get_notes_by_folder(folder_id)
@@ -5,28 +5,86 @@ use crate::audio::buffer::CircularAudioBuffer;
 // use crate::ai::llm::LlmModel;
 use crate::storage::db::Database;
 use crate::engine::endurance::{EnduranceController, EnduranceMode};
-use crate::ai::manager::ModelManager;
+use crate::ai::manager::{ModelManager, ModelStatus};
+use crate::ai::llm::{ExtractiveSummarizer, GenerationOptions, Summarizer};
+use crate::ai::prompt;
+use crate::ai::budget::BudgetReport;
+use crate::ai::qa::{self, Citation};
+use crate::ai::title;
+use crate::ai::keyphrase;
+use crate::ai::locale::{self, Locale};
+use crate::ai::mapreduce::{self, PartialSummary, REDUCE_BUDGET_CHARS, WINDOW_CHARS};
+use crate::ai::structured::{NoteSummary, StructuredSummary};
 use crate::ai::text::RollingBuffer;
 use crate::ai::lang::LanguageDetector;
-// use std::sync::{Arc, Mutex};
+use crate::ai::asr::{AsrBackendKind, AsrSegment, DecodeOptions};
+use crate::ai::asr;
+use crate::ai::registry::ModelEntry;
+use crate::ai::install;
+use crate::ai::transcript::{self, TranscriptSegment, TranscriptTrack};
+use crate::ai::filter::{FilterConfig, FilterVerdict, HallucinationFilter};
+use crate::ai::punct;
+use crate::ai::worker::{AsrResult, AsrWorker};
+use crate::study::anki;
+use crate::study::cards::{self, Card};
+use crate::study::srs::{Grade, ReviewState};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
+use std::time::Duration;
 // use crate::ai::rag::VectorStore;
 
 pub mod endurance;
+pub mod jobs;
+pub mod retranscribe;
+
+use jobs::{Job, JobKind};
+
+use retranscribe::{ApplyFn, RetranscribeJob, RetranscribeOutcome, Retranscriber, MULTILINGUAL_RETRANSCRIBE_MODEL, RETRANSCRIBE_MAX_ATTEMPTS, RETRANSCRIBE_MODEL};
+
+// F2: Sees the summary or answer as it is generated, on the job's thread; false cuts it short
+pub type TokenSink = Box<dyn FnMut(&str) -> bool + Send>;
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum EngineState {
     Idle,
     Recording,   // ASR Loaded, Buffer Empty
     Summarizing, // LLM Loaded, ASR Unloaded, Buffer Accumulating
+    Retranscribing, // F1.5: Larger ASR on a stored session, only from Idle
 }
 
 pub struct Engine {
     state: EngineState,
     recorder: AudioRecorder,
     audio_buffer: CircularAudioBuffer, // v5.1 Hardening
-    model_manager: ModelManager,
+    model_manager: Arc<Mutex<ModelManager>>, // Shared with the ASR worker thread
+    low_power: Arc<AtomicBool>, // F17: ModelManager's Endurance flag, set without waiting on the worker
+    model_status: Arc<RwLock<ModelStatus>>, // F17: ModelManager's errors and model list, read the same way
+    asr_worker: AsrWorker,
     buffer: RollingBuffer,
+    hallucination_filter: HallucinationFilter,
+
+    // F1.2: Translate mode keeps an English track next to the original
+    translate_mode: bool,
+    translation_buffer: RollingBuffer,
+    summary_track: TranscriptTrack,
+    generation: GenerationOptions, // F2: Summary length limit and stop sequences
+    session_segments: Vec<TranscriptSegment>,
+    submitted_samples: u64, // Session clock for segment timestamps
+
+    // F2.2: Map step of the session summary; windows before `summary_window_start` are summarized
+    session_id: i64, // Start time (ms), keys the note's partial summaries
+    partial_summaries: Vec<PartialSummary>,
+    summary_window_start: usize, // Index into session_segments
+
+    // F1.5: Background re-transcription of stored sessions
+    retranscriber: Retranscriber,
+    retranscribe_job: Option<Job>,    // F18: Finished once the outcome is applied
+    // F18: Summary, answer, flashcard or title job holding the model slot; Idle again once it is done
+    model_job: Option<i64>,
+
     lang_detector: LanguageDetector,
+    summary_language: Option<String>, // F2.8: None = the lecture's detected language
     current_subject: String,
     current_folder_id: Option<i64>,
     
@@ -35,26 +93,59 @@ pub struct Engine {
     tick_count: u64,
     
     database: Database,
-    _models_dir: String,
+    db_path: String, // F18: Job threads open their own connection
+    models_dir: String,
     transcription_buffer: Vec<f32>, // v5.4: Accumulator for smoother ASR
     full_session_audio: Vec<f32>,   // v5.5: Persistence Buffer
 }
 
+// Max ASR jobs waiting behind the one being decoded (3s chunks => ~12s of audio)
+const ASR_QUEUE_DEPTH: usize = 4;
+const DB_PASSWORD: &str = "default_password";
+// Notes returned by a semantic search
+const SEARCH_LIMIT: usize = 10;
+// F17.2: A second language with this share of the transcript makes the session mixed-language
+const MIXED_MIN_SHARE: f32 = 0.15;
+
 impl Engine {
     pub fn new(db_path: &str, models_dir: &str) -> Self {
+        let model_manager = Arc::new(Mutex::new(ModelManager::new(models_dir)));
+        let (low_power, model_status) = {
+            let models = model_manager.lock().unwrap();
+            (models.low_power_flag(), models.status_handle())
+        };
+        let asr_worker = AsrWorker::spawn(model_manager.clone(), ASR_QUEUE_DEPTH);
         Self {
             state: EngineState::Idle,
             recorder: AudioRecorder::new(),
             audio_buffer: CircularAudioBuffer::new(),
-            model_manager: ModelManager::new(models_dir),
+            model_manager,
+            low_power,
+            model_status,
+            asr_worker,
             buffer: RollingBuffer::new(8000), 
+            hallucination_filter: HallucinationFilter::new(FilterConfig::default()),
+            translate_mode: false,
+            translation_buffer: RollingBuffer::new(8000),
+            summary_track: TranscriptTrack::Original,
+            generation: GenerationOptions::default(),
+            session_segments: Vec::new(),
+            submitted_samples: 0,
+            session_id: chrono::Utc::now().timestamp_millis(),
+            partial_summaries: Vec::new(),
+            summary_window_start: 0,
+            retranscriber: Retranscriber::new(),
+            retranscribe_job: None,
+            model_job: None,
             lang_detector: LanguageDetector::new(),
+            summary_language: None,
             current_subject: "General".to_string(),
             current_folder_id: None,
             endurance: EnduranceController::new(),
             tick_count: 0,
-            database: Database::open(db_path, "default_password").expect("Failed to open DB"),
-            _models_dir: models_dir.to_string(),
+            database: Database::open(db_path, DB_PASSWORD).expect("Failed to open DB"),
+            db_path: db_path.to_string(),
+            models_dir: models_dir.to_string(),
             transcription_buffer: Vec::new(),
             full_session_audio: Vec::new(),
         }
@@ -66,23 +157,102 @@ impl Engine {
 
     pub fn start_recording(&mut self) -> anyhow::Result<()> {
         println!("Engine: Starting Recording (Subject: {})...", self.current_subject);
+        self.collect_model_job();
+        if self.model_job.is_some() {
+            return Err(anyhow::anyhow!("Engine is busy ({:?}), start again in a moment", self.state));
+        }
+        self.cancel_retranscription(); // Live capture owns the ASR slot
         
         // 1. Load ASR First
-        self.model_manager.load_asr();
+        self.models().load_asr();
         
         // 2. Start Audio Capture
         self.recorder.start()?;
         
         self.state = EngineState::Recording;
         self.full_session_audio.clear(); // Reset buffer
+        self.audio_buffer.clear();
+        self.transcription_buffer.clear();
+        self.session_segments.clear();
+        self.translation_buffer.clear();
+        self.submitted_samples = 0;
+        self.reset_session_summary();
+        Ok(())
+    }
+
+    // F1.2: Per-session translate mode (Whisper translate task into English)
+    // Fails without a multilingual model, whether or not ASR is loaded right now.
+    pub fn set_translate_mode(&mut self, enabled: bool) -> anyhow::Result<()> {
+        self.models().set_multilingual(enabled)?;
+        self.translate_mode = enabled;
+        Ok(())
+    }
+
+    // F1.2: Which track feeds the summarizer
+    pub fn set_summary_track(&mut self, track: TranscriptTrack) {
+        self.summary_track = track;
+    }
+
+    // F2.5: What the last generative prompt left out to fit the model's context window
+    pub fn last_prompt_budget(&self) -> Option<BudgetReport> {
+        self.models().last_budget()
+    }
+
+    // F2: Caps generated summaries; empty `stop` keeps the model's own end-of-turn only
+    pub fn set_generation_options(&mut self, max_tokens: usize, stop: Vec<String>) {
+        self.generation = GenerationOptions { max_tokens: max_tokens.max(1), stop, ..Default::default() };
+    }
+
+    // F2.8: Write summaries in this language whatever the lecture's is (None: follow the lecture)
+    pub fn set_summary_language(&mut self, code: Option<String>) -> anyhow::Result<()> {
+        self.summary_language = match code.as_deref().map(str::trim).filter(|c| !c.is_empty()) {
+            Some(code) => Some(locale::get(code)
+                .ok_or_else(|| anyhow::anyhow!("Summaries cannot be written in '{}'", code))?
+                .code.to_string()),
+            None => None,
+        };
         Ok(())
     }
 
+    pub fn get_summary_language(&self) -> Option<String> {
+        self.summary_language.clone()
+    }
+
+    fn models(&self) -> MutexGuard<'_, ModelManager> {
+        lock_models(&self.model_manager)
+    }
+
+    // F1: Select the ASR engine ("whisper", "onnx", "mock")
+    pub fn set_asr_backend(&mut self, name: &str) -> anyhow::Result<()> {
+        let kind = AsrBackendKind::parse(name)
+            .ok_or_else(|| anyhow::anyhow!("Unknown ASR backend '{}'", name))?;
+        let reload = self.state == EngineState::Recording;
+        let mut models = self.models();
+        models.set_asr_backend(kind);
+        if reload {
+            models.load_asr();
+        }
+        Ok(())
+    }
+
+    // F1.1: User-editable list of phantom phrases (e.g. localized "Thanks for watching")
+    pub fn set_phantom_phrases(&mut self, phrases: Vec<String>) {
+        self.hallucination_filter.set_blocklist(phrases);
+    }
+
     // Gap 5: Host -> Engine Thermal Update
     pub fn update_battery_temp(&mut self, temp: f32) {
         self.endurance.update_battery_temp(temp);
     }
 
+    // F1.5: Host -> Engine power/idle update (gates re-transcription)
+    pub fn update_power_state(&mut self, charging: bool, device_idle: bool) {
+        self.endurance.update_power_state(charging, device_idle);
+        if !self.endurance.allows_background_work() {
+            self.cancel_retranscription();
+        }
+    }
+
     pub fn tick(&mut self) {
         self.tick_count += 1;
         
@@ -93,18 +263,29 @@ impl Engine {
                 // Determine if we need to switch strategies
                 // For now, just logging
             }
+            // F17: Smaller models from the next load on while hot
+            // 8.2: Never the models lock here, the worker holds it for a whole Whisper pass
+            self.low_power.store(mode == EnduranceMode::Endurance, Ordering::Relaxed);
             
             // v5.3: Manual GC every 20 mins (simulated frequency here)
             if self.tick_count % 12000 == 0 {
-                self.model_manager.force_gc();
+                ModelManager::force_gc();
             }
+
+            // F1.5: Start or stop re-transcription as the device state changes
+            self.schedule_retranscription();
         }
+        self.collect_retranscription();
+        self.collect_model_job();
     
         // v5.1: Audio Capture logic runs in ALL active states
         let new_audio = self.recorder.get_audio_data();
         
         match self.state {
             EngineState::Recording => {
+                // 8.2: Pick up whatever the worker finished since the last tick
+                self.collect_asr_results();
+
                 // v5.5 Persistence Accumulation
                 if !new_audio.is_empty() {
                     self.full_session_audio.extend_from_slice(&new_audio);
@@ -123,30 +304,26 @@ impl Engine {
                          println!("Endurance Mode: Running Batch Inference on buffered audio before flush...");
                          // In simulation, we assume audio_buffer holds the chunk.
                          let buffered_audio = self.audio_buffer.read_all();
-                         let text = self.model_manager.transcribe(&buffered_audio);
-                         if !text.is_empty() {
-                             println!("Batch Transcribed: {}", text);
-                             self.buffer.push(&text);
-                         }
+                         self.submit_asr(buffered_audio);
                      }
 
                      // In real imp: Encrypt and append to SQLite blob
                      // For harness: Clear buffer to prove RAM release
                      self.audio_buffer.clear();
                      println!("Engine: RAM cleared. Audio flushed encrypted.");
+
+                     // F2.2: Map step for the transcript since the last window
+                     self.summarize_windows(false);
                 }
             
                 // 1. Check if we have buffered audio from a previous swap
                 if !self.audio_buffer.is_empty() {
                     // Only drain immediately if High Performance. In Endurance, we wait for Batch (State B).
-                    if mode == EnduranceMode::HighPerformance {
+                    // 8.2: Also wait for an idle worker so re-buffered audio does not bounce off a full queue.
+                    if mode == EnduranceMode::HighPerformance && self.asr_worker.pending() == 0 {
                         println!("Engine: Draining Circular Buffer ({} samples)...", self.audio_buffer.len());
                         let buffered_audio = self.audio_buffer.read_all();
-                        let text = self.model_manager.transcribe(&buffered_audio);
-                        if !text.is_empty() {
-                             self.buffer.push(&text);
-                             println!("Buffered Transcribed: {}", text);
-                        }
+                        self.submit_asr(buffered_audio);
                     }
                 }
                 
@@ -159,23 +336,8 @@ impl Engine {
                             
                             // 48000 samples = 3 seconds (Better context, less "swallowing")
                             if self.transcription_buffer.len() >= 48000 {
-                                let text = self.model_manager.transcribe(&self.transcription_buffer);
-                                // Filter out hallucinations and silence artifacts
-                                if !text.is_empty() && !text.contains("[BLANK_AUDIO]") {
-                                    // De-dup: Prevent repeating the exact same segment
-                                    // RollingBuffer is just a String wrapper, so we check suffixes.
-                                    let context = self.buffer.get_context().trim_end();
-                                    let normalized_text = text.trim();
-                                    
-                                    // Check if the END of the context matches the new text (basic suffix check)
-                                    // We also check "starts_with" inversely in case the buffer ends with partial of new text,
-                                    // but for now, exact suffix matching is the safest "Loop Breaker".
-                                    if !normalized_text.is_empty() && !context.ends_with(normalized_text) {
-                                         println!("Transcribed: {}", text);
-                                         self.buffer.push(&text);
-                                    }
-                                }
-                                self.transcription_buffer.clear();
+                                let chunk = std::mem::take(&mut self.transcription_buffer);
+                                self.submit_asr(chunk);
                             }
                         },
                         EnduranceMode::Endurance => {
@@ -184,7 +346,7 @@ impl Engine {
                     }
                 }
             }
-            EngineState::Summarizing => {
+            EngineState::Summarizing | EngineState::Retranscribing => {
                 // v5.1 CRITICAL: Do NOT stop capturing. 
                 // Buffer audio while ASR is unloaded.
                 if !new_audio.is_empty() {
@@ -197,125 +359,239 @@ impl Engine {
     }
 
 
+    // 8.2: Hand audio to the worker. If the queue is full, park it in the circular buffer;
+    // the High Performance path drains it again once the worker catches up.
+    fn submit_asr(&mut self, audio: Vec<f32>) {
+        if audio.is_empty() { return; }
+        let samples = audio.len() as u64;
+        let offset_ms = (self.submitted_samples * 1000 / 16000) as i64;
+        let options = DecodeOptions { language: None, translate: self.translate_mode };
+        match self.asr_worker.submit(audio, offset_ms, options) {
+            Ok(_) => self.submitted_samples += samples,
+            Err(audio) => {
+                println!("Engine: ASR queue full, re-buffering {} samples.", audio.len());
+                self.audio_buffer.push(&audio);
+            }
+        }
+    }
+
+    fn collect_asr_results(&mut self) {
+        for result in self.asr_worker.poll() {
+            println!("Engine: ASR job {} finished in {}ms.", result.seq, result.elapsed_ms);
+            self.ingest_result(result);
+        }
+    }
+
+    fn ingest_result(&mut self, result: AsrResult) {
+        ingest_asr_result(&self.hallucination_filter, &mut self.buffer, &mut self.translation_buffer, &mut self.session_segments, result);
+    }
+
+    // F2.2: Map step for the transcript since the last window (see summarize_windows)
+    fn summarize_windows(&mut self, final_window: bool) {
+        let pending = &self.session_segments[self.summary_window_start..];
+        let partials = summarize_windows(&self.hallucination_filter, pending, self.summary_track, final_window);
+        if partials.is_empty() {
+            return;
+        }
+        self.partial_summaries.extend(partials);
+        self.summary_window_start = self.session_segments.len();
+    }
+
+    // F2.2: A new session id, with no window summaries yet
+    fn reset_session_summary(&mut self) {
+        self.session_id = chrono::Utc::now().timestamp_millis();
+        self.partial_summaries.clear();
+        self.summary_window_start = 0;
+    }
+
+    // F1.5: Claim the next queued job when the device is idle, charging and cool.
+    // Only between sessions: the job needs the ASR slot, and live capture must not lose it.
+    fn schedule_retranscription(&mut self) {
+        let allowed = self.endurance.allows_background_work();
+        if self.retranscriber.is_running() {
+            if !allowed { self.cancel_retranscription(); }
+            return;
+        }
+        if !allowed || self.state != EngineState::Idle || self.asr_worker.pending() > 0 {
+            return;
+        }
+
+        let Ok(Some((job_id, note_id, model))) = self.database.claim_retranscribe_job() else { return };
+
+        let audio_paths: Vec<String> = self.database.get_attachments(note_id).unwrap_or_default()
+            .into_iter()
+            .filter(|(_, file_type, path)| file_type == "audio" && std::path::Path::new(path).exists())
+            .map(|(_, _, path)| path)
+            .collect();
+        if audio_paths.is_empty() {
+            let _ = self.database.fail_retranscribe_job(job_id, "No stored audio", 1);
+            return;
+        }
+
+        // Not downloaded yet: leave the job queued
+        let backend = asr::create_whisper_backend(&model)
+            .filter(|_| std::path::Path::new(&self.models_dir).join(&model).exists());
+        let Some(backend) = backend else {
+            let _ = self.database.set_retranscribe_status(job_id, "queued", Some("Model not installed"));
+            return;
+        };
+        // F17: Hashed on the job's thread, before the model is loaded
+        let manifest_entry = self.models().registry().entry_for_file(&model);
+        // F2.2: The sessions recorded so far; one appended while the job runs keeps its windows
+        let sessions = self.database.get_partial_summary_sessions(note_id).unwrap_or_default();
+
+        println!("Engine: Re-transcribing note {} with {}...", note_id, model);
+        self.state = EngineState::Retranscribing;
+        let progress = jobs::start(JobKind::Retranscribe, &format!("Re-transcribing note {}", note_id));
+        let context = RetranscribeContext {
+            db_path: self.db_path.clone(),
+            models: self.model_manager.clone(),
+            filter: self.hallucination_filter.clone(),
+            generation: self.generation.clone(),
+            summary_language: self.summary_language.clone(),
+        };
+        let apply: ApplyFn = Box::new(move |segments, progress| apply_retranscription(&context, job_id, note_id, &sessions, segments, progress));
+        let job = RetranscribeJob { job_id, note_id, audio_paths, manifest_entry };
+        self.retranscriber.start(job, backend, &self.models_dir, progress.clone(), apply);
+        if !self.retranscriber.is_running() {
+            let _ = self.database.set_retranscribe_status(job_id, "queued", None);
+            progress.finish(Err(anyhow::anyhow!("Failed to start the re-transcription worker")));
+            self.state = EngineState::Idle;
+            return;
+        }
+        self.retranscribe_job = Some(progress);
+    }
+
+    // The engine needs the ASR slot back: the job goes back to the queue and runs again later
+    // (unless it got to the end first)
+    fn cancel_retranscription(&mut self) {
+        if let Some(outcome) = self.retranscriber.cancel() {
+            self.end_retranscription(outcome, false);
+        }
+    }
+
+    fn collect_retranscription(&mut self) {
+        if let Some(outcome) = self.retranscriber.poll() {
+            self.end_retranscription(outcome, true);
+        }
+    }
+
+    // The outcome's bookkeeping only; the thread has already written the note
+    fn end_retranscription(&mut self, outcome: RetranscribeOutcome, user_cancelled: bool) {
+        let result = match outcome {
+            RetranscribeOutcome::Finished { note_id, .. } => Ok(Some(note_id.to_string())),
+            // F18: Cancelled by the user: not retried
+            RetranscribeOutcome::Cancelled { job_id } if user_cancelled => {
+                let _ = self.database.set_retranscribe_status(job_id, "cancelled", None);
+                Err(jobs::Cancelled.into())
+            }
+            RetranscribeOutcome::Cancelled { job_id } => {
+                let _ = self.database.set_retranscribe_status(job_id, "queued", None);
+                Err(jobs::Cancelled.into())
+            }
+            RetranscribeOutcome::Failed { job_id, error } => {
+                println!("Engine: Re-transcription job {} failed: {}", job_id, error);
+                let _ = self.database.fail_retranscribe_job(job_id, &error, RETRANSCRIBE_MAX_ATTEMPTS);
+                Err(anyhow::anyhow!(error))
+            }
+        };
+        if let Some(job) = self.retranscribe_job.take() {
+            job.finish(result);
+        }
+        self.state = EngineState::Idle;
+    }
+
+    // Synchronous form of start_summary, for callers that own the engine (tests, benchmarks)
     pub fn stop_recording_and_summarize(&mut self, append_to: Option<i64>) -> String {
+        match self.start_summary(append_to, Box::new(|_| true)) {
+            Ok((job_id, summary)) => {
+                self.wait_job(job_id);
+                summary.recv().unwrap_or_default()
+            }
+            Err(e) => {
+                println!("Engine: {}", e);
+                String::new()
+            }
+        }
+    }
+
+    // F2: `on_token` sees the summary as it is generated (return false to cut it short); the
+    // receiver gets the summary that was saved.
+    // F18: Runs as a Summary job on its own thread, so the engine lock is only held for the swap;
+    // cancelling it cuts the summary short, the note is still saved. Stopping ends the recording:
+    // the engine is Idle once the job is done, and only start_recording loads ASR again.
+    pub fn start_summary(&mut self, append_to: Option<i64>, mut on_token: TokenSink) -> anyhow::Result<(i64, Receiver<String>)> {
+        self.collect_model_job();
+        if self.model_job.is_some() {
+            return Err(anyhow::anyhow!("Engine is busy ({:?}), stop again in a moment", self.state));
+        }
         println!("Engine: Triggering Summary Swap...");
-        
-        // 1. Unload ASR
-        self.model_manager.unload_asr();
+        self.cancel_retranscription();
+        self.recorder.stop();
+
+        // 0. The worker goes with the session: the job waits for its queued chunks, off the engine lock,
+        // so the last seconds make it into the summary. The next session gets a fresh worker.
+        let asr = std::mem::replace(&mut self.asr_worker, AsrWorker::spawn(self.model_manager.clone(), ASR_QUEUE_DEPTH));
         self.state = EngineState::Summarizing;
-        
-        // 2. Load LLM
-        self.model_manager.load_llm();
-        
-        // 2.5 Load RAG (Persistent)
-        self.model_manager.load_rag();
-        
-        // 3. Prepare Prompt & RAG Context
-        let context_text = self.buffer.get_context();
-        let lang = self.lang_detector.detect(context_text);
-        
-        let mut final_input = context_text.to_string();
-        
-        println!("RAG: Generating embedding via ModelManager...");
-        if let Some(embedding) = self.model_manager.embed(context_text) {
-             // Search for similar notes
-             if let Ok(similar) = self.database.search_similar_notes(&embedding, 3) {
-                 if !similar.is_empty() {
-                     let mut context_str = String::from("\n\n---\nContext from past notes:\n");
-                     for (id, score) in similar {
-                         if let Ok((_, title, content, _)) = self.database.get_note(id) {
-                             // Only include if score > 0.5
-                             if score > 0.4 {
-                                context_str.push_str(&format!("- [{}] (Similarity: {:.2}): {}\n", title, score, content.lines().take(2).collect::<Vec<_>>().join(" ")));
-                             }
-                         }
-                     }
-                     final_input.push_str(&context_str);
-                     println!("RAG: Injected {} bytes of context.", context_str.len());
-                 }
-             }
-         }
-        
-        // 4. Run Summary with enriched context
-        let summary = self.model_manager.summarize(&final_input);
-        println!("Summary generated [{}]: {}", lang, summary);
-        
-        // 5. Unload LLM
-        self.model_manager.unload_llm();
-        
-        // 6. Save to DB
-        let note_id = match append_to {
-            Some(id) => {
-                if let Ok((_id, title, existing_content, _updated)) = self.database.get_note(id) {
-                     let new_content = format!("{}\n\n---\n\n{}", existing_content, summary);
-                     let _ = self.database.update_note(id, &title, &new_content);
-                     println!("Note {} updated with new summary.", id);
-                     Some(id)
-                } else { None }
-            },
-            None => {
-                match self.database.add_note(&format!("Note {}", chrono::Utc::now().timestamp()), &summary, self.current_folder_id) {
-                     Ok(id) => {
-                         println!("Note saved to DB: {}", id);
-                         Some(id)
-                     },
-                     Err(e) => {
-                         println!("Error saving note: {}", e);
-                         None
-                     }
-                }
-            }
+        // Endurance: extractive only
+        let mode = self.endurance.check_status();
+        self.low_power.store(mode == EnduranceMode::Endurance, Ordering::Relaxed);
+
+        // The session moves to the job; the engine starts the next one
+        let session = SummarySession {
+            db_path: self.db_path.clone(),
+            models: self.model_manager.clone(),
+            filter: self.hallucination_filter.clone(),
+            generation: self.generation.clone(),
+            summary_language: self.summary_language.clone(),
+            summary_track: self.summary_track,
+            subject: self.current_subject.clone(),
+            folder_id: self.current_folder_id,
+            session_id: self.session_id,
+            asr,
+            original: self.buffer.clone(),
+            translation: self.translation_buffer.clone(),
+            segments: std::mem::take(&mut self.session_segments),
+            partials: std::mem::take(&mut self.partial_summaries),
+            window_start: self.summary_window_start,
+            audio: std::mem::take(&mut self.full_session_audio),
+            append_to,
         };
-        
-        if let Some(id) = note_id {
-            if let Some(embedding) = self.model_manager.embed(context_text) {
-                 let _ = self.database.save_embedding(id, embedding);
-                 println!("RAG: Embedding saved for note {}.", id);
+        self.reset_session_summary();
+
+        let (sender, receiver) = mpsc::channel();
+        let job_id = jobs::spawn(JobKind::Summary, "Summarizing the lecture", move |job| {
+            let (summary, note_id) = summarize_session(session, job, &mut *on_token)?;
+            let _ = sender.send(summary);
+            match note_id {
+                Some(id) => Ok(Some(id.to_string())),
+                None => Err(anyhow::anyhow!("The note could not be saved")),
             }
-            
-            // 7.5 Save Full Audio (Persistence)
-            // Use current timestamp for unique filename
-            let filename = format!("recording_{}.wav", chrono::Utc::now().timestamp());
-            let path = std::path::Path::new("/tmp").join(&filename); // Ideally usage APP_DIR
-            
-            println!("Persistence: Saving {} samples to {:?}...", self.full_session_audio.len(), path);
-            
-            let spec = hound::WavSpec {
-                channels: 1,
-                sample_rate: 16000,
-                bits_per_sample: 16,
-                sample_format: hound::SampleFormat::Int,
-            };
-            
-            if let Ok(mut writer) = hound::WavWriter::create(&path, spec) {
-                // Convert f32 to i16
-                for &sample in &self.full_session_audio {
-                    let amplitude = sample * 32767.0;
-                    writer.write_sample(amplitude as i16).unwrap_or(());
-                }
-                writer.finalize().unwrap_or(());
-                
-                // Add as attachment
-                if let Some(path_str) = path.to_str() {
-                    let _ = self.database.add_attachment(id, "audio", path_str);
-                    println!("Persistence: Audio saved as attachment.");
-                }
-            } else {
-                println!("Persistence: Failed to create WAV file.");
+        });
+        // 8. Idle once the job is done
+        self.model_job = Some(job_id);
+        Ok((job_id, receiver))
+    }
+
+    // F18: Once the model job is done, the slot is free again
+    pub fn collect_model_job(&mut self) {
+        let Some(job_id) = self.model_job else { return };
+        if !jobs::get(job_id).is_none_or(|job| job.status.is_finished()) {
+            return;
+        }
+        self.model_job = None;
+        self.state = EngineState::Idle;
+    }
+
+    // Ticks until the job is done, for callers that own the engine rather than go through the API
+    fn wait_job(&mut self, job_id: i64) {
+        loop {
+            let job = jobs::wait(job_id, Duration::from_millis(100));
+            self.tick();
+            if job.is_none_or(|job| job.status.is_finished()) {
+                return;
             }
-            // Clear for next session
-            self.full_session_audio.clear();
         }
-        
-        // 7.6 Unload RAG
-        self.model_manager.unload_rag();
-        
-        // 8. Return to Recording
-        println!("Engine: Returning to Recording...");
-        self.model_manager.load_asr();
-        self.state = EngineState::Recording;
-        
-        summary
     }
 
     pub fn create_folder(&self, name: &str) -> anyhow::Result<i64> {
@@ -362,19 +638,290 @@ impl Engine {
         self.buffer.get_context().to_string()
     }
 
+    pub fn get_current_translation(&self) -> String {
+        self.translation_buffer.get_context().to_string()
+    }
+
+    pub fn get_transcript_versions(&self, note_id: i64) -> anyhow::Result<Vec<i64>> {
+        Ok(self.database.get_transcript_versions(note_id).map_err(|e| anyhow::anyhow!(e))?)
+    }
+
+    pub fn get_transcript_segments_version(&self, note_id: i64, track: TranscriptTrack, version: i64) -> anyhow::Result<Vec<TranscriptSegment>> {
+        Ok(self.database.get_transcript_segments_version(note_id, track, version).map_err(|e| anyhow::anyhow!(e))?)
+    }
+
+    // F1.5: Manual request; runs when the device is next charging and idle, between recordings
+    pub fn queue_retranscription(&self, note_id: i64) -> anyhow::Result<i64> {
+        let spoken = self.get_transcript_languages(note_id)?;
+        Ok(self.database.enqueue_retranscription(note_id, retranscribe_model(&spoken)).map_err(|e| anyhow::anyhow!(e))?)
+    }
+
+    // F17.2: Languages of the original transcript with their share of it, largest first
+    pub fn get_transcript_languages(&self, note_id: i64) -> anyhow::Result<Vec<(String, f32)>> {
+        let segments = self.get_transcript_segments(note_id, TranscriptTrack::Original)?;
+        Ok(transcript::language_shares(&segments, TranscriptTrack::Original))
+    }
+
+    pub fn get_retranscribe_jobs(&self, note_id: i64) -> anyhow::Result<Vec<(i64, String, i64, i64)>> {
+        Ok(self.database.get_retranscribe_jobs(note_id).map_err(|e| anyhow::anyhow!(e))?)
+    }
+
+    pub fn get_previous_summary(&self, job_id: i64) -> anyhow::Result<Option<String>> {
+        Ok(self.database.get_previous_summary(job_id).map_err(|e| anyhow::anyhow!(e))?)
+    }
+
+    // F3: Cloze cards from the note's defined terms, plus Q/A cards when a GGUF model is installed.
+    // Runs as a Flashcards job; its result is how many new cards were stored.
+    pub fn start_flashcards(&mut self, note_id: i64) -> anyhow::Result<i64> {
+        self.start_flashcards_for(vec![note_id])
+    }
+
+    pub fn start_folder_flashcards(&mut self, folder_id: i64) -> anyhow::Result<i64> {
+        let note_ids: Vec<i64> = self.get_notes_by_folder(folder_id)?.into_iter().map(|(id, _, _, _)| id).collect();
+        self.start_flashcards_for(note_ids)
+    }
+
+    fn start_flashcards_for(&mut self, note_ids: Vec<i64>) -> anyhow::Result<i64> {
+        self.collect_model_job();
+        // The Q/A cards need the LLM, which would take the live ASR's slot
+        if self.state == EngineState::Recording {
+            return Err(anyhow::anyhow!("Flashcards cannot be generated while recording"));
+        }
+        // The LLM is only loaded between sessions; a summary or re-transcription has the slot
+        let generative = self.state == EngineState::Idle;
+        let (db_path, models, generation) = (self.db_path.clone(), self.model_manager.clone(), self.generation.clone());
+        let job_id = jobs::spawn(JobKind::Flashcards, &format!("Flashcards for {} notes", note_ids.len()), move |job| {
+            let database = Database::open(&db_path, DB_PASSWORD).map_err(|e| anyhow::anyhow!(e))?;
+            let added = generate_flashcards(&database, &models, &generation, &note_ids, generative, job)?;
+            Ok(Some(added.to_string()))
+        });
+        if generative {
+            self.state = EngineState::Summarizing;
+            self.model_job = Some(job_id);
+        }
+        Ok(job_id)
+    }
+
+    // F2.7: Tags proposed for a note from its text and transcript, without storing them
+    pub fn suggest_tags(&self, note_id: i64) -> anyhow::Result<Vec<String>> {
+        let (_, _, content, _) = self.get_note(note_id)?;
+        let items = self.get_structured_summary(note_id)?.unwrap_or_else(|| StructuredSummary::parse(&content));
+        let segments = self.get_transcript_segments(note_id, TranscriptTrack::Original)?;
+        let text = format!("{}\n{}", items.items_text(), transcript::join_track(&segments, TranscriptTrack::Original));
+        Ok(keyphrase::propose_tags(&text, &self.lang_detector.detect(&text), keyphrase::AUTO_TAGS))
+    }
+
+    // Returns false if the note already had the tag
+    pub fn add_tag(&self, note_id: i64, name: &str) -> anyhow::Result<bool> {
+        let tag = keyphrase::normalize_tag(name)
+            .ok_or_else(|| anyhow::anyhow!("Tags are 1 to {} characters", keyphrase::MAX_TAG_CHARS))?;
+        self.get_note(note_id)?;
+        Ok(self.database.add_note_tags(note_id, &[tag], "user").map_err(|e| anyhow::anyhow!(e))? > 0)
+    }
+
+    // An automatic tag that is removed is not proposed for the note again
+    pub fn remove_tag(&self, note_id: i64, name: &str) -> anyhow::Result<bool> {
+        let Some(tag) = keyphrase::normalize_tag(name) else { return Ok(false) };
+        Ok(self.database.remove_note_tag(note_id, &tag).map_err(|e| anyhow::anyhow!(e))?)
+    }
+
+    // (tag, automatic)
+    pub fn get_note_tags(&self, note_id: i64) -> anyhow::Result<Vec<(String, bool)>> {
+        let tags = self.database.get_note_tags(note_id).map_err(|e| anyhow::anyhow!(e))?;
+        Ok(tags.into_iter().map(|(name, source)| (name, source == "auto")).collect())
+    }
+
+    pub fn get_tags(&self) -> anyhow::Result<Vec<(String, i64)>> {
+        Ok(self.database.get_tags().map_err(|e| anyhow::anyhow!(e))?)
+    }
+
+    pub fn get_notes_by_tag(&self, name: &str) -> anyhow::Result<Vec<(i64, String, String, i64)>> {
+        let Some(tag) = keyphrase::normalize_tag(name) else { return Ok(Vec::new()) };
+        Ok(self.database.get_notes_by_tag(&tag).map_err(|e| anyhow::anyhow!(e))?)
+    }
+
+    // F2.6: Retitles the notes of a folder (or all notes); with `only_untitled`, just those still
+    // called "Note <time>". Runs as a Titles job; title_job_results has the new titles.
+    pub fn start_regenerate_titles(&mut self, folder_id: Option<i64>, only_untitled: bool) -> anyhow::Result<i64> {
+        let note_ids = self.database.get_note_ids(folder_id).map_err(|e| anyhow::anyhow!(e))?;
+        Ok(self.start_retitle(note_ids, only_untitled))
+    }
+
+    pub fn start_regenerate_note_title(&mut self, note_id: i64) -> anyhow::Result<i64> {
+        self.get_note(note_id)?;
+        Ok(self.start_retitle(vec![note_id], false))
+    }
+
+    // Same as the jobs above, waiting for them (for callers that own the engine)
+    pub fn regenerate_titles(&mut self, folder_id: Option<i64>, only_untitled: bool) -> anyhow::Result<Vec<(i64, String)>> {
+        let job_id = self.start_regenerate_titles(folder_id, only_untitled)?;
+        self.wait_job(job_id);
+        self.title_job_results(job_id)
+    }
+
+    pub fn regenerate_note_title(&mut self, note_id: i64) -> anyhow::Result<String> {
+        let job_id = self.start_regenerate_note_title(note_id)?;
+        self.wait_job(job_id);
+        self.title_job_results(job_id)?
+            .pop()
+            .map(|(_, title)| title)
+            .ok_or_else(|| anyhow::anyhow!("Note {} not found", note_id))
+    }
+
+    // F2.6: The notes a finished Titles job retitled, with their new titles
+    pub fn title_job_results(&self, job_id: i64) -> anyhow::Result<Vec<(i64, String)>> {
+        let job = jobs::get(job_id).ok_or_else(|| anyhow::anyhow!("Unknown job {}", job_id))?;
+        match (job.kind, &job.result, &job.status) {
+            (JobKind::Titles, Some(result), _) => Ok(serde_json::from_str(result)?),
+            (_, _, jobs::JobStatus::Failed(error)) => Err(anyhow::anyhow!(error.clone())),
+            _ => Err(anyhow::anyhow!("Job {} has no titles ({})", job_id, job.status.as_str())),
+        }
+    }
+
+    fn start_retitle(&mut self, note_ids: Vec<i64>, only_untitled: bool) -> i64 {
+        self.collect_model_job();
+        // Same swap as a summary, so only between sessions; otherwise titles come from keyphrases
+        let generative = self.state == EngineState::Idle;
+        let (db_path, models, summary_language) = (self.db_path.clone(), self.model_manager.clone(), self.summary_language.clone());
+        let job_id = jobs::spawn(JobKind::Titles, &format!("Titling {} notes", note_ids.len()), move |job| {
+            let database = Database::open(&db_path, DB_PASSWORD).map_err(|e| anyhow::anyhow!(e))?;
+            let retitled = retitle(&database, &models, &note_ids, only_untitled, generative, summary_language, job)?;
+            Ok(Some(serde_json::to_string(&retitled)?))
+        });
+        if generative {
+            self.state = EngineState::Summarizing;
+            self.model_job = Some(job_id);
+        }
+        job_id
+    }
+
+    pub fn get_cards(&self, note_id: i64) -> anyhow::Result<Vec<Card>> {
+        Ok(self.database.get_cards(note_id).map_err(|e| anyhow::anyhow!(e))?)
+    }
+
+    pub fn delete_card(&self, card_id: i64) -> anyhow::Result<()> {
+        Ok(self.database.delete_card(card_id).map_err(|e| anyhow::anyhow!(e))?)
+    }
+
+    // F3.2: Study queue for one folder (or all of them)
+    pub fn get_due_cards(&self, folder_id: Option<i64>, limit: usize) -> anyhow::Result<Vec<Card>> {
+        let now = chrono::Utc::now().timestamp();
+        Ok(self.database.get_due_cards(folder_id, now, limit).map_err(|e| anyhow::anyhow!(e))?)
+    }
+
+    // (reviews due, new cards)
+    pub fn count_due_cards(&self, folder_id: Option<i64>) -> anyhow::Result<(usize, usize)> {
+        let now = chrono::Utc::now().timestamp();
+        Ok(self.database.count_due_cards(folder_id, now).map_err(|e| anyhow::anyhow!(e))?)
+    }
+
+    // `button` 1 = Again, 2 = Hard, 3 = Good, 4 = Easy. Returns the new schedule.
+    pub fn review_card(&self, card_id: i64, button: u8, duration_ms: i64) -> anyhow::Result<ReviewState> {
+        let grade = Grade::from_button(button)
+            .ok_or_else(|| anyhow::anyhow!("Invalid grade {}, expected 1 (Again) to 4 (Easy)", button))?;
+        let card = self.database.get_card(card_id).map_err(|e| anyhow::anyhow!(e))?
+            .ok_or_else(|| anyhow::anyhow!("Card {} not found", card_id))?;
+        let now = chrono::Utc::now().timestamp();
+        let state = card.review.review(grade, now);
+        self.database.record_review(card_id, grade, &state, duration_ms, now).map_err(|e| anyhow::anyhow!(e))?;
+        Ok(state)
+    }
+
+    // F3.1: `.apkg` or `.csv` by extension; every folder when `folder_id` is None. Returns the card count.
+    pub fn export_cards(&self, folder_id: Option<i64>, path: &str) -> anyhow::Result<usize> {
+        let cards = self.database.get_cards_with_titles(folder_id).map_err(|e| anyhow::anyhow!(e))?;
+        let path = std::path::Path::new(path);
+        match path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()).as_deref() {
+            Some("apkg") => {
+                let deck = folder_id
+                    .and_then(|id| self.get_folders().ok()?.into_iter().find(|(f, _)| *f == id))
+                    .map(|(_, name)| name)
+                    .unwrap_or_else(|| "TACTANOTES".to_string());
+                anki::write_apkg(path, &deck, &cards)?;
+            }
+            Some("csv") | Some("txt") => anki::write_csv(path, &cards)?,
+            _ => return Err(anyhow::anyhow!("Unsupported export format {:?}, use .apkg or .csv", path)),
+        }
+        Ok(cards.len())
+    }
+
+    // F2.3: Templates must contain {{transcript}} and only known variables
+    pub fn create_prompt_template(&self, name: &str, body: &str) -> anyhow::Result<i64> {
+        prompt::validate(body)?;
+        Ok(self.database.create_prompt_template(name, body).map_err(|e| anyhow::anyhow!(e))?)
+    }
+
+    pub fn update_prompt_template(&self, template_id: i64, name: &str, body: &str) -> anyhow::Result<()> {
+        prompt::validate(body)?;
+        Ok(self.database.update_prompt_template(template_id, name, body).map_err(|e| anyhow::anyhow!(e))?)
+    }
+
+    pub fn delete_prompt_template(&self, template_id: i64) -> anyhow::Result<()> {
+        Ok(self.database.delete_prompt_template(template_id).map_err(|e| anyhow::anyhow!(e))?)
+    }
+
+    pub fn get_prompt_templates(&self) -> anyhow::Result<Vec<(i64, String, String)>> {
+        Ok(self.database.get_prompt_templates().map_err(|e| anyhow::anyhow!(e))?)
+    }
+
+    pub fn set_folder_template(&self, folder_id: i64, template_id: Option<i64>) -> anyhow::Result<()> {
+        Ok(self.database.set_folder_template(folder_id, template_id).map_err(|e| anyhow::anyhow!(e))?)
+    }
+
+    pub fn get_partial_summaries(&self, note_id: i64) -> anyhow::Result<Vec<PartialSummary>> {
+        Ok(self.database.get_partial_summaries(note_id).map_err(|e| anyhow::anyhow!(e))?)
+    }
+
+    pub fn get_structured_summary(&self, note_id: i64) -> anyhow::Result<Option<StructuredSummary>> {
+        Ok(self.database.get_structured_summary(note_id).map_err(|e| anyhow::anyhow!(e))?)
+    }
+
+    // F2.1: Notes with action items, across every folder or one folder (course)
+    pub fn get_action_items(&self, folder_id: Option<i64>) -> anyhow::Result<Vec<NoteSummary>> {
+        let summaries = self.database.get_structured_summaries().map_err(|e| anyhow::anyhow!(e))?;
+        Ok(summaries.into_iter()
+            .filter(|n| folder_id.is_none() || n.folder_id == folder_id)
+            .filter(|n| !n.summary.action_items.is_empty())
+            .collect())
+    }
+
+    // F17: Why the last model load failed (None when everything loaded)
+    pub fn get_model_error(&self) -> Option<String> {
+        self.model_status.read().ok().and_then(|status| status.last_error.clone())
+    }
+
+    // Manifest entries with their install state
+    pub fn list_models(&self) -> Vec<(ModelEntry, bool)> {
+        self.model_status.read().map(|status| status.models.clone()).unwrap_or_default()
+    }
+
+    // F1.4: Live segments with confidence, for the recording screen
+    pub fn get_session_segments(&self, track: TranscriptTrack) -> Vec<TranscriptSegment> {
+        self.session_segments.iter().filter(|s| s.track == track).cloned().collect()
+    }
+
+    pub fn get_transcript_segments(&self, note_id: i64, track: TranscriptTrack) -> anyhow::Result<Vec<TranscriptSegment>> {
+        Ok(self.database.get_transcript_segments(note_id, track).map_err(|e| anyhow::anyhow!(e))?)
+    }
+
     // Feature F16: Semantic Search
     pub fn search_notes(&mut self, query: &str) -> anyhow::Result<Vec<(i64, String, String, i64)>> {
-        println!("Engine: Searching for '{}'...", query);
+        self.search_notes_in_track(query, TranscriptTrack::Original)
+    }
+
+    // F1.2: Search against the original or the English translation track
+    pub fn search_notes_in_track(&mut self, query: &str, track: TranscriptTrack) -> anyhow::Result<Vec<(i64, String, String, i64)>> {
+        println!("Engine: Searching for '{}' ({} track)...", query, track.as_str());
         
         // 1. Load RAG
-        self.model_manager.load_rag();
+        self.models().load_rag();
         
         let mut results = Vec::new();
         
         // 2. Embed Query
-        if let Some(embedding) = self.model_manager.embed(query) {
+        if let Some(embedding) = self.models().embed(query) {
              // 3. Vector Search
-             if let Ok(similar) = self.database.search_similar_notes(&embedding, 10) {
+             if let Ok(similar) = self.database.search_similar_notes_in_track(&embedding, track, SEARCH_LIMIT) {
                  for (id, _score) in similar {
                      // 4. Fetch Details
                      if let Ok(note) = self.database.get_note(id) {
@@ -385,8 +932,856 @@ impl Engine {
         }
         
         // 5. Unload RAG
-        self.model_manager.unload_rag();
+        self.models().unload_rag();
         
         Ok(results)
     }
+
+    // F18: Semantic search on a job thread. The result is the JSON list of matching note ids, best first.
+    pub fn start_search(&self, query: &str, track: TranscriptTrack) -> i64 {
+        let (db_path, models, query) = (self.db_path.clone(), self.model_manager.clone(), query.trim().to_string());
+        jobs::spawn(JobKind::Search, &format!("Searching for '{}'", query), move |job| {
+            let database = Database::open(&db_path, DB_PASSWORD).map_err(|e| anyhow::anyhow!(e))?;
+            job.progress(0, 2, "Embedding the query");
+            let embedding = lock_models(&models).embed_borrowed(std::slice::from_ref(&query))?.pop().flatten()
+                .ok_or_else(|| anyhow::anyhow!("No embedding model installed"))?;
+            job.check()?;
+            job.progress(1, 2, "Ranking notes");
+            let note_ids: Vec<i64> = database.search_similar_notes_in_track(&embedding, track, SEARCH_LIMIT)
+                .map_err(|e| anyhow::anyhow!(e))?
+                .into_iter()
+                .map(|(id, _)| id)
+                .collect();
+            job.progress(2, 2, "Done");
+            Ok(Some(serde_json::to_string(&note_ids)?))
+        })
+    }
+
+    // F18: Notes found by a finished search job, best first
+    pub fn search_job_results(&self, job_id: i64) -> anyhow::Result<Vec<(i64, String, String, i64)>> {
+        let job = jobs::get(job_id).ok_or_else(|| anyhow::anyhow!("Unknown job {}", job_id))?;
+        let note_ids: Vec<i64> = match (job.kind, &job.result) {
+            (JobKind::Search, Some(result)) => serde_json::from_str(result)?,
+            _ => return Err(anyhow::anyhow!("Job {} has no search results ({})", job_id, job.status.as_str())),
+        };
+        Ok(note_ids.into_iter().filter_map(|id| self.database.get_note(id).ok()).collect())
+    }
+
+    // F18: Re-embeds the notes of a folder (or all notes) and their passages on a job thread, e.g. after
+    // a new embedding model was installed. The result is the number of notes embedded.
+    pub fn start_reindex(&self, folder_id: Option<i64>) -> i64 {
+        let (db_path, models) = (self.db_path.clone(), self.model_manager.clone());
+        jobs::spawn(JobKind::Reindex, "Re-embedding notes", move |job| {
+            let database = Database::open(&db_path, DB_PASSWORD).map_err(|e| anyhow::anyhow!(e))?;
+            let note_ids = database.get_note_ids(folder_id).map_err(|e| anyhow::anyhow!(e))?;
+            let total = note_ids.len() as u64;
+            let mut embedded = 0;
+            for (i, &note_id) in note_ids.iter().enumerate() {
+                job.check()?;
+                job.progress(i as u64, total, &format!("Note {} of {}", i + 1, total));
+                if reindex_note(&database, &models, note_id)? {
+                    embedded += 1;
+                }
+            }
+            job.progress(total, total, "Done");
+            if embedded == 0 && total > 0 {
+                return Err(anyhow::anyhow!("No embedding model installed"));
+            }
+            Ok(Some(embedded.to_string()))
+        })
+    }
+
+    // F18: Model pack install (F17.1) on a job thread. The result is the JSON list of installed model
+    // ids; a cancelled install keeps its staging directory and resumes on the next try.
+    pub fn start_import(&self, archive_path: &str) -> i64 {
+        let device = self.models().registry().manifest().clone();
+        let (models, models_dir, archive) = (self.model_manager.clone(), self.models_dir.clone(), archive_path.to_string());
+        jobs::spawn(JobKind::Import, &format!("Importing {}", archive_path), move |job| {
+            let installed = install::install_pack_with_progress(
+                std::path::Path::new(&archive),
+                std::path::Path::new(&models_dir),
+                &device,
+                &mut |done, total, step| {
+                    job.progress(done as u64, total as u64, step);
+                    !job.is_cancelled()
+                },
+            );
+            let report = match installed {
+                Err(install::InstallError::Cancelled) => return Err(jobs::Cancelled.into()),
+                installed => installed?,
+            };
+            lock_models(&models).reload_registry()?;
+            Ok(Some(serde_json::to_string(&report.installed)?))
+        })
+    }
+
+    // F2.4: Answers from the notes in one folder (or all of them) as an Answer job. `on_token` sees the
+    // answer as it is written; the receiver gets it with the passages it cites, in order of first citation.
+    pub fn start_ask(&mut self, question: &str, folder_id: Option<i64>, mut on_token: TokenSink) -> anyhow::Result<(i64, Receiver<(String, Vec<Citation>)>)> {
+        let question = question.trim();
+        if question.is_empty() {
+            return Err(anyhow::anyhow!("Question is empty"));
+        }
+        self.collect_model_job();
+        // Retrieval and the LLM need the model slot, which is the live ASR's while recording
+        if self.state == EngineState::Recording {
+            return Err(anyhow::anyhow!("Questions cannot be answered while recording"));
+        }
+        if self.state != EngineState::Idle {
+            return Err(anyhow::anyhow!("Engine is busy ({:?}), ask again in a moment", self.state));
+        }
+        println!("Engine: Answering '{}'...", question);
+        let options = GenerationOptions { prompt: Some(qa::answer_prompt(question)), ..self.generation.clone() };
+        let (db_path, models, asked) = (self.db_path.clone(), self.model_manager.clone(), question.to_string());
+        let (sender, receiver) = mpsc::channel();
+        let job_id = jobs::spawn(JobKind::Answer, question, move |job| {
+            let answer = answer_question(&db_path, &models, &options, &asked, folder_id, job, &mut *on_token)?;
+            let _ = sender.send(answer);
+            Ok(None)
+        });
+        self.state = EngineState::Summarizing;
+        self.model_job = Some(job_id);
+        Ok((job_id, receiver))
+    }
+}
+
+// F18: A finished recording session, handed from the engine to its Summary job
+struct SummarySession {
+    db_path: String,
+    models: Arc<Mutex<ModelManager>>,
+    filter: HallucinationFilter,
+    generation: GenerationOptions,
+    summary_language: Option<String>,
+    summary_track: TranscriptTrack,
+    subject: String,
+    folder_id: Option<i64>,
+    session_id: i64,
+    asr: AsrWorker,      // Still decoding the last chunks of the session
+    original: RollingBuffer,
+    translation: RollingBuffer,
+    segments: Vec<TranscriptSegment>,
+    partials: Vec<PartialSummary>,
+    window_start: usize, // Segments before it are in `partials`
+    audio: Vec<f32>,
+    append_to: Option<i64>,
+}
+
+// Summarizes and saves the session on the Summary job's thread, with its own database connection.
+// Returns the summary and the note it was saved to.
+fn summarize_session(mut s: SummarySession, job: &Job, on_token: &mut dyn FnMut(&str) -> bool) -> anyhow::Result<(String, Option<i64>)> {
+    const STEPS: u64 = 5;
+    let database = Database::open(&s.db_path, DB_PASSWORD).map_err(|e| anyhow::anyhow!(e))?;
+    let detector = LanguageDetector::new();
+
+    // 0. Chunks that are not back in time are dropped rather than landing in the next session
+    job.progress(0, STEPS, "Transcribing the last seconds");
+    for result in s.asr.drain(Duration::from_secs(30)) {
+        ingest_asr_result(&s.filter, &mut s.original, &mut s.translation, &mut s.segments, result);
+    }
+    s.asr.discard_pending();
+    drop(s.asr);
+
+    // 1. Unload ASR
+    // An English-only model writes everything in English, so its text says nothing of the language spoken
+    let multilingual_asr = {
+        let mut models = lock_models(&s.models);
+        let multilingual = models.asr_capabilities().is_some_and(|c| c.languages.is_empty());
+        models.unload_asr();
+        multilingual
+    };
+
+    // 2. Load LLM and RAG
+    job.progress(1, STEPS, "Loading the summary model");
+    {
+        let mut models = lock_models(&s.models);
+        models.load_llm();
+        models.load_rag();
+    }
+
+    // 3. Prepare Prompt & RAG Context
+    // F1.2: Summarize the chosen track (falls back to the original if nothing was translated)
+    let (summary_track, context_text) = match s.summary_track {
+        TranscriptTrack::Translation if !s.translation.get_context().trim().is_empty() => {
+            (TranscriptTrack::Translation, s.translation.get_context().to_string())
+        }
+        _ => (TranscriptTrack::Original, s.original.get_context().to_string()),
+    };
+    // F17.2: Every segment gets its own language; the session's is the one most of it is in
+    label_languages(&detector, &mut s.segments, multilingual_asr);
+    let shares = transcript::language_shares(&s.segments, summary_track);
+    let spoken = transcript::language_shares(&s.segments, TranscriptTrack::Original);
+    let lang = shares.first().map(|(code, _)| code.clone()).unwrap_or_else(|| detector.detect(&context_text));
+    // F2.8: The summary is written in the lecture's language unless the user picked one
+    let summary_locale = summary_locale(s.summary_language.as_deref(), &lang);
+
+    // F2.2: The whole session, not just the rolling buffer. Short sessions go in as they are;
+    // longer ones as the reduced window summaries.
+    let session_text = transcript::join_track(&s.segments, summary_track);
+    let transcript_text = if session_text.trim().is_empty() { context_text.clone() } else { session_text };
+    let final_input = if s.partials.is_empty() && transcript_text.len() <= REDUCE_BUDGET_CHARS {
+        transcript_text.clone()
+    } else {
+        job.progress(2, STEPS, "Summarizing the lecture in parts");
+        s.partials.extend(summarize_windows(&s.filter, &s.segments[s.window_start..], s.summary_track, true));
+        let options = mapreduce::window_options();
+        let models = lock_models(&s.models);
+        mapreduce::reduce(&s.partials, REDUCE_BUDGET_CHARS, &mut |group| {
+            // Cancelled: groups pass through unreduced and the reduce step cuts them to size
+            if job.is_cancelled() { return group.to_string(); }
+            models.summarize_stream(group, &[], &options, &mut |_| !job.is_cancelled())
+        })
+    };
+
+    job.progress(3, STEPS, "Finding related notes");
+    let mut rag_context = String::new();
+    println!("RAG: Generating embedding via ModelManager...");
+    let embedding = lock_models(&s.models).embed(&context_text);
+    if let Some(embedding) = embedding {
+         // Search for similar notes
+         if let Ok(similar) = database.search_similar_notes(&embedding, 3) {
+             for (id, score) in similar {
+                 if let Ok((_, title, content, _)) = database.get_note(id) {
+                     // Only include if score > 0.4
+                     if score > 0.4 {
+                        rag_context.push_str(&format!("- [{}] (Similarity: {:.2}): {}\n", title, score, content.lines().take(2).collect::<Vec<_>>().join(" ")));
+                     }
+                 }
+             }
+             if !rag_context.is_empty() {
+                 rag_context.insert_str(0, "Context from past notes:\n");
+                 println!("RAG: Injected {} bytes of context.", rag_context.len());
+             }
+         }
+     }
+
+    // 4. Run Summary with the folder's prompt template (F2.3)
+    let options = GenerationOptions {
+        prompt: Some(summary_prompt(&database, s.folder_id, summary_locale, &shares)),
+        subject: s.subject.clone(),
+        // F2.5: Rendered by the summarizer, so the prompt budget can trim it first
+        context: rag_context,
+        language: Some(summary_locale.code.to_string()),
+        ..s.generation.clone()
+    };
+    // F1.4: Segments the filter would flag are downweighted by the summarizer
+    let low_confidence = low_confidence_texts(&s.filter, &s.segments, summary_track);
+    job.progress(4, STEPS, "Writing the summary");
+    let summary = lock_models(&s.models).summarize_stream(&final_input, &low_confidence, &options, &mut |token| {
+        !job.is_cancelled() && on_token(token)
+    });
+    // F2.1: Sections the summary left out are filled from the session transcript (not the RAG context)
+    let structured = StructuredSummary::build(&summary, &transcript_text);
+    let rendered = structured.render_in(summary_locale);
+    if let Some(extra) = rendered.strip_prefix(summary.as_str()) {
+        if !extra.is_empty() { on_token(extra); }
+    }
+    let summary = if rendered.is_empty() { summary } else { rendered };
+    println!("Summary generated [{} -> {}]: {}", lang, summary_locale.code, summary);
+    // F2.6: New notes are titled while the LLM is still loaded
+    let title = s.append_to.is_none().then(|| {
+        let models = (!job.is_cancelled()).then_some(&*s.models);
+        note_title(models, &summary, &transcript_text, &s.subject, chrono::Utc::now().timestamp(), &lang, summary_locale)
+    });
+
+    // 5. Unload LLM
+    lock_models(&s.models).unload_llm();
+
+    // 6. Save to DB
+    job.progress(5, STEPS, "Saving the note");
+    let note_id = match s.append_to {
+        Some(id) => {
+            if let Ok((_id, title, existing_content, _updated)) = database.get_note(id) {
+                 let new_content = format!("{}\n\n---\n\n{}", existing_content, summary);
+                 let _ = database.update_note(id, &title, &new_content);
+                 let mut combined = database.get_structured_summary(id).ok().flatten().unwrap_or_default();
+                 combined.merge(structured);
+                 let _ = database.save_structured_summary(id, &combined);
+                 println!("Note {} updated with new summary.", id);
+                 Some(id)
+            } else { None }
+        },
+        None => {
+            match database.add_note(title.as_deref().unwrap_or_default(), &summary, s.folder_id) {
+                 Ok(id) => {
+                     let _ = database.save_structured_summary(id, &structured);
+                     println!("Note saved to DB: {}", id);
+                     Some(id)
+                 },
+                 Err(e) => {
+                     println!("Error saving note: {}", e);
+                     None
+                 }
+            }
+        }
+    };
+
+    if let Some(id) = note_id {
+        // F1.2: One embedding per track so search can target either language
+        for (track, text) in [
+            (TranscriptTrack::Original, s.original.get_context()),
+            (TranscriptTrack::Translation, s.translation.get_context()),
+        ] {
+            if text.trim().is_empty() { continue; }
+            let embedding = lock_models(&s.models).embed(text);
+            if let Some(embedding) = embedding {
+                 let _ = database.save_track_embedding(id, track, embedding);
+                 println!("RAG: {} embedding saved for note {}.", track.as_str(), id);
+            }
+        }
+
+        // F1.2: Persist both segment tracks
+        if let Err(e) = database.add_transcript_segments(id, &s.segments) {
+            println!("Error saving transcript segments: {}", e);
+        }
+        // F2.4: Passages for question answering, while the embedding model is loaded
+        index_note_chunks(&database, &s.models, id);
+        // F2.7: Tags from the note's summary items (an appended session adds to what was there)
+        // and the transcript
+        let items = database.get_structured_summary(id).ok().flatten().unwrap_or_default();
+        tag_note(&database, id, &items, &transcript_text, &lang);
+        // F2.2: Saved with the note; a session that never gets saved leaves nothing behind
+        if let Err(e) = database.add_partial_summaries(id, s.session_id, summary_track, &s.partials) {
+            println!("Error saving partial summaries: {}", e);
+        }
+
+        // 7.5 Save Full Audio (Persistence)
+        // Use current timestamp for unique filename
+        let filename = format!("recording_{}.wav", chrono::Utc::now().timestamp());
+        let path = std::path::Path::new("/tmp").join(&filename); // Ideally usage APP_DIR
+
+        println!("Persistence: Saving {} samples to {:?}...", s.audio.len(), path);
+
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 16000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+
+        if let Ok(mut writer) = hound::WavWriter::create(&path, spec) {
+            // Convert f32 to i16
+            for &sample in &s.audio {
+                let amplitude = sample * 32767.0;
+                writer.write_sample(amplitude as i16).unwrap_or(());
+            }
+            writer.finalize().unwrap_or(());
+
+            // Add as attachment
+            if let Some(path_str) = path.to_str() {
+                let _ = database.add_attachment(id, "audio", path_str);
+                println!("Persistence: Audio saved as attachment.");
+                // F1.5: Redo this session with the larger model once the phone is charging
+                let _ = database.enqueue_retranscription(id, retranscribe_model(&spoken));
+            }
+        } else {
+            println!("Persistence: Failed to create WAV file.");
+        }
+    }
+
+    // 7.6 Unload RAG
+    lock_models(&s.models).unload_rag();
+    Ok((summary, note_id))
+}
+
+// F2.4: The Answer job's work, on its own thread. `options` carries the answer prompt.
+fn answer_question(db_path: &str, models: &Arc<Mutex<ModelManager>>, options: &GenerationOptions, question: &str, folder_id: Option<i64>, job: &Job, on_token: &mut dyn FnMut(&str) -> bool) -> anyhow::Result<(String, Vec<Citation>)> {
+    let database = Database::open(db_path, DB_PASSWORD).map_err(|e| anyhow::anyhow!(e))?;
+
+    // 1. Retrieve. Notes changed since they were last indexed are indexed on a job thread and
+    // count from the next question on.
+    let query = {
+        let mut models = lock_models(models);
+        models.load_rag();
+        models.embed(question)
+    };
+    let stale = database.get_stale_chunk_notes(query.is_some()).unwrap_or_default();
+    if !stale.is_empty() {
+        start_indexing(db_path, models, stale);
+    }
+    job.progress(0, 0, "Searching notes");
+    let mut hits = match &query {
+        Some(vector) => database.search_similar_chunks(vector, folder_id, qa::TOP_K).unwrap_or_default()
+            .into_iter().filter(|(_, score)| *score >= qa::MIN_SCORE).collect(),
+        None => Vec::new(),
+    };
+    if hits.is_empty() {
+        // No embedding model, or nothing close enough: fall back to shared words
+        hits = qa::lexical_rank(question, database.get_chunks(folder_id).unwrap_or_default(), qa::TOP_K);
+    }
+    lock_models(models).unload_rag();
+    let titles: Vec<String> = hits.iter()
+        .map(|(chunk, _)| database.get_note(chunk.note_id).map(|(_, title, _, _)| title).unwrap_or_default())
+        .collect();
+
+    // 2. Answer: generative with citations, else the best matching sentences
+    let answer = if job.is_cancelled() {
+        String::new()
+    } else if hits.is_empty() {
+        on_token(qa::NO_ANSWER);
+        qa::NO_ANSWER.to_string()
+    } else {
+        job.progress(0, 0, "Writing the answer");
+        let mut models = lock_models(models);
+        models.load_llm();
+        let generated = models.generate_only(&qa::numbered_passages(&hits, &titles), options, &mut |token| {
+            !job.is_cancelled() && on_token(token)
+        });
+        let mut answer = generated.unwrap_or_else(|e| {
+            println!("QA: {}, answering with the best matching sentences.", e);
+            String::new()
+        });
+        if answer.trim().is_empty() && !job.is_cancelled() {
+            answer = qa::extractive_answer(question, &hits);
+            on_token(&answer);
+        }
+        models.unload_llm();
+        answer
+    };
+    job.check()?;
+
+    let citations = qa::cited(&answer, hits.len()).into_iter()
+        .map(|i| {
+            let (chunk, score) = &hits[i];
+            Citation {
+                note_id: chunk.note_id,
+                title: titles[i].clone(),
+                start_ms: chunk.start_ms,
+                end_ms: chunk.end_ms,
+                snippet: chunk.text.clone(),
+                score: *score,
+            }
+        })
+        .collect();
+    Ok((answer, citations))
+}
+
+// F2.6: The Titles job's work, on its own thread. Cancelling keeps the titles written so far.
+fn retitle(database: &Database, models: &Mutex<ModelManager>, note_ids: &[i64], only_untitled: bool, generative: bool, summary_language: Option<String>, job: &Job) -> anyhow::Result<Vec<(i64, String)>> {
+    let folders = database.get_folders().map_err(|e| anyhow::anyhow!(e))?;
+    let mut notes = Vec::new();
+    for &note_id in note_ids {
+        let (_, old_title, content, updated_at) = database.get_note(note_id).map_err(|e| anyhow::anyhow!(e))?;
+        let created_at = title::default_title_time(&old_title);
+        if only_untitled && created_at.is_none() { continue; }
+        // The folder is the subject (F8); older titles carry the recording time
+        let subject = database.get_note_folder(note_id).ok().flatten()
+            .and_then(|folder_id| folders.iter().find(|(id, _)| *id == folder_id))
+            .map(|(_, name)| name.clone())
+            .unwrap_or_default();
+        notes.push((note_id, content, subject, created_at.unwrap_or(updated_at)));
+    }
+    if notes.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    if generative {
+        lock_models(models).load_llm();
+    }
+    let detector = LanguageDetector::new();
+    let total = notes.len() as u64;
+    let mut retitled = Vec::new();
+    for (i, (note_id, content, subject, timestamp)) in notes.into_iter().enumerate() {
+        if job.is_cancelled() { break; }
+        job.progress(i as u64, total, "Writing titles");
+        let segments = database.get_transcript_segments(note_id, TranscriptTrack::Original).unwrap_or_default();
+        let transcript = transcript::join_track(&segments, TranscriptTrack::Original);
+        let lang = detector.detect(&format!("{}\n{}", content, transcript));
+        let locale = summary_locale(summary_language.as_deref(), &lang);
+        let new_title = note_title(generative.then_some(models), &content, &transcript, &subject, timestamp, &lang, locale);
+        match database.update_note(note_id, &new_title, &content) {
+            Ok(()) => retitled.push((note_id, new_title)),
+            Err(e) => println!("Titles: Failed to retitle note {}: {}", note_id, e),
+        }
+    }
+    if generative {
+        lock_models(models).unload_llm();
+    }
+    println!("Titles: {} notes retitled.", retitled.len());
+    Ok(retitled)
+}
+
+// F3: The Flashcards job's work, on its own thread. Cancelling stores nothing.
+fn generate_flashcards(database: &Database, models: &Mutex<ModelManager>, generation: &GenerationOptions, note_ids: &[i64], generative: bool, job: &Job) -> anyhow::Result<usize> {
+    let mut notes = Vec::new();
+    for &note_id in note_ids {
+        let (_, _, content, _) = database.get_note(note_id).map_err(|e| anyhow::anyhow!(e))?;
+        let structured = database.get_structured_summary(note_id).map_err(|e| anyhow::anyhow!(e))?
+            .unwrap_or_else(|| StructuredSummary::build(&content, &content)); // Notes from before F2.1
+        notes.push((note_id, content, cards::cloze_cards(&structured)));
+    }
+
+    if generative {
+        let mut models = lock_models(models);
+        models.load_llm();
+        if models.llm_backend() == Some("llama.cpp") {
+            let options = GenerationOptions { prompt: Some(cards::qa_prompt()), ..generation.clone() };
+            let total = notes.len() as u64;
+            for (i, (_, content, note_cards)) in notes.iter_mut().enumerate() {
+                if job.is_cancelled() { break; }
+                job.progress(i as u64, total, "Writing questions");
+                let output = models.summarize_stream(content, &[], &options, &mut |_| !job.is_cancelled());
+                note_cards.extend(cards::parse_qa(&output));
+            }
+        }
+        models.unload_llm();
+    }
+    job.check()?;
+
+    let mut added = 0;
+    for (note_id, _, note_cards) in &notes {
+        added += database.add_cards(*note_id, note_cards).map_err(|e| anyhow::anyhow!(e))?;
+    }
+    println!("Flashcards: {} new cards for {} notes.", added, notes.len());
+    Ok(added)
+}
+
+// F1.5: What the re-transcription thread needs to finish a job away from the engine
+struct RetranscribeContext {
+    db_path: String,
+    models: Arc<Mutex<ModelManager>>,
+    filter: HallucinationFilter,
+    generation: GenerationOptions,
+    summary_language: Option<String>,
+}
+
+// Writes the new transcript as the latest version, then re-summarizes and re-embeds the note.
+// Runs on the re-transcription thread. Everything is worked out before the first write, so a job
+// cancelled part-way leaves the note as it was.
+fn apply_retranscription(ctx: &RetranscribeContext, job_id: i64, note_id: i64, sessions: &[i64], segments: Vec<AsrSegment>, progress: &Job) -> anyhow::Result<()> {
+    let database = Database::open(&ctx.db_path, DB_PASSWORD).map_err(|e| anyhow::anyhow!(e))?;
+    progress.progress(0, 0, "Summarizing the new transcript");
+    let mut context = RollingBuffer::new(8000);
+    let mut cleaned = Vec::new();
+    for segment in segments {
+        let Some(segment) = clean_segment(&ctx.filter, segment, context.get_context(), "Re-transcribed") else { continue };
+        context.push(&segment.text);
+        cleaned.push(TranscriptSegment {
+            track: TranscriptTrack::Original,
+            start_ms: segment.start_ms,
+            end_ms: segment.end_ms,
+            text: segment.text,
+            avg_token_prob: segment.avg_token_prob,
+            min_token_prob: segment.min_token_prob,
+            language: segment.language,
+        });
+    }
+    if cleaned.is_empty() {
+        return Err(anyhow::anyhow!("Re-transcription produced no text"));
+    }
+
+    let (_, title, previous_summary, _) = database.get_note(note_id).map_err(|e| anyhow::anyhow!(e))?;
+    let detector = LanguageDetector::new();
+    label_languages(&detector, &mut cleaned, true);
+
+    let text = transcript::join_track(&cleaned, TranscriptTrack::Original);
+    let low_confidence = low_confidence_texts(&ctx.filter, &cleaned, TranscriptTrack::Original);
+    // The subject is not stored with the note; the folder (course) name stands in for it
+    let folder_id = database.get_note_folder(note_id).map_err(|e| anyhow::anyhow!(e))?;
+    let subject = folder_id
+        .and_then(|id| database.get_folders().ok()?.into_iter().find(|(f, _)| *f == id))
+        .map(|(_, name)| name)
+        .unwrap_or_default();
+    // F17.2: The language most of the lecture is in
+    let shares = transcript::language_shares(&cleaned, TranscriptTrack::Original);
+    let lang = shares.first().map(|(code, _)| code.clone()).unwrap_or_else(|| detector.detect(&text));
+    let summary_locale = summary_locale(ctx.summary_language.as_deref(), &lang);
+    let options = GenerationOptions {
+        prompt: Some(summary_prompt(&database, folder_id, summary_locale, &shares)),
+        subject,
+        language: Some(summary_locale.code.to_string()),
+        ..ctx.generation.clone()
+    };
+    let windows = mapreduce::windows(&cleaned, TranscriptTrack::Original, WINDOW_CHARS);
+    let mut partials = Vec::new();
+    let running = || !progress.is_cancelled();
+    let summary = {
+        let mut models = lock_models(&ctx.models);
+        models.load_llm();
+        // F2.2: Long sessions are summarized window by window, then reduced
+        let input = if windows.len() <= 1 {
+            text.clone()
+        } else {
+            let options = mapreduce::window_options();
+            for (start_ms, end_ms, window) in &windows {
+                let summary = models.summarize_stream(window, &low_confidence, &options, &mut |_| running());
+                partials.push(PartialSummary { start_ms: *start_ms, end_ms: *end_ms, summary });
+            }
+            mapreduce::reduce(&partials, REDUCE_BUDGET_CHARS, &mut |group| models.summarize_stream(group, &[], &options, &mut |_| running()))
+        };
+        let summary = models.summarize_stream(&input, &low_confidence, &options, &mut |_| running());
+        models.unload_llm();
+        summary
+    };
+    if !running() {
+        return Err(jobs::Cancelled.into());
+    }
+    if summary.trim().is_empty() {
+        return Err(anyhow::anyhow!("Re-summarization produced no text"));
+    }
+    let structured = StructuredSummary::build(&summary, &text);
+    let rendered = structured.render_in(summary_locale);
+    let embedding = {
+        let mut models = lock_models(&ctx.models);
+        models.load_rag();
+        let embedding = models.embed(&text);
+        models.unload_rag();
+        embedding
+    };
+    if !running() {
+        return Err(jobs::Cancelled.into());
+    }
+
+    let version = database.add_transcript_version(note_id, &cleaned).map_err(|e| anyhow::anyhow!(e))?;
+    database.update_note(note_id, &title, &rendered).map_err(|e| anyhow::anyhow!(e))?;
+    database.save_structured_summary(note_id, &structured).map_err(|e| anyhow::anyhow!(e))?;
+    let session_id = chrono::Utc::now().timestamp_millis();
+    database.replace_partial_summaries(note_id, sessions, session_id, TranscriptTrack::Original, &partials).map_err(|e| anyhow::anyhow!(e))?;
+    tag_note(&database, note_id, &structured, &text, &lang);
+    if let Some(embedding) = embedding {
+        let _ = database.save_embedding(note_id, embedding);
+    }
+    database.complete_retranscribe_job(job_id, &previous_summary).map_err(|e| anyhow::anyhow!(e))?;
+    println!("Engine: Note {} re-transcribed (transcript version {}).", note_id, version);
+    Ok(())
+}
+
+// Filter + casing for one segment; None if the filter drops it
+fn ingest_asr_result(filter: &HallucinationFilter, original: &mut RollingBuffer, translation: &mut RollingBuffer, into: &mut Vec<TranscriptSegment>, result: AsrResult) {
+    let kept = ingest_segments(filter, original, into, result.segments, TranscriptTrack::Original, "Transcribed");
+    // A translation of audio whose original was rejected is a hallucination too
+    if kept > 0 {
+        ingest_segments(filter, translation, into, result.translation, TranscriptTrack::Translation, "Translated");
+    }
+}
+
+// F1.1: Every ASR segment goes through the hallucination filter before RollingBuffer
+fn ingest_segments(filter: &HallucinationFilter, buffer: &mut RollingBuffer, into: &mut Vec<TranscriptSegment>, segments: Vec<AsrSegment>, track: TranscriptTrack, label: &str) -> usize {
+    let mut kept = 0;
+    for segment in segments {
+        let Some(segment) = clean_segment(filter, segment, buffer.get_context(), label) else { continue };
+        buffer.push(&segment.text);
+        into.push(TranscriptSegment {
+            track,
+            start_ms: segment.start_ms,
+            end_ms: segment.end_ms,
+            text: segment.text,
+            avg_token_prob: segment.avg_token_prob,
+            min_token_prob: segment.min_token_prob,
+            language: None,
+        });
+        kept += 1;
+    }
+    kept
+}
+
+fn clean_segment(filter: &HallucinationFilter, segment: AsrSegment, context: &str, label: &str) -> Option<AsrSegment> {
+    let filtered = filter.apply(segment, context);
+    match filtered.verdict {
+        FilterVerdict::Keep => {
+            println!("{}: {}", label, filtered.segment.text);
+        }
+        FilterVerdict::Flag(reason) => {
+            println!("{} (flagged {:?}): {}", label, reason, filtered.segment.text);
+        }
+        FilterVerdict::Drop(reason) => {
+            println!("ASR Filter: Dropped segment ({:?}): {}", reason, filtered.segment.text);
+            return None;
+        }
+    }
+
+    // F1.3: Punctuated on the worker thread; casing depends on the transcript so far
+    let mut segment = filtered.segment;
+    segment.text = punct::truecase(&segment.text, punct::ends_sentence(context));
+    Some(segment)
+}
+
+fn low_confidence_texts(filter: &HallucinationFilter, segments: &[TranscriptSegment], track: TranscriptTrack) -> Vec<String> {
+    segments.iter()
+        .filter(|s| s.track == track && s.avg_token_prob < filter.config().flag_avg_token_prob)
+        .map(|s| s.text.clone())
+        .collect()
+}
+
+// F2.8: The chosen language, else the detected one; English if it has no localized headers
+fn summary_locale(chosen: Option<&str>, detected: &str) -> &'static Locale {
+    locale::get_or_english(chosen.unwrap_or(detected))
+}
+
+fn summary_template(database: &Database, folder_id: Option<i64>) -> String {
+    folder_id
+        .and_then(|id| database.get_folder_template(id).ok().flatten())
+        .unwrap_or_else(|| prompt::DEFAULT_TEMPLATE.to_string())
+}
+
+// F2.3: The folder's template asking for `summary_locale`; the summarizer fills in its variables.
+// F17.2: `shares` are the transcript's languages; a mixed-language lecture is pointed out.
+fn summary_prompt(database: &Database, folder_id: Option<i64>, summary_locale: &Locale, shares: &[(String, f32)]) -> String {
+    let mut template = summary_template(database, folder_id);
+    let spoken: Vec<&str> = shares.iter().filter(|(_, share)| *share >= MIXED_MIN_SHARE).map(|(code, _)| code.as_str()).collect();
+    if spoken.len() > 1 {
+        template.push_str("\n\n");
+        template.push_str(&locale::mixed_language_note(&spoken));
+    }
+    summary_locale.localize_prompt(&template)
+}
+
+// F2.7: The best keyphrases of the note's summary items and transcript become its automatic tags.
+// Not the rendered note: its headers are in the summary language, which the stopwords may not cover.
+fn tag_note(database: &Database, note_id: i64, summary: &StructuredSummary, transcript: &str, lang: &str) {
+    let text = format!("{}\n{}", summary.items_text(), transcript);
+    let tags = keyphrase::propose_tags(&text, lang, keyphrase::AUTO_TAGS);
+    match database.set_auto_tags(note_id, &tags) {
+        Ok(()) => println!("Tags: Note {} tagged {:?}.", note_id, tags),
+        Err(e) => println!("Tags: Failed to tag note {}: {}", note_id, e),
+    }
+}
+
+// F17.2: Labels the original track segment by segment; the translation track is Whisper's English.
+// Text from an English-only model (`multilingual` false) is left unlabelled, and segments that
+// were decoded with their language forced keep it.
+fn label_languages(detector: &LanguageDetector, segments: &mut [TranscriptSegment], multilingual: bool) {
+    let labels = {
+        let texts: Vec<&str> = segments.iter()
+            .filter(|s| s.track == TranscriptTrack::Original)
+            .map(|s| s.text.as_str())
+            .collect();
+        detector.label_segments(&texts)
+    };
+    let mut labels = labels.into_iter();
+    for segment in segments.iter_mut() {
+        segment.language = match segment.track {
+            TranscriptTrack::Original => {
+                let label = labels.next().flatten().filter(|_| multilingual).map(|l| l.code.to_string());
+                segment.language.take().or(label)
+            }
+            TranscriptTrack::Translation => Some("en".to_string()),
+        };
+    }
+}
+
+// F17.2: The English-only model cannot redo speech in other languages. Nothing labelled means
+// the live model was English-only too, and the language is unknown.
+fn retranscribe_model(spoken: &[(String, f32)]) -> &'static str {
+    let english = !spoken.is_empty() && spoken.iter().all(|(code, _)| code == "en");
+    if english { RETRANSCRIBE_MODEL } else { MULTILINGUAL_RETRANSCRIBE_MODEL }
+}
+
+// F2.2: Summarizes `pending` window by window, once there is a full window of it (or whatever is
+// left when `final_window`). Extractive, since the ASR model holds the memory.
+fn summarize_windows(filter: &HallucinationFilter, pending: &[TranscriptSegment], summary_track: TranscriptTrack, final_window: bool) -> Vec<PartialSummary> {
+    let track = match summary_track {
+        TranscriptTrack::Translation if pending.iter().any(|s| s.track == TranscriptTrack::Translation) => TranscriptTrack::Translation,
+        _ => TranscriptTrack::Original,
+    };
+    let windows = mapreduce::windows(pending, track, WINDOW_CHARS);
+    let total: usize = windows.iter().map(|(_, _, text)| text.len()).sum();
+    if windows.is_empty() || (!final_window && total < WINDOW_CHARS) {
+        return Vec::new();
+    }
+
+    let low_confidence = low_confidence_texts(filter, pending, track);
+    let options = mapreduce::window_options();
+    windows.into_iter()
+        .map(|(start_ms, end_ms, text)| {
+            let summary = ExtractiveSummarizer.summarize(&text, &low_confidence, &options, &mut |_| true).unwrap_or_default();
+            println!("Map-Reduce: Window summarized ({} chars of transcript).", text.len());
+            PartialSummary { start_ms, end_ms, summary }
+        })
+        .collect()
+}
+
+// F2.6: "<subject>: <topic> (<date>)" in the summary's `locale`; the topic is written by the LLM if
+// there are `models` with a GGUF model loaded, else taken from the keyphrases (in `lang`) of the
+// summary and transcript
+fn note_title(models: Option<&Mutex<ModelManager>>, summary: &str, transcript: &str, subject: &str, timestamp: i64, lang: &str, locale: &Locale) -> String {
+    let generated = models.and_then(|models| {
+        let output = lock_models(models).generate_only(summary, &title::title_options(), &mut |_| true);
+        title::clean_generated(&output.ok()?)
+    });
+    let items = StructuredSummary::parse(summary).items_text();
+    let topic = generated.or_else(|| title::topic(&format!("{}\n{}", items, transcript), lang, locale));
+    title::compose(subject, topic.as_deref(), timestamp, locale)
+}
+
+// F2.4: Splits the note into passages and embeds them (without vectors if no embedding model is loaded)
+fn index_note_chunks(database: &Database, models: &Mutex<ModelManager>, note_id: i64) {
+    let Some((_, chunks)) = note_passages(database, note_id) else { return };
+    let chunks: Vec<_> = {
+        let models = lock_models(models);
+        chunks.into_iter()
+            .map(|chunk| {
+                let vector = models.embed(&chunk.text);
+                (chunk, vector)
+            })
+            .collect()
+    };
+    match database.replace_note_chunks(note_id, &chunks) {
+        Ok(()) => println!("QA: Indexed {} passages of note {}.", chunks.len(), note_id),
+        Err(e) => println!("QA: Failed to index note {}: {}", note_id, e),
+    }
+}
+
+// F2.4: Passages of `note_ids` on a job thread, unless an earlier batch is still being indexed
+fn start_indexing(db_path: &str, models: &Arc<Mutex<ModelManager>>, note_ids: Vec<i64>) {
+    let busy = jobs::list().iter().any(|j| j.kind == JobKind::Reindex && !j.status.is_finished());
+    if busy {
+        return;
+    }
+    let (db_path, models) = (db_path.to_string(), models.clone());
+    jobs::spawn(JobKind::Reindex, &format!("Indexing {} notes", note_ids.len()), move |job| {
+        let database = Database::open(&db_path, DB_PASSWORD).map_err(|e| anyhow::anyhow!(e))?;
+        let total = note_ids.len() as u64;
+        for (i, &note_id) in note_ids.iter().enumerate() {
+            job.check()?;
+            job.progress(i as u64, total, "Indexing notes");
+            let Some((_, chunks)) = note_passages(&database, note_id) else { continue };
+            let texts: Vec<String> = chunks.iter().map(|c| c.text.clone()).collect();
+            let vectors = lock_models(&models).embed_borrowed(&texts)?;
+            let chunks: Vec<_> = chunks.into_iter().zip(vectors).collect();
+            database.replace_note_chunks(note_id, &chunks).map_err(|e| anyhow::anyhow!(e))?;
+        }
+        job.progress(total, total, "Done");
+        Ok(Some(total.to_string()))
+    });
+}
+
+// A panic inside a backend must not take the whole engine down with it
+fn lock_models(models: &Mutex<ModelManager>) -> MutexGuard<'_, ModelManager> {
+    models.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+// F2.4: The note's passages, with the transcript they were cut from; None if the note is gone
+fn note_passages(database: &Database, note_id: i64) -> Option<(Vec<TranscriptSegment>, Vec<qa::Chunk>)> {
+    let (_, _, content, _) = database.get_note(note_id).ok()?;
+    let mut segments = Vec::new();
+    for track in [TranscriptTrack::Original, TranscriptTrack::Translation] {
+        segments.extend(database.get_transcript_segments(note_id, track).unwrap_or_default());
+    }
+    let chunks = qa::chunk_note(note_id, &content, &segments);
+    Some((segments, chunks))
+}
+
+// F18: Track embeddings (F1.2) and passages (F2.4) of one note, in one model swap.
+// False if nothing was embedded (no embedding model installed, or the note is gone).
+fn reindex_note(database: &Database, models: &Mutex<ModelManager>, note_id: i64) -> anyhow::Result<bool> {
+    let Some((segments, chunks)) = note_passages(database, note_id) else { return Ok(false) };
+    let tracks = [TranscriptTrack::Original, TranscriptTrack::Translation];
+    let mut texts: Vec<String> = tracks.iter().map(|&track| transcript::join_track(&segments, track)).collect();
+    if texts[0].trim().is_empty() {
+        // Typed notes have no transcript: their text stands in for it
+        texts[0] = chunks.iter().filter(|c| c.start_ms.is_none()).map(|c| c.text.as_str()).collect::<Vec<_>>().join("\n");
+    }
+    texts.extend(chunks.iter().map(|c| c.text.clone()));
+    let mut vectors = lock_models(models).embed_borrowed(&texts)?.into_iter();
+
+    let mut embedded = false;
+    for (track, text) in tracks.iter().zip(&texts) {
+        if let Some(vector) = vectors.next().flatten().filter(|_| !text.trim().is_empty()) {
+            database.save_track_embedding(note_id, *track, vector).map_err(|e| anyhow::anyhow!(e))?;
+            embedded = true;
+        }
+    }
+    let chunks: Vec<_> = chunks.into_iter().zip(vectors).collect();
+    database.replace_note_chunks(note_id, &chunks).map_err(|e| anyhow::anyhow!(e))?;
+    Ok(embedded)
 }
@@ -12,6 +12,10 @@ pub struct EnduranceController {
     battery_temp_celsius: f32, // From Flutter/Android API
     cpu_temp_celsius: f32,     // From /sys/class/thermal
     
+    // F1.5: Reported by the host (BatteryManager / screen state)
+    charging: bool,
+    device_idle: bool,
+
     // Mocks for testing (preserved)
     simulated_mode: bool,
     
@@ -25,6 +29,8 @@ impl EnduranceController {
             current_mode: EnduranceMode::HighPerformance,
             battery_temp_celsius: 0.0,
             cpu_temp_celsius: 0.0,
+            charging: false,
+            device_idle: false,
             simulated_mode: false,
         }
     }
@@ -33,6 +39,16 @@ impl EnduranceController {
         self.battery_temp_celsius = temp;
     }
 
+    pub fn update_power_state(&mut self, charging: bool, device_idle: bool) {
+        self.charging = charging;
+        self.device_idle = device_idle;
+    }
+
+    // F1.5: Heavy optional work (re-transcription) only runs plugged in, cool and unattended
+    pub fn allows_background_work(&self) -> bool {
+        self.current_mode == EnduranceMode::HighPerformance && self.charging && self.device_idle
+    }
+
     fn read_cpu_temp(&mut self) {
         // Gap 5: Fallback 2 - Try reading system file (often blocked, but worth trying)
         if let Ok(content) = std::fs::read_to_string("/sys/class/thermal/thermal_zone0/temp") {
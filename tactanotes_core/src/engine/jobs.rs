@@ -0,0 +1,320 @@
+// Feature F18: Jobs
+// Long operations (summaries, answers, flashcards, titles, search, re-embedding, model pack imports and
+// re-transcription) register here so the UI can follow their progress and cancel them. The registry
+// has its own lock: listing, cancelling and waiting never wait for the engine mutex the job may hold.
+// Every job runs on a thread of its own with a second database connection (WAL lets it read and
+// write next to the engine's) and checks its token between steps and inside the ASR/LLM/embedding
+// loops. Summaries, answers and flashcards take the model slot with them; the engine gets it back
+// once they are done.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+// Finished jobs kept for list_jobs; older ones are forgotten
+const MAX_FINISHED: usize = 50;
+
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+// Returned (through anyhow) by work that stopped because its job was cancelled
+#[derive(Debug, thiserror::Error)]
+#[error("Cancelled")]
+pub struct Cancelled;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobKind {
+    Summary,
+    Answer,
+    Flashcards,
+    Titles,
+    Search,
+    Reindex,
+    Import,
+    Retranscribe,
+}
+
+impl JobKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Summary => "summary",
+            Self::Answer => "answer",
+            Self::Flashcards => "flashcards",
+            Self::Titles => "titles",
+            Self::Search => "search",
+            Self::Reindex => "reindex",
+            Self::Import => "import",
+            Self::Retranscribe => "retranscribe",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JobStatus {
+    Running,
+    Done,
+    Cancelled,
+    Failed(String),
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Running => "running",
+            Self::Done => "done",
+            Self::Cancelled => "cancelled",
+            Self::Failed(_) => "failed",
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        *self != Self::Running
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobInfo {
+    pub id: i64,
+    pub kind: JobKind,
+    pub status: JobStatus,
+    pub done: u64,
+    pub total: u64,              // 0 = unknown
+    pub message: String,         // Current step, for the UI
+    pub result: Option<String>,  // Kind-specific: note id, JSON list of note ids, model ids...
+    pub started_at: i64,
+    pub finished_at: Option<i64>,
+}
+
+#[derive(Default)]
+struct State {
+    jobs: Vec<JobInfo>, // Oldest first
+    tokens: HashMap<i64, CancelToken>,
+    next_id: i64,
+    subscribers: Vec<Sender<JobInfo>>,
+}
+
+#[derive(Default)]
+struct Registry {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+fn registry() -> &'static Registry {
+    static REGISTRY: OnceLock<Registry> = OnceLock::new();
+    REGISTRY.get_or_init(Registry::default)
+}
+
+impl Registry {
+    // Applies `update` to the job and tells waiters and subscribers
+    fn update(&self, id: i64, update: impl FnOnce(&mut JobInfo)) {
+        let Ok(mut state) = self.state.lock() else { return };
+        let Some(job) = state.jobs.iter_mut().find(|j| j.id == id) else { return };
+        if job.status.is_finished() { return; }
+        update(job);
+        let info = job.clone();
+        if info.status.is_finished() {
+            state.tokens.remove(&id);
+            let finished = state.jobs.iter().filter(|j| j.status.is_finished()).count();
+            if finished > MAX_FINISHED {
+                if let Some(oldest) = state.jobs.iter().position(|j| j.status.is_finished()) {
+                    state.jobs.remove(oldest);
+                }
+            }
+        }
+        state.subscribers.retain(|s| s.send(info.clone()).is_ok());
+        self.changed.notify_all();
+    }
+}
+
+// Handle held by the code doing the work. Clones share the job.
+#[derive(Debug, Clone)]
+pub struct Job {
+    id: i64,
+    token: CancelToken,
+}
+
+impl Job {
+    pub fn id(&self) -> i64 {
+        self.id
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    pub fn cancel(&self) {
+        self.token.cancel();
+    }
+
+    // Err(Cancelled) once the job was cancelled; call between steps
+    pub fn check(&self) -> anyhow::Result<()> {
+        if self.is_cancelled() { Err(Cancelled.into()) } else { Ok(()) }
+    }
+
+    pub fn progress(&self, done: u64, total: u64, message: &str) {
+        registry().update(self.id, |job| {
+            job.done = done;
+            job.total = total;
+            job.message = message.to_string();
+        });
+    }
+
+    // Done with a result, Cancelled, or Failed with the error; later calls are ignored
+    pub fn finish(&self, result: anyhow::Result<Option<String>>) {
+        let now = chrono::Utc::now().timestamp();
+        registry().update(self.id, |job| {
+            job.finished_at = Some(now);
+            match result {
+                Ok(result) => {
+                    job.status = if self.is_cancelled() { JobStatus::Cancelled } else { JobStatus::Done };
+                    job.result = result;
+                }
+                Err(e) if e.is::<Cancelled>() => job.status = JobStatus::Cancelled,
+                Err(e) => job.status = JobStatus::Failed(e.to_string()),
+            }
+        });
+    }
+}
+
+// Registers a running job for work done on the calling thread
+pub fn start(kind: JobKind, message: &str) -> Job {
+    let registry = registry();
+    let mut state = registry.state.lock().unwrap_or_else(|e| e.into_inner());
+    state.next_id += 1;
+    let id = state.next_id;
+    let token = CancelToken::default();
+    let info = JobInfo {
+        id,
+        kind,
+        status: JobStatus::Running,
+        done: 0,
+        total: 0,
+        message: message.to_string(),
+        result: None,
+        started_at: chrono::Utc::now().timestamp(),
+        finished_at: None,
+    };
+    state.jobs.push(info.clone());
+    state.tokens.insert(id, token.clone());
+    state.subscribers.retain(|s| s.send(info.clone()).is_ok());
+    println!("Jobs: {} job {} started ({}).", kind.as_str(), id, message);
+    Job { id, token }
+}
+
+// Runs `work` on its own thread; its return value finishes the job. Returns the job id.
+pub fn spawn<F>(kind: JobKind, message: &str, work: F) -> i64
+where
+    F: FnOnce(&Job) -> anyhow::Result<Option<String>> + Send + 'static,
+{
+    let job = start(kind, message);
+    let id = job.id;
+    let thread_job = job.clone();
+    let spawned = std::thread::Builder::new()
+        .name(format!("tactanotes-job-{}", id))
+        .spawn(move || {
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| work(&thread_job)))
+                .unwrap_or_else(|_| Err(anyhow::anyhow!("Job panicked")));
+            thread_job.finish(result);
+        });
+    if let Err(e) = spawned {
+        job.finish(Err(anyhow::anyhow!("Failed to spawn job thread: {}", e)));
+    }
+    id
+}
+
+// Newest first
+pub fn list() -> Vec<JobInfo> {
+    let state = registry().state.lock().unwrap_or_else(|e| e.into_inner());
+    state.jobs.iter().rev().cloned().collect()
+}
+
+pub fn get(id: i64) -> Option<JobInfo> {
+    let state = registry().state.lock().unwrap_or_else(|e| e.into_inner());
+    state.jobs.iter().find(|j| j.id == id).cloned()
+}
+
+// Asks the job to stop; it finishes as Cancelled at its next check. False if it is not running.
+pub fn cancel(id: i64) -> bool {
+    let state = registry().state.lock().unwrap_or_else(|e| e.into_inner());
+    match state.tokens.get(&id) {
+        Some(token) => {
+            println!("Jobs: Cancelling job {}...", id);
+            token.cancel();
+            true
+        }
+        None => false,
+    }
+}
+
+// The job once finished, or as it is when `timeout` runs out. None for unknown ids.
+pub fn wait(id: i64, timeout: Duration) -> Option<JobInfo> {
+    let registry = registry();
+    let deadline = Instant::now() + timeout;
+    let mut state = registry.state.lock().unwrap_or_else(|e| e.into_inner());
+    loop {
+        let job = state.jobs.iter().find(|j| j.id == id)?.clone();
+        let now = Instant::now();
+        if job.status.is_finished() || now >= deadline {
+            return Some(job);
+        }
+        state = registry.changed.wait_timeout(state, deadline - now).unwrap_or_else(|e| e.into_inner()).0;
+    }
+}
+
+// Every start, progress step and finish from now on
+pub fn subscribe() -> Receiver<JobInfo> {
+    let (tx, rx) = mpsc::channel();
+    let mut state = registry().state.lock().unwrap_or_else(|e| e.into_inner());
+    state.subscribers.push(tx);
+    rx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jobs_report_progress_and_cancel() {
+        let events = subscribe();
+        let id = spawn(JobKind::Reindex, "Re-embedding", |job| {
+            for i in 0..1000u64 {
+                job.check()?;
+                job.progress(i + 1, 1000, "Note");
+                std::thread::sleep(Duration::from_millis(2));
+            }
+            Ok(Some("done".to_string()))
+        });
+        // Wait for some progress, then cancel
+        let first = events.iter().find(|e| e.id == id && e.done > 0).unwrap();
+        assert_eq!((first.kind, first.total), (JobKind::Reindex, 1000));
+        assert!(cancel(id));
+
+        let finished = wait(id, Duration::from_secs(10)).unwrap();
+        assert_eq!(finished.status, JobStatus::Cancelled);
+        assert!(finished.done < 1000 && finished.finished_at.is_some());
+        assert!(!cancel(id));
+        assert!(list().iter().any(|j| j.id == id));
+
+        let quick = spawn(JobKind::Search, "Searching", |_| Ok(Some("[3,1]".to_string())));
+        let done = wait(quick, Duration::from_secs(10)).unwrap();
+        assert_eq!((done.status, done.result), (JobStatus::Done, Some("[3,1]".to_string())));
+
+        let failing = start(JobKind::Import, "Importing");
+        failing.finish(Err(anyhow::anyhow!("Pack is damaged")));
+        failing.finish(Ok(None)); // Ignored
+        assert_eq!(get(failing.id()).unwrap().status, JobStatus::Failed("Pack is damaged".to_string()));
+        assert_eq!(wait(-1, Duration::ZERO), None);
+    }
+}
@@ -0,0 +1,431 @@
+// Feature F1.5: Background Re-transcription
+// Live capture runs Whisper-tiny to stay inside the thermal budget. Once the phone is charging,
+// cool and idle, stored session audio is decoded again with a larger model on this thread.
+// The model checksum is checked here too, and the job is finished here: the Engine passes in an
+// `apply` step that writes the new transcript version and re-summarizes the note, so none of
+// that runs in Engine::tick.
+// F17.2: Auto-detect settles on one language per 30s window, so in a code-switched lecture the
+// other language comes out transliterated, translated or garbled. When a session turns out to be
+// in more than one language, segments labelled with a minority language are decoded again with
+// that language forced, and unsure ones with each of the other session languages. A reading is
+// kept only if it reads as the forced language (forcing `en` on other speech makes Whisper
+// translate) and the decoder is surer of it.
+
+use super::jobs::{self, Job};
+use crate::ai::asr::{AsrBackend, AsrSegment, DecodeOptions};
+use crate::ai::lang::LanguageDetector;
+use crate::ai::punct::Punctuator;
+use crate::ai::registry::{self, ModelEntry};
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread::{self, JoinHandle};
+
+pub const RETRANSCRIBE_MODEL: &str = "ggml-base.en.bin";
+// F17.2: For sessions that are not all English
+pub const MULTILINGUAL_RETRANSCRIBE_MODEL: &str = "ggml-base.bin";
+pub const RETRANSCRIBE_MAX_ATTEMPTS: i64 = 3;
+
+// Whisper's native 30s window; cancellation is checked between chunks
+const CHUNK_SAMPLES: usize = 30 * 16000;
+// F17.2: Segments decoded at least this surely are left alone, unless they read as a minority language
+const REDECODE_BELOW_PROB: f32 = 0.8;
+// A forced-language reading has to beat the original by this much
+const REDECODE_MARGIN: f32 = 0.05;
+// Shorter spans give the decoder too little to go on
+const MIN_REDECODE_MS: i64 = 1000;
+// Decoder calls the second pass may make; each one pads its slice to a full 30s window
+const MAX_REDECODE_CALLS: usize = 120;
+
+pub struct RetranscribeJob {
+    pub job_id: i64,
+    pub note_id: i64,
+    pub audio_paths: Vec<String>, // Session WAVs in recording order
+    pub manifest_entry: Option<ModelEntry>, // F17: Checksum to match before loading (None: unlisted file)
+}
+
+// Stores the decoded segments. Returns jobs::Cancelled, having written nothing, if the job is
+// cancelled while it runs.
+pub type ApplyFn = Box<dyn FnOnce(Vec<AsrSegment>, &Job) -> anyhow::Result<()> + Send>;
+
+pub enum RetranscribeOutcome {
+    Finished { job_id: i64, note_id: i64 },
+    Cancelled { job_id: i64 },
+    Failed { job_id: i64, error: String },
+}
+
+pub struct Retranscriber {
+    job: Option<Job>, // F18: Progress and cancellation
+    results: Option<Receiver<RetranscribeOutcome>>,
+    handle: Option<JoinHandle<()>>,
+    job_id: Option<i64>,
+}
+
+impl Retranscriber {
+    pub fn new() -> Self {
+        Self {
+            job: None,
+            results: None,
+            handle: None,
+            job_id: None,
+        }
+    }
+
+    pub fn running_job(&self) -> Option<i64> {
+        self.job_id
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.job_id.is_some()
+    }
+
+    // The backend is loaded on the worker thread: a larger GGML file takes seconds to map.
+    // `progress` is the F18 job the UI sees; cancelling it ends the run with `Cancelled`.
+    pub fn start(&mut self, job: RetranscribeJob, mut backend: Box<dyn AsrBackend>, models_dir: &str, progress: Job, apply: ApplyFn) {
+        if self.is_running() { return; }
+
+        let (result_tx, result_rx) = mpsc::channel();
+        let models_dir = models_dir.to_string();
+        let job_id = job.job_id;
+        let thread_progress = progress.clone();
+
+        let spawned = thread::Builder::new()
+            .name("tactanotes-retranscribe".to_string())
+            .spawn(move || {
+                println!("Retranscriber: Job {} started for note {}.", job.job_id, job.note_id);
+                let outcome = match run_job(&job, backend.as_mut(), &models_dir, &thread_progress) {
+                    Ok(segments) => {
+                        drop(backend); // The next steps load models of their own
+                        finish_job(&job, segments, &models_dir, apply, &thread_progress)
+                    }
+                    Err(outcome) => outcome,
+                };
+                let _ = result_tx.send(outcome);
+            });
+
+        match spawned {
+            Ok(handle) => {
+                self.job = Some(progress);
+                self.results = Some(result_rx);
+                self.handle = Some(handle);
+                self.job_id = Some(job_id);
+            }
+            Err(e) => println!("Retranscriber: Failed to spawn thread: {}", e),
+        }
+    }
+
+    // Never blocks
+    pub fn poll(&mut self) -> Option<RetranscribeOutcome> {
+        let received = match &self.results {
+            Some(results) => results.try_recv(),
+            None => return None,
+        };
+        match received {
+            Ok(outcome) => {
+                self.finish();
+                Some(outcome)
+            }
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                // Thread died without reporting (panic inside the backend)
+                let job_id = self.job_id;
+                self.finish();
+                job_id.map(|job_id| RetranscribeOutcome::Failed { job_id, error: "Worker thread stopped".to_string() })
+            }
+        }
+    }
+
+    // Blocks until the current step is done. Returns how the job ended: usually `Cancelled`, but
+    // it may have finished (or failed) before it saw the request.
+    pub fn cancel(&mut self) -> Option<RetranscribeOutcome> {
+        let job_id = self.job_id?;
+        println!("Retranscriber: Cancelling job {}...", job_id);
+        if let Some(job) = &self.job {
+            job.cancel();
+        }
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+        let outcome = self.results.as_ref()
+            .and_then(|results| results.try_recv().ok())
+            .unwrap_or(RetranscribeOutcome::Cancelled { job_id });
+        self.finish();
+        Some(outcome)
+    }
+
+    fn finish(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+        self.results = None;
+        self.job_id = None;
+        self.job = None;
+    }
+}
+
+impl Default for Retranscriber {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Retranscriber {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+// Decoded segments, or how the job ended early
+fn run_job(job: &RetranscribeJob, backend: &mut dyn AsrBackend, models_dir: &str, progress: &Job) -> Result<Vec<AsrSegment>, RetranscribeOutcome> {
+    let job_id = job.job_id;
+    // F17: A corrupt download must not overwrite a good transcript
+    if let Some(entry) = &job.manifest_entry {
+        progress.progress(0, 0, "Checking model");
+        if let Err(e) = registry::check_sha256(entry, &Path::new(models_dir).join(&entry.file)) {
+            return Err(RetranscribeOutcome::Failed { job_id, error: e.to_string() });
+        }
+    }
+    progress.progress(0, 0, "Loading model");
+    if !backend.is_loaded() {
+        if let Err(e) = backend.load(models_dir) {
+            return Err(RetranscribeOutcome::Failed { job_id, error: e.to_string() });
+        }
+    }
+
+    let mut segments = Vec::new();
+    let mut offset_ms = 0i64;
+    for (file, path) in job.audio_paths.iter().enumerate() {
+        let audio = read_wav(path).map_err(|e| RetranscribeOutcome::Failed { job_id, error: e.to_string() })?;
+        let chunks = audio.len().div_ceil(CHUNK_SAMPLES) as u64;
+        for (i, chunk) in audio.chunks(CHUNK_SAMPLES).enumerate() {
+            if progress.is_cancelled() {
+                return Err(RetranscribeOutcome::Cancelled { job_id });
+            }
+            progress.progress(i as u64, chunks, &format!("Decoding recording {} of {}", file + 1, job.audio_paths.len()));
+            for mut segment in backend.segments(chunk, &DecodeOptions::default()) {
+                segment.start_ms += offset_ms;
+                segment.end_ms += offset_ms;
+                segments.push(segment);
+            }
+            offset_ms += (chunk.len() as i64 * 1000) / 16000;
+        }
+    }
+
+    println!("Retranscriber: Job {} decoded {} segments.", job_id, segments.len());
+    redecode_switched(job, backend, &mut segments, progress)?;
+    Ok(segments)
+}
+
+// F1.3: Punctuation (the larger Whisper file is out of memory by now), then the Engine's step
+fn finish_job(job: &RetranscribeJob, mut segments: Vec<AsrSegment>, models_dir: &str, apply: ApplyFn, progress: &Job) -> RetranscribeOutcome {
+    let punctuator = Punctuator::load(models_dir);
+    // The session is over after its last segment
+    punctuator.punctuate_segments(&mut segments, i64::MAX);
+    drop(punctuator);
+    let job_id = job.job_id;
+    match apply(segments, progress) {
+        Ok(()) => RetranscribeOutcome::Finished { job_id, note_id: job.note_id },
+        Err(e) if e.is::<jobs::Cancelled>() => RetranscribeOutcome::Cancelled { job_id },
+        Err(e) => RetranscribeOutcome::Failed { job_id, error: e.to_string() },
+    }
+}
+
+// F17.2: Second pass for sessions in more than one language (see the header)
+fn redecode_switched(job: &RetranscribeJob, backend: &dyn AsrBackend, segments: &mut [AsrSegment], progress: &Job) -> Result<usize, RetranscribeOutcome> {
+    let detector = LanguageDetector::new();
+    let texts: Vec<&str> = segments.iter().map(|s| s.text.as_str()).collect();
+    let labels = detector.label_segments(&texts);
+    let mut letters: Vec<(&'static str, usize)> = Vec::new();
+    for (label, text) in labels.iter().zip(&texts) {
+        let Some(label) = label else { continue };
+        match letters.iter_mut().find(|(code, _)| *code == label.code) {
+            Some((_, count)) => *count += text.chars().count(),
+            None => letters.push((label.code, text.chars().count())),
+        }
+    }
+    letters.sort_by_key(|&(_, n)| std::cmp::Reverse(n));
+    // .en models decode English only
+    let supported = backend.capabilities().languages;
+    let languages: Vec<&'static str> = letters.iter()
+        .map(|(code, _)| *code)
+        .filter(|code| supported.is_empty() || supported.iter().any(|l| l == code))
+        .collect();
+    if languages.len() < 2 {
+        return Ok(0);
+    }
+    println!("Retranscriber: Job {} is in {:?}, decoding unsure segments again.", job.job_id, languages);
+
+    // Languages to force per segment: a minority label only its own, an unsure segment the others
+    let tries: Vec<(usize, Vec<&'static str>)> = (0..segments.len())
+        .filter(|&i| segments[i].end_ms - segments[i].start_ms >= MIN_REDECODE_MS)
+        .filter_map(|i| {
+            let current = labels[i].as_ref().map(|l| l.code);
+            let forced: Vec<&'static str> = match current {
+                Some(code) if code != languages[0] => languages.iter().copied().filter(|&l| l == code).collect(),
+                _ if segments[i].avg_token_prob < REDECODE_BELOW_PROB => {
+                    languages.iter().copied().filter(|&l| Some(l) != current).collect()
+                }
+                _ => Vec::new(),
+            };
+            (!forced.is_empty()).then_some((i, forced))
+        })
+        .collect();
+    let total = tries.iter().map(|(_, forced)| forced.len()).sum::<usize>().min(MAX_REDECODE_CALLS);
+    let mut replaced = 0;
+    let mut calls = 0;
+    let mut offset_ms = 0i64;
+    'files: for path in &job.audio_paths {
+        let audio = read_wav(path).map_err(|e| RetranscribeOutcome::Failed { job_id: job.job_id, error: e.to_string() })?;
+        let end_ms = offset_ms + (audio.len() as i64 * 1000) / 16000;
+        let in_file: Vec<&(usize, Vec<&'static str>)> = tries.iter()
+            .filter(|(i, _)| segments[*i].start_ms >= offset_ms && segments[*i].start_ms < end_ms)
+            .collect();
+        for &(i, ref forced) in in_file {
+            let start = ((segments[i].start_ms - offset_ms) * 16) as usize;
+            let end = (((segments[i].end_ms - offset_ms) * 16) as usize).min(audio.len());
+            if start >= end { continue; }
+            for &language in forced {
+                if calls >= MAX_REDECODE_CALLS {
+                    println!("Retranscriber: Job {} reached {} forced-language decodes, stopping.", job.job_id, calls);
+                    break 'files;
+                }
+                if progress.is_cancelled() {
+                    return Err(RetranscribeOutcome::Cancelled { job_id: job.job_id });
+                }
+                progress.progress(calls as u64, total as u64, "Decoding mixed-language speech again");
+                calls += 1;
+                let options = DecodeOptions { language: Some(language.to_string()), translate: false };
+                let decoded = backend.segments(&audio[start..end], &options);
+                let text = decoded.iter().map(|s| s.text.trim()).filter(|t| !t.is_empty()).collect::<Vec<_>>().join(" ");
+                if text.is_empty() { continue; }
+                // Whichever session language the reading is most like has to be the forced one
+                let reads_as = detector.rank(&text).into_iter().find(|s| languages.contains(&s.code)).map(|s| s.code);
+                let avg = decoded.iter().map(|s| s.avg_token_prob).sum::<f32>() / decoded.len() as f32;
+                if reads_as == Some(language) && avg >= segments[i].avg_token_prob + REDECODE_MARGIN {
+                    segments[i].text = text;
+                    segments[i].avg_token_prob = avg;
+                    segments[i].min_token_prob = decoded.iter().map(|s| s.min_token_prob).fold(1.0, f32::min);
+                    segments[i].language = Some(language.to_string());
+                    replaced += 1;
+                }
+            }
+        }
+        offset_ms = end_ms;
+    }
+    println!("Retranscriber: Job {} kept {} forced-language readings.", job.job_id, replaced);
+    Ok(replaced)
+}
+
+// Session WAVs are written by the Engine as 16kHz mono i16
+fn read_wav(path: &str) -> anyhow::Result<Vec<f32>> {
+    let mut reader = hound::WavReader::open(path)
+        .map_err(|e| anyhow::anyhow!("Cannot open {}: {}", path, e))?;
+    let spec = reader.spec();
+    if spec.sample_rate != 16000 {
+        return Err(anyhow::anyhow!("{} is {}Hz, expected 16000Hz", path, spec.sample_rate));
+    }
+    let channels = spec.channels.max(1) as usize;
+    let samples: Vec<f32> = reader.samples::<i16>()
+        .step_by(channels) // First channel only
+        .filter_map(|s| s.ok())
+        .map(|s| s as f32 / 32768.0)
+        .collect();
+    Ok(samples)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::asr::MockAsr;
+    use std::time::{Duration, Instant};
+
+    fn write_silence(path: &std::path::Path, seconds: usize) {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 16000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(path, spec).unwrap();
+        for _ in 0..seconds * 16000 {
+            writer.write_sample(0i16).unwrap();
+        }
+        writer.finalize().unwrap();
+    }
+
+    #[test]
+    fn test_segments_are_offset_across_chunks() {
+        let path = std::env::temp_dir().join(format!("tactanotes_retranscribe_{}.wav", std::process::id()));
+        write_silence(&path, 45);
+
+        let backend = MockAsr::new().with_fallback("chunk");
+        let (spans_tx, spans_rx) = mpsc::channel();
+        let mut runner = Retranscriber::new();
+        runner.start(
+            RetranscribeJob { job_id: 7, note_id: 3, audio_paths: vec![path.to_string_lossy().to_string()], manifest_entry: None },
+            Box::new(backend),
+            "./models",
+            crate::engine::jobs::start(crate::engine::jobs::JobKind::Retranscribe, "Test"),
+            Box::new(move |segments, _| {
+                let spans: Vec<(i64, i64)> = segments.iter().map(|s| (s.start_ms, s.end_ms)).collect();
+                spans_tx.send(spans)?;
+                Ok(())
+            }),
+        );
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let outcome = loop {
+            if let Some(outcome) = runner.poll() { break outcome; }
+            assert!(Instant::now() < deadline, "job did not finish");
+            std::thread::sleep(Duration::from_millis(10));
+        };
+        let _ = std::fs::remove_file(&path);
+
+        match outcome {
+            RetranscribeOutcome::Finished { job_id, note_id } => assert_eq!((job_id, note_id), (7, 3)),
+            _ => panic!("expected Finished"),
+        }
+        // 45s => one 30s chunk and one 15s chunk
+        assert_eq!(spans_rx.recv().unwrap(), vec![(0, 30000), (30000, 45000)]);
+        assert!(!runner.is_running());
+    }
+
+    #[test]
+    fn test_forced_readings_must_read_as_the_forced_language() {
+        let path = std::env::temp_dir().join(format!("tactanotes_redecode_{}.wav", std::process::id()));
+        write_silence(&path, 12);
+        let job = RetranscribeJob { job_id: 8, note_id: 4, audio_paths: vec![path.to_string_lossy().to_string()], manifest_entry: None };
+        let segment = |text: &str, start_ms: i64, prob: f32| AsrSegment {
+            text: text.to_string(),
+            start_ms,
+            end_ms: start_ms + 3000,
+            avg_token_prob: prob,
+            min_token_prob: prob,
+            ..Default::default()
+        };
+        let session = || vec![
+            segment("Today we will look at how cells divide and why the process matters so much.", 0, 0.95),
+            segment("Mitosis produces two identical daughter cells from a single parent cell.", 3000, 0.95),
+            segment("Leo tutaangalia jinsi seli zinavyogawanyika na kwa nini mchakato huu ni muhimu.", 6000, 0.6),
+            segment("Please write down the four phases before we move on to meiosis.", 9000, 0.95),
+        ];
+        let progress = crate::engine::jobs::start(crate::engine::jobs::JobKind::Retranscribe, "Test");
+
+        // Forcing the language hands back a confident English translation: not kept
+        let translated = segment("Today we will see how cells divide and why this process is important.", 0, 0.99);
+        let backend = MockAsr::with_script(vec![vec![translated]]);
+        let mut segments = session();
+        assert_eq!(redecode_switched(&job, &backend, &mut segments, &progress).ok(), Some(0));
+        assert_eq!(backend.calls(), 1, "only the Swahili segment, only in Swahili");
+        assert!(segments[2].text.starts_with("Leo tutaangalia"));
+        assert_eq!(segments[2].language, None);
+
+        // A surer Swahili reading replaces the text and carries its language
+        let swahili = segment("Leo tutaangalia jinsi seli zinavyogawanyika, na kwa nini mchakato huu ni muhimu sana.", 0, 0.9);
+        let backend = MockAsr::with_script(vec![vec![swahili]]);
+        let mut segments = session();
+        assert_eq!(redecode_switched(&job, &backend, &mut segments, &progress).ok(), Some(1));
+        assert!(segments[2].text.ends_with("muhimu sana."));
+        assert_eq!(segments[2].language.as_deref(), Some("sw"));
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -5,6 +5,7 @@ pub mod storage;
 pub mod ai;
 pub mod engine;
 pub mod ocr;
+pub mod study;
 
 #[cfg(not(target_arch = "wasm32"))]
 use mimalloc::MiMalloc;
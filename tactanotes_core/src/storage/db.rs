@@ -1,7 +1,14 @@
 #[cfg(not(target_arch = "wasm32"))]
 mod real {
-    use rusqlite::{params, Connection, Result};
+    use rusqlite::{params, Connection, OptionalExtension, Result};
     use crate::storage::security::Encryptor;
+    use crate::ai::mapreduce::PartialSummary;
+    use crate::ai::qa::Chunk;
+    use crate::ai::structured::{NoteSummary, StructuredSummary};
+    use crate::ai::transcript::{TranscriptSegment, TranscriptTrack};
+    use crate::study::cards::{Card, CardKind, Flashcard};
+    use crate::study::srs::{Grade, ReviewState};
+    use crate::storage::sync::{CardDelta, ReviewDelta};
 
     pub struct Database {
         conn: Connection,
@@ -17,6 +24,8 @@ mod real {
             // PRAGMA journal_mode returns the new mode, so we must consume it to avoid ExecuteReturnedResults error
             let _mode: String = conn.query_row("PRAGMA journal_mode=WAL;", [], |row| row.get(0))?;
             conn.execute("PRAGMA synchronous=NORMAL;", [])?;
+            // F18: Job threads write through a second connection; wait for the other writer
+            conn.busy_timeout(std::time::Duration::from_secs(5))?;
             
             // Feature F8: Academic Hierarchy (Schema)
             conn.execute(
@@ -24,7 +33,6 @@ mod real {
                     id INTEGER PRIMARY KEY,
                     title TEXT,
                     content TEXT,
-                    tags TEXT,
                     created_at INTEGER,
                     updated_at INTEGER,
                     is_deleted INTEGER DEFAULT 0
@@ -83,9 +91,192 @@ mod real {
                 [],
             )?;
 
+            // Feature F1.2: Transcript segments, one row per segment per track (original / translation)
+            // F1.4: with the decoder's token confidence. F1.5: re-transcription adds a version
+            // instead of overwriting.
+            conn.execute(
+                "CREATE TABLE IF NOT EXISTS transcript_segments (
+                    id INTEGER PRIMARY KEY,
+                    note_id INTEGER,
+                    track TEXT,
+                    seq INTEGER,
+                    start_ms INTEGER,
+                    end_ms INTEGER,
+                    text BLOB,
+                    avg_token_prob REAL DEFAULT 1.0,
+                    min_token_prob REAL DEFAULT 1.0,
+                    version INTEGER DEFAULT 1,
+                    language TEXT DEFAULT NULL,
+                    created_at INTEGER
+                )",
+                [],
+            )?;
+            conn.execute(
+                "CREATE INDEX IF NOT EXISTS idx_transcript_segments_note ON transcript_segments (note_id, track, seq)",
+                [],
+            )?;
+
+            // Feature F1.2: Per-track embeddings (the original track keeps using `embeddings`)
+            conn.execute(
+                "CREATE TABLE IF NOT EXISTS track_embeddings (
+                    note_id INTEGER,
+                    track TEXT,
+                    vector TEXT,
+                    created_at INTEGER,
+                    PRIMARY KEY (note_id, track)
+                )",
+                [],
+            )?;
+
+            // Feature F2.4: Passages for question answering (text encrypted; vector NULL without an embedding model)
+            conn.execute(
+                "CREATE TABLE IF NOT EXISTS note_chunks (
+                    id INTEGER PRIMARY KEY,
+                    note_id INTEGER,
+                    start_ms INTEGER,
+                    end_ms INTEGER,
+                    text BLOB,
+                    vector TEXT,
+                    created_at INTEGER
+                )",
+                [],
+            )?;
+            conn.execute("CREATE INDEX IF NOT EXISTS idx_note_chunks_note ON note_chunks (note_id)", [])?;
+            // When each note was last split into passages, so a note without any is not indexed again
+            conn.execute(
+                "CREATE TABLE IF NOT EXISTS indexed_notes (
+                    note_id INTEGER PRIMARY KEY,
+                    missing_vectors INTEGER,
+                    indexed_at INTEGER
+                )",
+                [],
+            )?;
+
+            // Feature F1.5: Re-transcription queue (larger model, run while charging and idle)
+            conn.execute(
+                "CREATE TABLE IF NOT EXISTS retranscribe_jobs (
+                    id INTEGER PRIMARY KEY,
+                    note_id INTEGER,
+                    model TEXT,
+                    status TEXT,
+                    attempts INTEGER DEFAULT 0,
+                    error TEXT,
+                    previous_summary BLOB,
+                    created_at INTEGER,
+                    updated_at INTEGER
+                )",
+                [],
+            )?;
+            // Feature F2.1: Structured summary per note (encrypted JSON; notes.content keeps the rendered text)
+            conn.execute(
+                "CREATE TABLE IF NOT EXISTS note_summaries (
+                    note_id INTEGER PRIMARY KEY,
+                    summary_json BLOB,
+                    updated_at INTEGER
+                )",
+                [],
+            )?;
+
+            // Feature F2.2: Per-window summaries of a session, written with its note
+            conn.execute(
+                "CREATE TABLE IF NOT EXISTS partial_summaries (
+                    id INTEGER PRIMARY KEY,
+                    session_id INTEGER,
+                    note_id INTEGER,
+                    track TEXT,
+                    start_ms INTEGER,
+                    end_ms INTEGER,
+                    summary BLOB,
+                    created_at INTEGER
+                )",
+                [],
+            )?;
+            conn.execute(
+                "CREATE INDEX IF NOT EXISTS idx_partial_summaries_note ON partial_summaries (note_id, session_id)",
+                [],
+            )?;
+
+            // Feature F2.3: Summary prompt templates, attached to folders (courses)
+            conn.execute(
+                "CREATE TABLE IF NOT EXISTS prompt_templates (
+                    id INTEGER PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    body TEXT NOT NULL,
+                    created_at INTEGER,
+                    updated_at INTEGER
+                )",
+                [],
+            )?;
+
+            // Feature F3: Flashcards generated from a note (front/back encrypted like note content)
+            // F3.2: with their SM-2 schedule (due_at 0 = new); deletes are tombstones for sync
+            conn.execute(
+                "CREATE TABLE IF NOT EXISTS cards (
+                    id INTEGER PRIMARY KEY,
+                    note_id INTEGER,
+                    kind TEXT,
+                    front BLOB,
+                    back BLOB,
+                    ease REAL DEFAULT 2.5,
+                    interval_days INTEGER DEFAULT 0,
+                    repetitions INTEGER DEFAULT 0,
+                    lapses INTEGER DEFAULT 0,
+                    due_at INTEGER DEFAULT 0,
+                    is_deleted INTEGER DEFAULT 0,
+                    created_at INTEGER,
+                    updated_at INTEGER
+                )",
+                [],
+            )?;
+            conn.execute("CREATE INDEX IF NOT EXISTS idx_cards_note ON cards (note_id)", [])?;
+            conn.execute("CREATE INDEX IF NOT EXISTS idx_cards_due ON cards (due_at)", [])?;
+
+            // Feature F3.2: One row per answered card (append-only, synced by reviewed_at)
+            conn.execute(
+                "CREATE TABLE IF NOT EXISTS review_log (
+                    id INTEGER PRIMARY KEY,
+                    card_id INTEGER,
+                    grade INTEGER,
+                    ease REAL,
+                    interval_days INTEGER,
+                    duration_ms INTEGER,
+                    reviewed_at INTEGER
+                )",
+                [],
+            )?;
+            conn.execute("CREATE INDEX IF NOT EXISTS idx_review_log_card ON review_log (card_id)", [])?;
+
+            // Feature F2.7: Tags. source: 'user', 'auto' (keyphrases), or 'removed' (an automatic tag
+            // the user took off, kept so re-tagging does not bring it back)
+            conn.execute(
+                "CREATE TABLE IF NOT EXISTS tags (
+                    id INTEGER PRIMARY KEY,
+                    name TEXT NOT NULL UNIQUE,
+                    created_at INTEGER
+                )",
+                [],
+            )?;
+            conn.execute(
+                "CREATE TABLE IF NOT EXISTS note_tags (
+                    note_id INTEGER NOT NULL,
+                    tag_id INTEGER NOT NULL,
+                    source TEXT NOT NULL DEFAULT 'user',
+                    created_at INTEGER,
+                    PRIMARY KEY (note_id, tag_id)
+                )",
+                [],
+            )?;
+            conn.execute("CREATE INDEX IF NOT EXISTS idx_note_tags_tag ON note_tags (tag_id)", [])?;
+
+            // A job that was running when the app died starts over
+            conn.execute("UPDATE retranscribe_jobs SET status = 'queued' WHERE status = 'running'", [])?;
+
             // Migration: Add folder_id to notes if missing
             // This is a naive check; production would use proper migration versioning (e.g., user_version pragma)
             let _ = conn.execute("ALTER TABLE notes ADD COLUMN folder_id INTEGER DEFAULT NULL", []);
+            // Migration (F2.3): Folder prompt template (NULL = default template)
+            let _ = conn.execute("ALTER TABLE folders ADD COLUMN template_id INTEGER DEFAULT NULL", []);
+            migrate(&conn)?;
 
             Ok(Self { conn, encryptor })
         }
@@ -101,25 +292,201 @@ mod real {
 
         pub fn search_similar_notes(&self, query_vector: &[f32], limit: usize) -> Result<Vec<(i64, f32)>> {
             let mut stmt = self.conn.prepare("SELECT note_id, vector FROM embeddings")?;
-            let rows = stmt.query_map([], |row| {
-                 let vector_json: String = row.get(1)?;
-                 let vector: Vec<f32> = serde_json::from_str(&vector_json).unwrap_or_default();
-                 Ok((row.get::<_, i64>(0)?, vector))
-            })?;
+            rank_by_similarity(&mut stmt, params![], query_vector, limit)
+        }
 
-            let mut scores = Vec::new();
+        pub fn save_track_embedding(&self, note_id: i64, track: TranscriptTrack, vector: Vec<f32>) -> Result<()> {
+            if track == TranscriptTrack::Original {
+                return self.save_embedding(note_id, vector);
+            }
+            let vector_json = serde_json::to_string(&vector).unwrap_or_default();
+            self.conn.execute(
+                "INSERT OR REPLACE INTO track_embeddings (note_id, track, vector, created_at) VALUES (?1, ?2, ?3, ?4)",
+                params![note_id, track.as_str(), vector_json, chrono::Utc::now().timestamp()],
+            )?;
+            Ok(())
+        }
+
+        pub fn search_similar_notes_in_track(&self, query_vector: &[f32], track: TranscriptTrack, limit: usize) -> Result<Vec<(i64, f32)>> {
+            if track == TranscriptTrack::Original {
+                return self.search_similar_notes(query_vector, limit);
+            }
+            let mut stmt = self.conn.prepare("SELECT note_id, vector FROM track_embeddings WHERE track = ?1")?;
+            rank_by_similarity(&mut stmt, params![track.as_str()], query_vector, limit)
+        }
+
+        // F2.4: Replaces the note's passages
+        pub fn replace_note_chunks(&self, note_id: i64, chunks: &[(Chunk, Option<Vec<f32>>)]) -> Result<()> {
+            let tx = self.conn.unchecked_transaction()?;
+            tx.execute("DELETE FROM note_chunks WHERE note_id = ?1", [note_id])?;
+            let now = chrono::Utc::now().timestamp();
+            for (chunk, vector) in chunks {
+                let text = self.encryptor.encrypt(chunk.text.as_bytes())
+                    .map_err(|_| rusqlite::Error::ToSqlConversionFailure("Encryption Error".into()))?;
+                let vector_json = vector.as_ref().map(|v| serde_json::to_string(v).unwrap_or_default());
+                tx.execute(
+                    "INSERT INTO note_chunks (note_id, start_ms, end_ms, text, vector, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![note_id, chunk.start_ms, chunk.end_ms, text, vector_json, now],
+                )?;
+            }
+            let missing = chunks.iter().filter(|(_, vector)| vector.is_none()).count();
+            tx.execute(
+                "INSERT OR REPLACE INTO indexed_notes (note_id, missing_vectors, indexed_at) VALUES (?1, ?2, ?3)",
+                params![note_id, missing, now],
+            )?;
+            tx.commit()
+        }
+
+        // Live notes whose passages are missing or older than the note; with `need_vectors`,
+        // also those indexed while no embedding model was available
+        pub fn get_stale_chunk_notes(&self, need_vectors: bool) -> Result<Vec<i64>> {
+            let mut stmt = self.conn.prepare(
+                "SELECT n.id FROM notes n
+                 LEFT JOIN indexed_notes i ON i.note_id = n.id
+                 WHERE n.is_deleted = 0
+                   AND (i.indexed_at IS NULL OR i.indexed_at < n.updated_at OR (?1 AND i.missing_vectors > 0))
+                 ORDER BY n.id ASC"
+            )?;
+            let rows = stmt.query_map([need_vectors], |row| row.get(0))?;
+            let mut results = Vec::new();
             for row in rows {
-                if let Ok((id, vector)) = row {
-                    if vector.len() == query_vector.len() {
-                        let similarity = cosine_similarity(query_vector, &vector);
-                        scores.push((id, similarity));
-                    }
+                results.push(row?);
+            }
+            Ok(results)
+        }
+
+        // Best passages by cosine similarity, for one folder or all of them
+        pub fn search_similar_chunks(&self, query_vector: &[f32], folder_id: Option<i64>, limit: usize) -> Result<Vec<(Chunk, f32)>> {
+            let mut stmt = self.conn.prepare(
+                "SELECT c.id, c.vector FROM note_chunks c JOIN notes n ON n.id = c.note_id
+                 WHERE c.vector IS NOT NULL AND n.is_deleted = 0 AND (?1 IS NULL OR n.folder_id = ?1)"
+            )?;
+            let ranked = rank_by_similarity(&mut stmt, [folder_id], query_vector, limit)?;
+            let mut results = Vec::new();
+            for (id, score) in ranked {
+                let chunk = self.conn.query_row(
+                    "SELECT note_id, start_ms, end_ms, text FROM note_chunks WHERE id = ?1",
+                    [id],
+                    |row| self.chunk_from_row(row),
+                )?;
+                results.push((chunk, score));
+            }
+            Ok(results)
+        }
+
+        pub fn get_chunks(&self, folder_id: Option<i64>) -> Result<Vec<Chunk>> {
+            let mut stmt = self.conn.prepare(
+                "SELECT c.note_id, c.start_ms, c.end_ms, c.text FROM note_chunks c JOIN notes n ON n.id = c.note_id
+                 WHERE n.is_deleted = 0 AND (?1 IS NULL OR n.folder_id = ?1) ORDER BY c.id ASC"
+            )?;
+            let rows = stmt.query_map([folder_id], |row| self.chunk_from_row(row))?;
+            let mut results = Vec::new();
+            for row in rows {
+                results.push(row?);
+            }
+            Ok(results)
+        }
+
+        // note_id, start_ms, end_ms, text
+        fn chunk_from_row(&self, row: &rusqlite::Row) -> Result<Chunk> {
+            let blob: Vec<u8> = row.get(3)?;
+            let text = String::from_utf8(self.encryptor.decrypt(&blob).unwrap_or_default()).unwrap_or_default();
+            Ok(Chunk { note_id: row.get(0)?, start_ms: row.get(1)?, end_ms: row.get(2)?, text })
+        }
+
+        // F1.2: Persist a session's segments (all tracks) under its note.
+        // Appended sessions extend the note's current transcript version.
+        pub fn add_transcript_segments(&self, note_id: i64, segments: &[TranscriptSegment]) -> Result<i64> {
+            let version: i64 = self.conn.query_row(
+                "SELECT COALESCE(MAX(version), 1) FROM transcript_segments WHERE note_id = ?1",
+                [note_id],
+                |row| row.get(0),
+            )?;
+            self.insert_transcript_segments(note_id, segments, version)?;
+            Ok(version)
+        }
+
+        // F1.5: A full replacement transcript (re-transcription). Older versions stay readable.
+        pub fn add_transcript_version(&self, note_id: i64, segments: &[TranscriptSegment]) -> Result<i64> {
+            let version: i64 = self.conn.query_row(
+                "SELECT COALESCE(MAX(version), 0) + 1 FROM transcript_segments WHERE note_id = ?1",
+                [note_id],
+                |row| row.get(0),
+            )?;
+            self.insert_transcript_segments(note_id, segments, version)?;
+            Ok(version)
+        }
+
+        fn insert_transcript_segments(&self, note_id: i64, segments: &[TranscriptSegment], version: i64) -> Result<()> {
+            let tx = self.conn.unchecked_transaction()?;
+            let now = chrono::Utc::now().timestamp();
+            for track in [TranscriptTrack::Original, TranscriptTrack::Translation] {
+                let first_seq: i64 = tx.query_row(
+                    "SELECT COALESCE(MAX(seq), -1) + 1 FROM transcript_segments WHERE note_id = ?1 AND track = ?2 AND version = ?3",
+                    params![note_id, track.as_str(), version],
+                    |row| row.get(0),
+                )?;
+                for (i, segment) in segments.iter().filter(|s| s.track == track).enumerate() {
+                    let seq = first_seq + i as i64;
+                    let encrypted_text = self.encryptor.encrypt(segment.text.as_bytes())
+                        .map_err(|_| rusqlite::Error::ToSqlConversionFailure("Encryption Error".into()))?;
+                    tx.execute(
+                        "INSERT INTO transcript_segments (note_id, track, seq, start_ms, end_ms, text, created_at, avg_token_prob, min_token_prob, version, language)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                        params![note_id, track.as_str(), seq, segment.start_ms, segment.end_ms, encrypted_text, now,
+                                segment.avg_token_prob as f64, segment.min_token_prob as f64, version, segment.language],
+                    )?;
                 }
             }
+            tx.commit()
+        }
+
+        // Latest version of one track
+        pub fn get_transcript_segments(&self, note_id: i64, track: TranscriptTrack) -> Result<Vec<TranscriptSegment>> {
+            let version: Option<i64> = self.conn.query_row(
+                "SELECT MAX(version) FROM transcript_segments WHERE note_id = ?1 AND track = ?2",
+                params![note_id, track.as_str()],
+                |row| row.get(0),
+            )?;
+            match version {
+                Some(version) => self.get_transcript_segments_version(note_id, track, version),
+                None => Ok(Vec::new()),
+            }
+        }
+
+        pub fn get_transcript_versions(&self, note_id: i64) -> Result<Vec<i64>> {
+            let mut stmt = self.conn.prepare(
+                "SELECT DISTINCT version FROM transcript_segments WHERE note_id = ?1 ORDER BY version ASC"
+            )?;
+            let rows = stmt.query_map([note_id], |row| row.get(0))?;
+            let mut results = Vec::new();
+            for row in rows {
+                results.push(row?);
+            }
+            Ok(results)
+        }
 
-            // Sort descending by similarity
-            scores.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
-            Ok(scores.into_iter().take(limit).collect())
+        pub fn get_transcript_segments_version(&self, note_id: i64, track: TranscriptTrack, version: i64) -> Result<Vec<TranscriptSegment>> {
+            let mut stmt = self.conn.prepare(
+                "SELECT start_ms, end_ms, text, avg_token_prob, min_token_prob, language FROM transcript_segments
+                 WHERE note_id = ?1 AND track = ?2 AND version = ?3 ORDER BY seq ASC"
+            )?;
+            let rows = stmt.query_map(params![note_id, track.as_str(), version], |row| {
+                Ok(TranscriptSegment {
+                    track,
+                    start_ms: row.get(0)?,
+                    end_ms: row.get(1)?,
+                    text: String::from_utf8(self.encryptor.decrypt(&row.get::<_, Vec<u8>>(2)?).unwrap_or(b"Decryption Failed".to_vec())).unwrap_or_default(),
+                    avg_token_prob: row.get::<_, Option<f64>>(3)?.unwrap_or(1.0) as f32,
+                    min_token_prob: row.get::<_, Option<f64>>(4)?.unwrap_or(1.0) as f32,
+                    language: row.get(5)?,
+                })
+            })?;
+            let mut results = Vec::new();
+            for row in rows {
+                results.push(row?);
+            }
+            Ok(results)
         }
 
         pub fn add_note(&self, title: &str, content: &str, folder_id: Option<i64>) -> Result<i64> {
@@ -198,6 +565,15 @@ mod real {
             Ok(results)
         }
 
+        // F18: Notes in the folder, or every note; oldest first
+        pub fn get_note_ids(&self, folder_id: Option<i64>) -> Result<Vec<i64>> {
+            let mut stmt = self.conn.prepare(
+                "SELECT id FROM notes WHERE is_deleted = 0 AND (?1 IS NULL OR folder_id = ?1) ORDER BY id ASC",
+            )?;
+            let rows = stmt.query_map([folder_id], |row| row.get(0))?;
+            rows.collect()
+        }
+
         pub fn update_note(&self, note_id: i64, title: &str, content: &str) -> Result<()> {
             // Encrypt content
             let encrypted_content = self.encryptor.encrypt(content.as_bytes())
@@ -253,79 +629,912 @@ mod real {
             }
             Ok(results)
         }
-    }
 
-    fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
-        let dot_product: f32 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
-        let norm_a: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
-        let norm_b: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
-        if norm_a == 0.0 || norm_b == 0.0 {
-            0.0
-        } else {
-            dot_product / (norm_a * norm_b)
+        // F1.5: Re-transcription queue. A note has at most one open (queued/running) job.
+        pub fn enqueue_retranscription(&self, note_id: i64, model: &str) -> Result<i64> {
+            let open: Option<i64> = self.conn.query_row(
+                "SELECT id FROM retranscribe_jobs WHERE note_id = ?1 AND status IN ('queued', 'running')",
+                [note_id],
+                |row| row.get(0),
+            ).optional()?;
+            if let Some(id) = open {
+                return Ok(id);
+            }
+            let now = chrono::Utc::now().timestamp();
+            self.conn.execute(
+                "INSERT INTO retranscribe_jobs (note_id, model, status, attempts, created_at, updated_at) VALUES (?1, ?2, 'queued', 0, ?3, ?3)",
+                params![note_id, model, now],
+            )?;
+            Ok(self.conn.last_insert_rowid())
         }
-    }
-}
-
-
-#[cfg(not(target_arch = "wasm32"))]
-pub use real::Database;
-
-#[cfg(target_arch = "wasm32")]
-mod mock {
-    pub struct Database;
 
-    impl Database {
-        pub fn open(_path: &str, _password: &str) -> anyhow::Result<Self> {
-            Ok(Self)
+        // Oldest queued job as (job_id, note_id, model), marked running
+        pub fn claim_retranscribe_job(&self) -> Result<Option<(i64, i64, String)>> {
+            let job: Option<(i64, i64, String)> = self.conn.query_row(
+                "SELECT id, note_id, model FROM retranscribe_jobs WHERE status = 'queued' ORDER BY created_at ASC, id ASC LIMIT 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            ).optional()?;
+            if let Some((id, _, _)) = &job {
+                self.set_retranscribe_status(*id, "running", None)?;
+            }
+            Ok(job)
         }
 
-        // Use a generic result or matching error type if possible, but anyhow is safer for stub
-        pub fn add_note(&self, _title: &str, _content: &str, _folder_id: Option<i64>) -> anyhow::Result<i64> {
-            Ok(1)
+        pub fn set_retranscribe_status(&self, job_id: i64, status: &str, error: Option<&str>) -> Result<()> {
+            self.conn.execute(
+                "UPDATE retranscribe_jobs SET status = ?1, error = ?2, updated_at = ?3 WHERE id = ?4",
+                params![status, error, chrono::Utc::now().timestamp(), job_id],
+            )?;
+            Ok(())
         }
 
-        pub fn get_modified_notes(&self, _since: i64) -> anyhow::Result<Vec<(i64, String, Vec<u8>, i64)>> {
-            Ok(Vec::new())
+        // Back to the queue until `max_attempts` is reached, then 'failed'
+        pub fn fail_retranscribe_job(&self, job_id: i64, error: &str, max_attempts: i64) -> Result<()> {
+            self.conn.execute(
+                "UPDATE retranscribe_jobs
+                 SET attempts = attempts + 1,
+                     status = CASE WHEN attempts + 1 >= ?1 THEN 'failed' ELSE 'queued' END,
+                     error = ?2, updated_at = ?3
+                 WHERE id = ?4",
+                params![max_attempts, error, chrono::Utc::now().timestamp(), job_id],
+            )?;
+            Ok(())
         }
 
-        pub fn search_notes(&self, _query: &str) -> anyhow::Result<Vec<(String, String)>> {
-            Ok(Vec::new())
+        // Keeps the summary being replaced so the user can get it back
+        pub fn complete_retranscribe_job(&self, job_id: i64, previous_summary: &str) -> Result<()> {
+            let encrypted = self.encryptor.encrypt(previous_summary.as_bytes())
+                .map_err(|_| rusqlite::Error::ToSqlConversionFailure("Encryption Error".into()))?;
+            self.conn.execute(
+                "UPDATE retranscribe_jobs SET status = 'done', error = NULL, previous_summary = ?1, updated_at = ?2 WHERE id = ?3",
+                params![encrypted, chrono::Utc::now().timestamp(), job_id],
+            )?;
+            Ok(())
         }
 
-        pub fn create_folder(&self, _name: &str) -> anyhow::Result<i64> {
-            Ok(1)
+        // (job_id, status, attempts, updated_at), newest first
+        pub fn get_retranscribe_jobs(&self, note_id: i64) -> Result<Vec<(i64, String, i64, i64)>> {
+            let mut stmt = self.conn.prepare(
+                "SELECT id, status, attempts, updated_at FROM retranscribe_jobs WHERE note_id = ?1 ORDER BY id DESC"
+            )?;
+            let rows = stmt.query_map([note_id], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })?;
+            let mut results = Vec::new();
+            for row in rows {
+                results.push(row?);
+            }
+            Ok(results)
         }
 
-        pub fn get_folders(&self) -> anyhow::Result<Vec<(i64, String)>> {
-            Ok(vec![(1, "General".to_string())])
+        pub fn get_previous_summary(&self, job_id: i64) -> Result<Option<String>> {
+            let blob: Option<Vec<u8>> = self.conn.query_row(
+                "SELECT previous_summary FROM retranscribe_jobs WHERE id = ?1",
+                [job_id],
+                |row| row.get(0),
+            )?;
+            Ok(blob.map(|b| String::from_utf8(self.encryptor.decrypt(&b).unwrap_or(b"Decryption Failed".to_vec())).unwrap_or_default()))
         }
 
-        pub fn assign_note_to_folder(&self, _note_id: i64, _folder_id: i64) -> anyhow::Result<()> {
+        // F2.1: Replaces the note's structured summary
+        pub fn save_structured_summary(&self, note_id: i64, summary: &StructuredSummary) -> Result<()> {
+            let json = serde_json::to_string(summary).unwrap_or_default();
+            let encrypted = self.encryptor.encrypt(json.as_bytes())
+                .map_err(|_| rusqlite::Error::ToSqlConversionFailure("Encryption Error".into()))?;
+            self.conn.execute(
+                "INSERT OR REPLACE INTO note_summaries (note_id, summary_json, updated_at) VALUES (?1, ?2, ?3)",
+                params![note_id, encrypted, chrono::Utc::now().timestamp()],
+            )?;
             Ok(())
         }
 
-        pub fn get_notes_by_folder(&self, _folder_id: i64) -> anyhow::Result<Vec<(i64, String, String, i64)>> {
-             Ok(Vec::new())
+        pub fn get_structured_summary(&self, note_id: i64) -> Result<Option<StructuredSummary>> {
+            let blob: Option<Vec<u8>> = self.conn.query_row(
+                "SELECT summary_json FROM note_summaries WHERE note_id = ?1",
+                [note_id],
+                |row| row.get(0),
+            ).optional()?;
+            Ok(blob.and_then(|b| self.decrypt_summary(&b)))
         }
 
-        pub fn update_note(&self, _note_id: i64, _title: &str, _content: &str) -> anyhow::Result<()> {
-            Ok(())
+        // Every live note's summary, newest first.
+        // The JSON is encrypted, so filtering (e.g. homework only) happens after decryption.
+        pub fn get_structured_summaries(&self) -> Result<Vec<NoteSummary>> {
+            let mut stmt = self.conn.prepare(
+                "SELECT n.id, n.title, n.folder_id, s.summary_json FROM note_summaries s
+                 JOIN notes n ON n.id = s.note_id
+                 WHERE n.is_deleted = 0 ORDER BY n.updated_at DESC"
+            )?;
+            let rows = stmt.query_map([], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get::<_, Vec<u8>>(3)?))
+            })?;
+            let mut results = Vec::new();
+            for row in rows {
+                let (note_id, title, folder_id, blob) = row?;
+                if let Some(summary) = self.decrypt_summary(&blob) {
+                    results.push(NoteSummary { note_id, title, folder_id, summary });
+                }
+            }
+            Ok(results)
         }
 
-        pub fn delete_note(&self, _note_id: i64) -> anyhow::Result<()> {
-            Ok(())
+        // F2.2: Map step output of one recording session, written when the session is saved
+        pub fn add_partial_summaries(&self, note_id: i64, session_id: i64, track: TranscriptTrack, partials: &[PartialSummary]) -> Result<()> {
+            let tx = self.conn.unchecked_transaction()?;
+            self.insert_partial_summaries(&tx, note_id, session_id, track, partials)?;
+            tx.commit()
         }
 
-        pub fn get_note(&self, _note_id: i64) -> anyhow::Result<(i64, String, String, i64)> {
-            Ok((1, "Mock".to_string(), "Content".to_string(), 0))
+        pub fn get_partial_summary_sessions(&self, note_id: i64) -> Result<Vec<i64>> {
+            let mut stmt = self.conn.prepare("SELECT DISTINCT session_id FROM partial_summaries WHERE note_id = ?1")?;
+            let rows = stmt.query_map([note_id], |row| row.get(0))?;
+            rows.collect()
         }
 
-        pub fn add_attachment(&self, _note_id: i64, _type: &str, _path: &str) -> anyhow::Result<i64> {
-            Ok(1)
+        // Re-transcription summarizes the new transcript of `sessions`; its windows replace theirs
+        pub fn replace_partial_summaries(&self, note_id: i64, sessions: &[i64], session_id: i64, track: TranscriptTrack, partials: &[PartialSummary]) -> Result<()> {
+            let tx = self.conn.unchecked_transaction()?;
+            for session in sessions {
+                tx.execute("DELETE FROM partial_summaries WHERE note_id = ?1 AND session_id = ?2", params![note_id, session])?;
+            }
+            self.insert_partial_summaries(&tx, note_id, session_id, track, partials)?;
+            tx.commit()
         }
 
-        pub fn get_attachments(&self, _note_id: i64) -> anyhow::Result<Vec<(i64, String, String)>> {
+        fn insert_partial_summaries(&self, conn: &Connection, note_id: i64, session_id: i64, track: TranscriptTrack, partials: &[PartialSummary]) -> Result<()> {
+            let now = chrono::Utc::now().timestamp();
+            for partial in partials {
+                let encrypted = self.encryptor.encrypt(partial.summary.as_bytes())
+                    .map_err(|_| rusqlite::Error::ToSqlConversionFailure("Encryption Error".into()))?;
+                conn.execute(
+                    "INSERT INTO partial_summaries (session_id, note_id, track, start_ms, end_ms, summary, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    params![session_id, note_id, track.as_str(), partial.start_ms, partial.end_ms, encrypted, now],
+                )?;
+            }
+            Ok(())
+        }
+
+        // In recording order; an appended note lists its sessions one after the other
+        pub fn get_partial_summaries(&self, note_id: i64) -> Result<Vec<PartialSummary>> {
+            let mut stmt = self.conn.prepare(
+                "SELECT start_ms, end_ms, summary FROM partial_summaries WHERE note_id = ?1 ORDER BY id ASC"
+            )?;
+            let rows = stmt.query_map([note_id], |row| {
+                Ok(PartialSummary {
+                    start_ms: row.get(0)?,
+                    end_ms: row.get(1)?,
+                    summary: String::from_utf8(self.encryptor.decrypt(&row.get::<_, Vec<u8>>(2)?).unwrap_or(b"Decryption Failed".to_vec())).unwrap_or_default(),
+                })
+            })?;
+            let mut results = Vec::new();
+            for row in rows {
+                results.push(row?);
+            }
+            Ok(results)
+        }
+
+        // F2.3: Prompt templates
+        pub fn create_prompt_template(&self, name: &str, body: &str) -> Result<i64> {
+            let now = chrono::Utc::now().timestamp();
+            self.conn.execute(
+                "INSERT INTO prompt_templates (name, body, created_at, updated_at) VALUES (?1, ?2, ?3, ?3)",
+                params![name, body, now],
+            )?;
+            Ok(self.conn.last_insert_rowid())
+        }
+
+        pub fn update_prompt_template(&self, template_id: i64, name: &str, body: &str) -> Result<()> {
+            self.conn.execute(
+                "UPDATE prompt_templates SET name = ?1, body = ?2, updated_at = ?3 WHERE id = ?4",
+                params![name, body, chrono::Utc::now().timestamp(), template_id],
+            )?;
+            Ok(())
+        }
+
+        // Folders using it fall back to the default template
+        pub fn delete_prompt_template(&self, template_id: i64) -> Result<()> {
+            self.conn.execute("UPDATE folders SET template_id = NULL WHERE template_id = ?1", [template_id])?;
+            self.conn.execute("DELETE FROM prompt_templates WHERE id = ?1", [template_id])?;
+            Ok(())
+        }
+
+        // (id, name, body)
+        pub fn get_prompt_templates(&self) -> Result<Vec<(i64, String, String)>> {
+            let mut stmt = self.conn.prepare("SELECT id, name, body FROM prompt_templates ORDER BY id ASC")?;
+            let rows = stmt.query_map([], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })?;
+            let mut results = Vec::new();
+            for row in rows {
+                results.push(row?);
+            }
+            Ok(results)
+        }
+
+        pub fn set_folder_template(&self, folder_id: i64, template_id: Option<i64>) -> Result<()> {
+            self.conn.execute(
+                "UPDATE folders SET template_id = ?1 WHERE id = ?2",
+                params![template_id, folder_id],
+            )?;
+            Ok(())
+        }
+
+        // Body of the folder's template, None if it has none
+        pub fn get_folder_template(&self, folder_id: i64) -> Result<Option<String>> {
+            self.conn.query_row(
+                "SELECT t.body FROM folders f JOIN prompt_templates t ON t.id = f.template_id WHERE f.id = ?1",
+                [folder_id],
+                |row| row.get(0),
+            ).optional()
+        }
+
+        pub fn get_note_folder(&self, note_id: i64) -> Result<Option<i64>> {
+            self.conn.query_row(
+                "SELECT folder_id FROM notes WHERE id = ?1",
+                [note_id],
+                |row| row.get(0),
+            )
+        }
+
+        // F3: Stores the cards the note does not have yet (same front); returns how many were new.
+        // Deleted cards count as existing, so regenerating does not bring them back.
+        pub fn add_cards(&self, note_id: i64, cards: &[Flashcard]) -> Result<usize> {
+            let existing = self.query_cards(
+                &format!("SELECT {} FROM cards WHERE note_id = ?1", CARD_COLUMNS),
+                [note_id],
+            )?;
+            let now = chrono::Utc::now().timestamp();
+            let mut added = 0;
+            for card in cards {
+                if existing.iter().any(|c| crate::study::cards::same_front(&c.front, &card.front)) { continue; }
+                let front = self.encryptor.encrypt(card.front.as_bytes())
+                    .map_err(|_| rusqlite::Error::ToSqlConversionFailure("Encryption Error".into()))?;
+                let back = self.encryptor.encrypt(card.back.as_bytes())
+                    .map_err(|_| rusqlite::Error::ToSqlConversionFailure("Encryption Error".into()))?;
+                self.conn.execute(
+                    "INSERT INTO cards (note_id, kind, front, back, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?5)",
+                    params![note_id, card.kind.as_str(), front, back, now],
+                )?;
+                added += 1;
+            }
+            Ok(added)
+        }
+
+        pub fn get_cards(&self, note_id: i64) -> Result<Vec<Card>> {
+            self.query_cards(
+                &format!("SELECT {} FROM cards WHERE note_id = ?1 AND is_deleted = 0 ORDER BY id ASC", CARD_COLUMNS),
+                [note_id],
+            )
+        }
+
+        // Cards of live notes with the note title, for one folder or all of them
+        pub fn get_cards_with_titles(&self, folder_id: Option<i64>) -> Result<Vec<(Card, String)>> {
+            let mut stmt = self.conn.prepare(&format!(
+                "SELECT {}, n.title FROM cards c
+                 JOIN notes n ON n.id = c.note_id
+                 WHERE n.is_deleted = 0 AND c.is_deleted = 0 AND (?1 IS NULL OR n.folder_id = ?1)
+                 ORDER BY c.note_id ASC, c.id ASC",
+                CARD_COLUMNS_C,
+            ))?;
+            let rows = stmt.query_map([folder_id], |row| {
+                Ok((self.card_from_row(row)?, row.get::<_, Option<String>>(10)?.unwrap_or_default()))
+            })?;
+            let mut results = Vec::new();
+            for row in rows {
+                results.push(row?);
+            }
+            Ok(results)
+        }
+
+        // Soft delete, so the deletion syncs like a note's
+        pub fn delete_card(&self, card_id: i64) -> Result<()> {
+            self.conn.execute(
+                "UPDATE cards SET is_deleted = 1, updated_at = ?2 WHERE id = ?1",
+                params![card_id, chrono::Utc::now().timestamp()],
+            )?;
+            Ok(())
+        }
+
+        // F3.2: Cards due at `now` for one folder or all of them: overdue reviews first
+        // (oldest first), then new cards in the order they were made
+        pub fn get_due_cards(&self, folder_id: Option<i64>, now: i64, limit: usize) -> Result<Vec<Card>> {
+            self.query_cards(
+                &format!(
+                    "SELECT {} FROM cards c
+                     JOIN notes n ON n.id = c.note_id
+                     WHERE n.is_deleted = 0 AND c.is_deleted = 0 AND (?1 IS NULL OR n.folder_id = ?1)
+                       AND c.due_at <= ?2
+                     ORDER BY c.due_at = 0 ASC, c.due_at ASC, c.id ASC
+                     LIMIT ?3",
+                    CARD_COLUMNS_C,
+                ),
+                params![folder_id, now, limit as i64],
+            )
+        }
+
+        // (reviews due, new cards) at `now`
+        pub fn count_due_cards(&self, folder_id: Option<i64>, now: i64) -> Result<(usize, usize)> {
+            self.conn.query_row(
+                "SELECT COALESCE(SUM(c.due_at > 0), 0), COALESCE(SUM(c.due_at = 0), 0) FROM cards c
+                 JOIN notes n ON n.id = c.note_id
+                 WHERE n.is_deleted = 0 AND c.is_deleted = 0 AND (?1 IS NULL OR n.folder_id = ?1)
+                   AND c.due_at <= ?2",
+                params![folder_id, now],
+                |row| Ok((row.get::<_, i64>(0)? as usize, row.get::<_, i64>(1)? as usize)),
+            )
+        }
+
+        pub fn get_card(&self, card_id: i64) -> Result<Option<Card>> {
+            Ok(self.query_cards(
+                &format!("SELECT {} FROM cards WHERE id = ?1 AND is_deleted = 0", CARD_COLUMNS),
+                [card_id],
+            )?.pop())
+        }
+
+        // Stores the new schedule and logs the answer in one transaction
+        pub fn record_review(&self, card_id: i64, grade: Grade, state: &ReviewState, duration_ms: i64, now: i64) -> Result<()> {
+            let tx = self.conn.unchecked_transaction()?;
+            tx.execute(
+                "UPDATE cards SET ease = ?2, interval_days = ?3, repetitions = ?4, lapses = ?5, due_at = ?6, updated_at = ?7
+                 WHERE id = ?1",
+                params![card_id, state.ease as f64, state.interval_days, state.repetitions, state.lapses, state.due_at, now],
+            )?;
+            tx.execute(
+                "INSERT INTO review_log (card_id, grade, ease, interval_days, duration_ms, reviewed_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![card_id, grade as u8, state.ease as f64, state.interval_days, duration_ms, now],
+            )?;
+            tx.commit()
+        }
+
+        // F2.7: `names` are normalized tags; a user tag stays a user tag. Returns how many were new on the note.
+        pub fn add_note_tags(&self, note_id: i64, names: &[String], source: &str) -> Result<usize> {
+            let tx = self.conn.unchecked_transaction()?;
+            let now = chrono::Utc::now().timestamp();
+            let mut added = 0;
+            for name in names {
+                if insert_note_tag(&tx, note_id, name, source, now)? {
+                    added += 1;
+                }
+            }
+            tx.commit()?;
+            Ok(added)
+        }
+
+        // F2.7: Replaces the note's automatic tags; user tags and removed ones are left as they are
+        pub fn set_auto_tags(&self, note_id: i64, names: &[String]) -> Result<()> {
+            let tx = self.conn.unchecked_transaction()?;
+            tx.execute("DELETE FROM note_tags WHERE note_id = ?1 AND source = 'auto'", [note_id])?;
+            let now = chrono::Utc::now().timestamp();
+            for name in names {
+                insert_note_tag(&tx, note_id, name, "auto", now)?;
+            }
+            tx.commit()
+        }
+
+        // False if the note did not have the tag
+        pub fn remove_note_tag(&self, note_id: i64, name: &str) -> Result<bool> {
+            let deleted = self.conn.execute(
+                "DELETE FROM note_tags WHERE note_id = ?1 AND source = 'user' AND tag_id = (SELECT id FROM tags WHERE name = ?2)",
+                params![note_id, name],
+            )?;
+            let hidden = self.conn.execute(
+                "UPDATE note_tags SET source = 'removed'
+                 WHERE note_id = ?1 AND source = 'auto' AND tag_id = (SELECT id FROM tags WHERE name = ?2)",
+                params![note_id, name],
+            )?;
+            Ok(deleted + hidden > 0)
+        }
+
+        // (name, source), user tags first
+        pub fn get_note_tags(&self, note_id: i64) -> Result<Vec<(String, String)>> {
+            let mut stmt = self.conn.prepare(
+                "SELECT t.name, nt.source FROM note_tags nt JOIN tags t ON t.id = nt.tag_id
+                 WHERE nt.note_id = ?1 AND nt.source != 'removed'
+                 ORDER BY nt.source = 'user' DESC, t.name ASC",
+            )?;
+            let rows = stmt.query_map([note_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect()
+        }
+
+        // Tags in use with their note counts, most used first
+        pub fn get_tags(&self) -> Result<Vec<(String, i64)>> {
+            let mut stmt = self.conn.prepare(
+                "SELECT t.name, COUNT(n.id) AS uses FROM tags t
+                 JOIN note_tags nt ON nt.tag_id = t.id AND nt.source != 'removed'
+                 JOIN notes n ON n.id = nt.note_id AND n.is_deleted = 0
+                 GROUP BY t.id ORDER BY uses DESC, t.name ASC",
+            )?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect()
+        }
+
+        pub fn get_notes_by_tag(&self, name: &str) -> Result<Vec<(i64, String, String, i64)>> {
+            let mut stmt = self.conn.prepare(
+                "SELECT n.id, n.title, n.content, n.updated_at FROM notes n
+                 JOIN note_tags nt ON nt.note_id = n.id AND nt.source != 'removed'
+                 JOIN tags t ON t.id = nt.tag_id
+                 WHERE t.name = ?1 AND n.is_deleted = 0 ORDER BY n.updated_at DESC",
+            )?;
+            let rows = stmt.query_map([name], |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    String::from_utf8(self.encryptor.decrypt(&row.get::<_, Vec<u8>>(2)?).unwrap_or(b"Decryption Failed".to_vec())).unwrap_or_default(),
+                    row.get(3)?,
+                ))
+            })?;
+            rows.collect()
+        }
+
+        // Feature F09: Card changes (including tombstones) since the last sync; front/back stay encrypted
+        pub fn get_modified_cards(&self, since: i64) -> Result<Vec<CardDelta>> {
+            let mut stmt = self.conn.prepare(
+                "SELECT id, note_id, kind, front, back, ease, interval_days, repetitions, lapses, due_at, is_deleted, updated_at
+                 FROM cards WHERE updated_at > ?1"
+            )?;
+            let rows = stmt.query_map([since], |row| {
+                Ok(CardDelta {
+                    id: row.get(0)?,
+                    note_id: row.get(1)?,
+                    kind: row.get(2)?,
+                    encrypted_front: row.get(3)?,
+                    encrypted_back: row.get(4)?,
+                    review: review_from_row(row, 5)?,
+                    is_deleted: row.get::<_, i64>(10)? != 0,
+                    updated_at: row.get(11)?,
+                })
+            })?;
+            let mut results = Vec::new();
+            for row in rows {
+                results.push(row?);
+            }
+            Ok(results)
+        }
+
+        pub fn get_modified_reviews(&self, since: i64) -> Result<Vec<ReviewDelta>> {
+            let mut stmt = self.conn.prepare(
+                "SELECT id, card_id, grade, ease, interval_days, duration_ms, reviewed_at
+                 FROM review_log WHERE reviewed_at > ?1 ORDER BY id ASC"
+            )?;
+            let rows = stmt.query_map([since], |row| {
+                Ok(ReviewDelta {
+                    id: row.get(0)?,
+                    card_id: row.get(1)?,
+                    grade: row.get(2)?,
+                    ease: row.get::<_, f64>(3)? as f32,
+                    interval_days: row.get(4)?,
+                    duration_ms: row.get(5)?,
+                    reviewed_at: row.get(6)?,
+                })
+            })?;
+            let mut results = Vec::new();
+            for row in rows {
+                results.push(row?);
+            }
+            Ok(results)
+        }
+
+        fn query_cards<P: rusqlite::Params>(&self, sql: &str, query_params: P) -> Result<Vec<Card>> {
+            let mut stmt = self.conn.prepare(sql)?;
+            let rows = stmt.query_map(query_params, |row| self.card_from_row(row))?;
+            let mut results = Vec::new();
+            for row in rows {
+                results.push(row?);
+            }
+            Ok(results)
+        }
+
+        // CARD_COLUMNS order
+        fn card_from_row(&self, row: &rusqlite::Row) -> Result<Card> {
+            let decrypt = |blob: Vec<u8>| String::from_utf8(self.encryptor.decrypt(&blob).unwrap_or(b"Decryption Failed".to_vec())).unwrap_or_default();
+            Ok(Card {
+                id: row.get(0)?,
+                note_id: row.get(1)?,
+                kind: CardKind::parse(&row.get::<_, String>(2)?).unwrap_or(CardKind::Qa),
+                front: decrypt(row.get(3)?),
+                back: decrypt(row.get(4)?),
+                review: review_from_row(row, 5)?,
+            })
+        }
+
+        fn decrypt_summary(&self, blob: &[u8]) -> Option<StructuredSummary> {
+            let json = self.encryptor.decrypt(blob).ok()?;
+            serde_json::from_slice(&json).ok()
+        }
+    }
+
+    // Versioned migrations (PRAGMA user_version). The ALTER TABLEs in `open` predate them and are
+    // safe to repeat; changes that are not go here.
+    fn migrate(conn: &Connection) -> Result<()> {
+        let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version < 1 {
+            // v1 (F2.7): The unused notes.tags column moves into tags / note_tags
+            let tx = conn.unchecked_transaction()?;
+            if tx.prepare("SELECT tags FROM notes LIMIT 0").is_ok() {
+                let tagged: Vec<(i64, String)> = {
+                    let mut stmt = tx.prepare("SELECT id, tags FROM notes WHERE tags IS NOT NULL AND tags != ''")?;
+                    let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+                    rows.collect::<Result<_>>()?
+                };
+                let now = chrono::Utc::now().timestamp();
+                for (note_id, tags) in tagged {
+                    for name in tags.split([',', ';', '#']).filter_map(crate::ai::keyphrase::normalize_tag) {
+                        insert_note_tag(&tx, note_id, &name, "user", now)?;
+                    }
+                }
+                // SQLite before 3.35 cannot drop columns; the column then just stays unused
+                let _ = tx.execute("ALTER TABLE notes DROP COLUMN tags", []);
+            }
+            tx.execute_batch("PRAGMA user_version = 1")?;
+            tx.commit()?;
+            println!("DB: Migrated to schema version 1 (tags table).");
+        }
+        if version < 2 {
+            // v2 (F2.2): Partial summaries used to be written while recording and linked to the
+            // note on save; sessions that were never saved left rows without a note behind
+            conn.execute_batch(
+                "DELETE FROM partial_summaries WHERE note_id IS NULL;
+                 PRAGMA user_version = 2;"
+            )?;
+            println!("DB: Migrated to schema version 2 (orphaned partial summaries removed).");
+        }
+        if version < 3 {
+            // v3 (F2.4): Notes indexed before indexed_notes existed keep their passages
+            conn.execute_batch(
+                "INSERT OR IGNORE INTO indexed_notes (note_id, missing_vectors, indexed_at)
+                     SELECT note_id, SUM(vector IS NULL), MIN(created_at) FROM note_chunks GROUP BY note_id;
+                 PRAGMA user_version = 3;"
+            )?;
+            println!("DB: Migrated to schema version 3 (indexed notes).");
+        }
+        if version < 4 {
+            // v4 (F17.2): Per-segment language (NULL = not detected)
+            let tx = conn.unchecked_transaction()?;
+            if tx.prepare("SELECT language FROM transcript_segments LIMIT 0").is_err() {
+                tx.execute("ALTER TABLE transcript_segments ADD COLUMN language TEXT DEFAULT NULL", [])?;
+            }
+            tx.execute_batch("PRAGMA user_version = 4")?;
+            tx.commit()?;
+            println!("DB: Migrated to schema version 4 (segment language).");
+        }
+        Ok(())
+    }
+
+    // True if the note did not have the tag yet
+    fn insert_note_tag(conn: &Connection, note_id: i64, name: &str, source: &str, now: i64) -> Result<bool> {
+        conn.execute("INSERT OR IGNORE INTO tags (name, created_at) VALUES (?1, ?2)", params![name, now])?;
+        let tag_id: i64 = conn.query_row("SELECT id FROM tags WHERE name = ?1", [name], |row| row.get(0))?;
+        let added = conn.execute(
+            "INSERT OR IGNORE INTO note_tags (note_id, tag_id, source, created_at) VALUES (?1, ?2, ?3, ?4)",
+            params![note_id, tag_id, source, now],
+        )?;
+        if added == 0 && source == "user" {
+            // Adding by hand makes an automatic tag the user's, and brings a removed one back
+            let restored = conn.execute(
+                "UPDATE note_tags SET source = 'user' WHERE note_id = ?1 AND tag_id = ?2 AND source = 'removed'",
+                params![note_id, tag_id],
+            )?;
+            conn.execute(
+                "UPDATE note_tags SET source = 'user' WHERE note_id = ?1 AND tag_id = ?2 AND source = 'auto'",
+                params![note_id, tag_id],
+            )?;
+            return Ok(restored > 0);
+        }
+        Ok(added > 0)
+    }
+
+    const CARD_COLUMNS: &str = "id, note_id, kind, front, back, ease, interval_days, repetitions, lapses, due_at";
+    const CARD_COLUMNS_C: &str = "c.id, c.note_id, c.kind, c.front, c.back, c.ease, c.interval_days, c.repetitions, c.lapses, c.due_at";
+
+    // ease, interval_days, repetitions, lapses, due_at starting at column `first`
+    fn review_from_row(row: &rusqlite::Row, first: usize) -> Result<ReviewState> {
+        Ok(ReviewState {
+            ease: row.get::<_, f64>(first)? as f32,
+            interval_days: row.get(first + 1)?,
+            repetitions: row.get(first + 2)?,
+            lapses: row.get(first + 3)?,
+            due_at: row.get(first + 4)?,
+        })
+    }
+
+    // Scores every (note_id, vector_json) row against the query; highest similarity first
+    fn rank_by_similarity<P: rusqlite::Params>(stmt: &mut rusqlite::Statement, query_params: P, query_vector: &[f32], limit: usize) -> Result<Vec<(i64, f32)>> {
+        let rows = stmt.query_map(query_params, |row| {
+             let vector_json: String = row.get(1)?;
+             let vector: Vec<f32> = serde_json::from_str(&vector_json).unwrap_or_default();
+             Ok((row.get::<_, i64>(0)?, vector))
+        })?;
+
+        let mut scores = Vec::new();
+        for (id, vector) in rows.flatten() {
+            if vector.len() == query_vector.len() {
+                let similarity = cosine_similarity(query_vector, &vector);
+                scores.push((id, similarity));
+            }
+        }
+
+        // Sort descending by similarity
+        scores.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        Ok(scores.into_iter().take(limit).collect())
+    }
+
+    fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+        let dot_product: f32 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
+        let norm_a: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+        let norm_b: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if norm_a == 0.0 || norm_b == 0.0 {
+            0.0
+        } else {
+            dot_product / (norm_a * norm_b)
+        }
+    }
+}
+
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use real::Database;
+
+#[cfg(target_arch = "wasm32")]
+mod mock {
+    use crate::ai::mapreduce::PartialSummary;
+    use crate::ai::qa::Chunk;
+    use crate::ai::structured::{NoteSummary, StructuredSummary};
+    use crate::ai::transcript::{TranscriptSegment, TranscriptTrack};
+    use crate::study::cards::{Card, Flashcard};
+    use crate::study::srs::{Grade, ReviewState};
+    use crate::storage::sync::{CardDelta, ReviewDelta};
+
+    pub struct Database;
+
+    impl Database {
+        pub fn open(_path: &str, _password: &str) -> anyhow::Result<Self> {
+            Ok(Self)
+        }
+
+        // Use a generic result or matching error type if possible, but anyhow is safer for stub
+        pub fn add_note(&self, _title: &str, _content: &str, _folder_id: Option<i64>) -> anyhow::Result<i64> {
+            Ok(1)
+        }
+
+        pub fn get_modified_notes(&self, _since: i64) -> anyhow::Result<Vec<(i64, String, Vec<u8>, i64)>> {
+            Ok(Vec::new())
+        }
+
+        pub fn search_notes(&self, _query: &str) -> anyhow::Result<Vec<(String, String)>> {
+            Ok(Vec::new())
+        }
+
+        pub fn create_folder(&self, _name: &str) -> anyhow::Result<i64> {
+            Ok(1)
+        }
+
+        pub fn get_folders(&self) -> anyhow::Result<Vec<(i64, String)>> {
+            Ok(vec![(1, "General".to_string())])
+        }
+
+        pub fn assign_note_to_folder(&self, _note_id: i64, _folder_id: i64) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        pub fn get_notes_by_folder(&self, _folder_id: i64) -> anyhow::Result<Vec<(i64, String, String, i64)>> {
+             Ok(Vec::new())
+        }
+
+        pub fn get_note_ids(&self, _folder_id: Option<i64>) -> anyhow::Result<Vec<i64>> {
+            Ok(Vec::new())
+        }
+
+        pub fn add_note_tags(&self, _note_id: i64, _names: &[String], _source: &str) -> anyhow::Result<usize> {
+            Ok(0)
+        }
+
+        pub fn set_auto_tags(&self, _note_id: i64, _names: &[String]) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        pub fn remove_note_tag(&self, _note_id: i64, _name: &str) -> anyhow::Result<bool> {
+            Ok(false)
+        }
+
+        pub fn get_note_tags(&self, _note_id: i64) -> anyhow::Result<Vec<(String, String)>> {
+            Ok(Vec::new())
+        }
+
+        pub fn get_tags(&self) -> anyhow::Result<Vec<(String, i64)>> {
+            Ok(Vec::new())
+        }
+
+        pub fn get_notes_by_tag(&self, _name: &str) -> anyhow::Result<Vec<(i64, String, String, i64)>> {
+            Ok(Vec::new())
+        }
+
+        pub fn update_note(&self, _note_id: i64, _title: &str, _content: &str) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        pub fn delete_note(&self, _note_id: i64) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        pub fn get_note(&self, _note_id: i64) -> anyhow::Result<(i64, String, String, i64)> {
+            Ok((1, "Mock".to_string(), "Content".to_string(), 0))
+        }
+
+        pub fn add_attachment(&self, _note_id: i64, _type: &str, _path: &str) -> anyhow::Result<i64> {
+            Ok(1)
+        }
+
+        pub fn get_attachments(&self, _note_id: i64) -> anyhow::Result<Vec<(i64, String, String)>> {
+            Ok(Vec::new())
+        }
+
+        pub fn save_track_embedding(&self, _note_id: i64, _track: TranscriptTrack, _vector: Vec<f32>) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        pub fn search_similar_notes_in_track(&self, _query_vector: &[f32], _track: TranscriptTrack, _limit: usize) -> anyhow::Result<Vec<(i64, f32)>> {
+            Ok(Vec::new())
+        }
+
+        pub fn add_transcript_segments(&self, _note_id: i64, _segments: &[TranscriptSegment]) -> anyhow::Result<i64> {
+            Ok(1)
+        }
+
+        pub fn get_transcript_segments(&self, _note_id: i64, _track: TranscriptTrack) -> anyhow::Result<Vec<TranscriptSegment>> {
+            Ok(Vec::new())
+        }
+
+        pub fn add_transcript_version(&self, _note_id: i64, _segments: &[TranscriptSegment]) -> anyhow::Result<i64> {
+            Ok(1)
+        }
+
+        pub fn get_transcript_versions(&self, _note_id: i64) -> anyhow::Result<Vec<i64>> {
+            Ok(Vec::new())
+        }
+
+        pub fn get_transcript_segments_version(&self, _note_id: i64, _track: TranscriptTrack, _version: i64) -> anyhow::Result<Vec<TranscriptSegment>> {
+            Ok(Vec::new())
+        }
+
+        pub fn enqueue_retranscription(&self, _note_id: i64, _model: &str) -> anyhow::Result<i64> {
+            Ok(1)
+        }
+
+        pub fn claim_retranscribe_job(&self) -> anyhow::Result<Option<(i64, i64, String)>> {
+            Ok(None)
+        }
+
+        pub fn set_retranscribe_status(&self, _job_id: i64, _status: &str, _error: Option<&str>) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        pub fn fail_retranscribe_job(&self, _job_id: i64, _error: &str, _max_attempts: i64) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        pub fn complete_retranscribe_job(&self, _job_id: i64, _previous_summary: &str) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        pub fn get_retranscribe_jobs(&self, _note_id: i64) -> anyhow::Result<Vec<(i64, String, i64, i64)>> {
+            Ok(Vec::new())
+        }
+
+        pub fn get_previous_summary(&self, _job_id: i64) -> anyhow::Result<Option<String>> {
+            Ok(None)
+        }
+
+        pub fn save_structured_summary(&self, _note_id: i64, _summary: &StructuredSummary) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        pub fn get_structured_summary(&self, _note_id: i64) -> anyhow::Result<Option<StructuredSummary>> {
+            Ok(None)
+        }
+
+        pub fn get_structured_summaries(&self) -> anyhow::Result<Vec<NoteSummary>> {
+            Ok(Vec::new())
+        }
+
+        pub fn add_partial_summaries(&self, _note_id: i64, _session_id: i64, _track: TranscriptTrack, _partials: &[PartialSummary]) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        pub fn get_partial_summary_sessions(&self, _note_id: i64) -> anyhow::Result<Vec<i64>> {
+            Ok(Vec::new())
+        }
+
+        pub fn replace_partial_summaries(&self, _note_id: i64, _sessions: &[i64], _session_id: i64, _track: TranscriptTrack, _partials: &[PartialSummary]) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        pub fn create_prompt_template(&self, _name: &str, _body: &str) -> anyhow::Result<i64> {
+            Ok(0)
+        }
+
+        pub fn update_prompt_template(&self, _template_id: i64, _name: &str, _body: &str) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        pub fn delete_prompt_template(&self, _template_id: i64) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        pub fn get_prompt_templates(&self) -> anyhow::Result<Vec<(i64, String, String)>> {
+            Ok(Vec::new())
+        }
+
+        pub fn set_folder_template(&self, _folder_id: i64, _template_id: Option<i64>) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        pub fn get_folder_template(&self, _folder_id: i64) -> anyhow::Result<Option<String>> {
+            Ok(None)
+        }
+
+        pub fn get_note_folder(&self, _note_id: i64) -> anyhow::Result<Option<i64>> {
+            Ok(None)
+        }
+
+        pub fn add_cards(&self, _note_id: i64, _cards: &[Flashcard]) -> anyhow::Result<usize> {
+            Ok(0)
+        }
+
+        pub fn get_cards(&self, _note_id: i64) -> anyhow::Result<Vec<Card>> {
+            Ok(Vec::new())
+        }
+
+        pub fn get_cards_with_titles(&self, _folder_id: Option<i64>) -> anyhow::Result<Vec<(Card, String)>> {
+            Ok(Vec::new())
+        }
+
+        pub fn delete_card(&self, _card_id: i64) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        pub fn replace_note_chunks(&self, _note_id: i64, _chunks: &[(Chunk, Option<Vec<f32>>)]) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        pub fn get_stale_chunk_notes(&self, _need_vectors: bool) -> anyhow::Result<Vec<i64>> {
+            Ok(Vec::new())
+        }
+
+        pub fn search_similar_chunks(&self, _query_vector: &[f32], _folder_id: Option<i64>, _limit: usize) -> anyhow::Result<Vec<(Chunk, f32)>> {
+            Ok(Vec::new())
+        }
+
+        pub fn get_chunks(&self, _folder_id: Option<i64>) -> anyhow::Result<Vec<Chunk>> {
+            Ok(Vec::new())
+        }
+
+        pub fn get_due_cards(&self, _folder_id: Option<i64>, _now: i64, _limit: usize) -> anyhow::Result<Vec<Card>> {
+            Ok(Vec::new())
+        }
+
+        pub fn count_due_cards(&self, _folder_id: Option<i64>, _now: i64) -> anyhow::Result<(usize, usize)> {
+            Ok((0, 0))
+        }
+
+        pub fn get_card(&self, _card_id: i64) -> anyhow::Result<Option<Card>> {
+            Ok(None)
+        }
+
+        pub fn record_review(&self, _card_id: i64, _grade: Grade, _state: &ReviewState, _duration_ms: i64, _now: i64) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        pub fn get_modified_cards(&self, _since: i64) -> anyhow::Result<Vec<CardDelta>> {
+            Ok(Vec::new())
+        }
+
+        pub fn get_modified_reviews(&self, _since: i64) -> anyhow::Result<Vec<ReviewDelta>> {
+            Ok(Vec::new())
+        }
+
+        pub fn get_partial_summaries(&self, _note_id: i64) -> anyhow::Result<Vec<PartialSummary>> {
             Ok(Vec::new())
         }
     }
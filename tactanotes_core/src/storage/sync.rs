@@ -1,11 +1,17 @@
 // Feature F09: Cloud Delta Sync
 use serde::{Deserialize, Serialize};
+use crate::study::srs::ReviewState;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SyncBlob {
     pub version: u32,
     pub timestamp: i64,
     pub changes: Vec<NoteDelta>,
+    // F3.2: Flashcards and their review history (absent in blobs from older versions)
+    #[serde(default)]
+    pub cards: Vec<CardDelta>,
+    #[serde(default)]
+    pub reviews: Vec<ReviewDelta>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -16,6 +22,31 @@ pub struct NoteDelta {
     pub updated_at: i64,
 }
 
+// Schedule travels with the card; last `updated_at` wins
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CardDelta {
+    pub id: i64,
+    pub note_id: i64,
+    pub kind: String,
+    pub encrypted_front: Vec<u8>,
+    pub encrypted_back: Vec<u8>,
+    pub review: ReviewState,
+    pub is_deleted: bool,
+    pub updated_at: i64,
+}
+
+// Review log rows are append-only
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ReviewDelta {
+    pub id: i64,
+    pub card_id: i64,
+    pub grade: u8,
+    pub ease: f32,
+    pub interval_days: u32,
+    pub duration_ms: i64,
+    pub reviewed_at: i64,
+}
+
 pub struct SyncEngine;
 
 impl SyncEngine {
@@ -26,6 +57,11 @@ impl SyncEngine {
     // "Packing" Logic (Encryption + Serialization)
     // Takes raw modified rows from DB and creates a transport-ready BLOB
     pub fn pack_delta(notes: Vec<(i64, String, Vec<u8>, i64)>) -> Result<Vec<u8>, serde_json::Error> {
+        Self::pack_delta_with_study(notes, Vec::new(), Vec::new())
+    }
+
+    // F3.2: Notes plus flashcard and review changes in one blob
+    pub fn pack_delta_with_study(notes: Vec<(i64, String, Vec<u8>, i64)>, cards: Vec<CardDelta>, reviews: Vec<ReviewDelta>) -> Result<Vec<u8>, serde_json::Error> {
         let deltas: Vec<NoteDelta> = notes.into_iter().map(|(id, title, encrypted_content, updated_at)| {
             NoteDelta {
                 id,
@@ -39,6 +75,8 @@ impl SyncEngine {
             version: 1,
             timestamp: chrono::Utc::now().timestamp(),
             changes: deltas,
+            cards,
+            reviews,
         };
 
         // Serialize to generic binary (JSON for MVP, Protobuf for Prod)
@@ -0,0 +1,233 @@
+// Feature F3.1: Anki export
+// CSV: Anki's text import with header lines (2.1.54+), one card per row with front, back, tags.
+// .apkg: a zip holding `collection.anki2` (an Anki 2.1 collection, schema 11) and an empty
+// `media` map. Every card is exported as a Front/Back note of one "TACTANOTES" note type; cloze
+// fronts already show the blank, so Anki's own cloze type is not needed.
+
+use super::cards::Card;
+use std::path::Path;
+
+// Fixed so repeated imports reuse the same note type instead of adding a copy each time
+#[cfg(not(target_arch = "wasm32"))]
+const MODEL_ID: i64 = 1_700_000_000_042;
+
+// `cards` with the title of the note each came from
+pub fn to_csv(cards: &[(Card, String)]) -> String {
+    let mut csv = String::from("#separator:Comma\n#html:false\n#tags column:3\n");
+    for (card, note_title) in cards {
+        csv.push_str(&format!("{},{},{}\n", csv_field(&card.front), csv_field(&card.back), csv_field(&tags(card, note_title))));
+    }
+    csv
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// Space separated; Anki tags cannot contain spaces
+fn tags(card: &Card, note_title: &str) -> String {
+    let title: String = note_title.trim()
+        .chars()
+        .map(|c| if c.is_whitespace() { '_' } else { c })
+        .collect();
+    if title.is_empty() {
+        format!("tactanotes {}", card.kind.as_str())
+    } else {
+        format!("tactanotes {} {}", card.kind.as_str(), title)
+    }
+}
+
+pub fn write_csv(path: &Path, cards: &[(Card, String)]) -> anyhow::Result<()> {
+    std::fs::write(path, to_csv(cards))
+        .map_err(|e| anyhow::anyhow!("Failed to write {:?}: {}", path, e))
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn write_apkg(_path: &Path, _deck_name: &str, _cards: &[(Card, String)]) -> anyhow::Result<()> {
+    Err(anyhow::anyhow!(".apkg export is not available on the web build, use CSV"))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_apkg(path: &Path, deck_name: &str, cards: &[(Card, String)]) -> anyhow::Result<()> {
+    use std::io::Write;
+
+    // The collection is built as a SQLite file next to the output, then zipped
+    let collection_path = path.with_extension("anki2.tmp");
+    let _ = std::fs::remove_file(&collection_path);
+    let built = build_collection(&collection_path, deck_name, cards);
+    let collection = built.and_then(|_| Ok(std::fs::read(&collection_path)?));
+    let _ = std::fs::remove_file(&collection_path);
+    let collection = collection.map_err(|e| anyhow::anyhow!("Failed to build Anki collection: {}", e))?;
+
+    let file = std::fs::File::create(path).map_err(|e| anyhow::anyhow!("Failed to create {:?}: {}", path, e))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    zip.start_file("collection.anki2", options)?;
+    zip.write_all(&collection)?;
+    zip.start_file("media", options)?;
+    zip.write_all(b"{}")?;
+    zip.finish()?;
+    println!("Anki: Exported {} cards to {:?}.", cards.len(), path);
+    Ok(())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn build_collection(path: &Path, deck_name: &str, cards: &[(Card, String)]) -> anyhow::Result<()> {
+    use rusqlite::{params, Connection};
+    use serde_json::json;
+
+    let conn = Connection::open(path)?;
+    conn.execute_batch(SCHEMA)?;
+
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let now = now_ms / 1000;
+    let deck_id = now_ms;
+
+    let model = json!({
+        "id": MODEL_ID, "name": "TACTANOTES", "type": 0, "mod": now, "usn": -1, "sortf": 0,
+        "did": deck_id, "tags": [], "vers": [], "req": [[0, "any", [0]]],
+        "flds": [field("Front", 0), field("Back", 1)],
+        "tmpls": [{
+            "name": "Card 1", "ord": 0, "did": null, "bqfmt": "", "bafmt": "",
+            "qfmt": "{{Front}}", "afmt": "{{FrontSide}}<hr id=answer>{{Back}}",
+        }],
+        "css": ".card { font-family: arial; font-size: 20px; text-align: center; color: black; background-color: white; }",
+        "latexPre": "\\documentclass[12pt]{article}\n\\special{papersize=3in,5in}\n\\usepackage[utf8]{inputenc}\n\\usepackage{amssymb,amsmath}\n\\pagestyle{empty}\n\\setlength{\\parindent}{0in}\n\\begin{document}\n",
+        "latexPost": "\\end{document}",
+    });
+    let deck = |id: i64, name: &str| json!({
+        "id": id, "name": name, "mod": now, "usn": -1, "desc": "", "dyn": 0, "conf": 1,
+        "collapsed": false, "extendNew": 10, "extendRev": 50,
+        "newToday": [0, 0], "revToday": [0, 0], "lrnToday": [0, 0], "timeToday": [0, 0],
+    });
+    let decks = json!({ "1": deck(1, "Default"), deck_id.to_string(): deck(deck_id, deck_name) });
+    let dconf = json!({ "1": {
+        "id": 1, "name": "Default", "mod": 0, "usn": 0, "maxTaken": 60, "autoplay": true,
+        "timer": 0, "replayq": true, "dyn": false,
+        "new": { "delays": [1, 10], "ints": [1, 4, 7], "initialFactor": 2500, "order": 1, "perDay": 20, "bury": true, "separate": true },
+        "rev": { "perDay": 200, "ease4": 1.3, "fuzz": 0.05, "minSpace": 1, "ivlFct": 1, "maxIvl": 36500, "bury": true },
+        "lapse": { "delays": [10], "mult": 0, "minInt": 1, "leechFails": 8, "leechAction": 0 },
+    }});
+    let conf = json!({
+        "nextPos": cards.len() + 1, "estTimes": true, "activeDecks": [1], "sortType": "noteFld",
+        "timeLim": 0, "sortBackwards": false, "addToCur": true, "curDeck": 1, "newSpread": 0,
+        "dueCounts": true, "curModel": MODEL_ID.to_string(), "collapseTime": 1200,
+    });
+    let models = json!({ MODEL_ID.to_string(): model });
+    conn.execute(
+        "INSERT INTO col VALUES (1, ?1, ?2, ?2, 11, 0, 0, 0, ?3, ?4, ?5, ?6, '{}')",
+        params![now, now_ms, conf.to_string(), models.to_string(), decks.to_string(), dconf.to_string()],
+    )?;
+
+    for (i, (card, note_title)) in cards.iter().enumerate() {
+        let id = now_ms + i as i64;
+        let front = html_escape(&card.front);
+        conn.execute(
+            "INSERT INTO notes VALUES (?1, ?2, ?3, ?4, -1, ?5, ?6, ?7, ?8, 0, '')",
+            params![
+                id,
+                format!("tactanotes-{}", card.id), // Stable, so re-importing updates the note
+                MODEL_ID,
+                now,
+                format!(" {} ", tags(card, note_title)),
+                format!("{}\x1f{}", front, html_escape(&card.back)),
+                front,
+                checksum(&card.front),
+            ],
+        )?;
+        // New card at position i
+        conn.execute(
+            "INSERT INTO cards VALUES (?1, ?1, ?2, 0, ?3, -1, 0, 0, ?4, 0, 0, 0, 0, 0, 0, 0, 0, '')",
+            params![id, deck_id, now, i as i64 + 1],
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn field(name: &str, ord: i64) -> serde_json::Value {
+    serde_json::json!({ "name": name, "ord": ord, "sticky": false, "rtl": false, "font": "Arial", "size": 20, "media": [] })
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('\n', "<br>")
+}
+
+// Anki's duplicate check: first 8 hex digits of the SHA-1 of the sort field
+#[cfg(not(target_arch = "wasm32"))]
+fn checksum(text: &str) -> i64 {
+    let digest = ring::digest::digest(&ring::digest::SHA1_FOR_LEGACY_USE_ONLY, text.as_bytes());
+    let bytes = digest.as_ref();
+    i64::from(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+const SCHEMA: &str = "
+CREATE TABLE col (id integer primary key, crt integer not null, mod integer not null, scm integer not null,
+    ver integer not null, dty integer not null, usn integer not null, ls integer not null, conf text not null,
+    models text not null, decks text not null, dconf text not null, tags text not null);
+CREATE TABLE notes (id integer primary key, guid text not null, mid integer not null, mod integer not null,
+    usn integer not null, tags text not null, flds text not null, sfld integer not null, csum integer not null,
+    flags integer not null, data text not null);
+CREATE TABLE cards (id integer primary key, nid integer not null, did integer not null, ord integer not null,
+    mod integer not null, usn integer not null, type integer not null, queue integer not null, due integer not null,
+    ivl integer not null, factor integer not null, reps integer not null, lapses integer not null, left integer not null,
+    odue integer not null, odid integer not null, flags integer not null, data text not null);
+CREATE TABLE revlog (id integer primary key, cid integer not null, usn integer not null, ease integer not null,
+    ivl integer not null, lastIvl integer not null, factor integer not null, time integer not null, type integer not null);
+CREATE TABLE graves (usn integer not null, oid integer not null, type integer not null);
+CREATE INDEX ix_notes_usn on notes (usn);
+CREATE INDEX ix_cards_usn on cards (usn);
+CREATE INDEX ix_revlog_usn on revlog (usn);
+CREATE INDEX ix_cards_nid on cards (nid);
+CREATE INDEX ix_cards_sched on cards (did, queue, due);
+CREATE INDEX ix_revlog_cid on revlog (cid);
+CREATE INDEX ix_notes_csum on notes (csum);
+";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::study::cards::CardKind;
+
+    fn card(id: i64, front: &str, back: &str) -> (Card, String) {
+        (Card { id, note_id: 7, kind: CardKind::Cloze, front: front.to_string(), back: back.to_string(), review: Default::default() }, "Thermo week 3".to_string())
+    }
+
+    #[test]
+    fn test_csv_quotes_fields() {
+        let csv = to_csv(&[card(1, "_____: disorder, roughly", "Entropy"), card(2, "Say \"hi\"", "Hi")]);
+        assert_eq!(csv, "#separator:Comma\n#html:false\n#tags column:3\n\
+            \"_____: disorder, roughly\",Entropy,tactanotes cloze Thermo_week_3\n\
+            \"Say \"\"hi\"\"\",Hi,tactanotes cloze Thermo_week_3\n");
+    }
+
+    #[test]
+    fn test_apkg_holds_an_anki_collection() {
+        let dir = std::env::temp_dir().join(format!("tactanotes_apkg_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("deck.apkg");
+        write_apkg(&path, "Thermodynamics", &[card(1, "_____: disorder", "Entropy"), card(2, "1 < 2?", "Yes")]).unwrap();
+
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&path).unwrap()).unwrap();
+        let extracted = dir.join("collection.anki2");
+        std::io::copy(&mut archive.by_name("collection.anki2").unwrap(), &mut std::fs::File::create(&extracted).unwrap()).unwrap();
+        assert!(archive.by_name("media").is_ok());
+
+        let conn = rusqlite::Connection::open(&extracted).unwrap();
+        let fields: Vec<String> = conn.prepare("SELECT flds FROM notes ORDER BY id").unwrap()
+            .query_map([], |row| row.get(0)).unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(fields, vec!["_____: disorder\x1fEntropy".to_string(), "1 &lt; 2?\x1fYes".to_string()]);
+        let cards: i64 = conn.query_row("SELECT COUNT(*) FROM cards", [], |row| row.get(0)).unwrap();
+        assert_eq!(cards, 2);
+        let decks: String = conn.query_row("SELECT decks FROM col", [], |row| row.get(0)).unwrap();
+        assert!(decks.contains("\"Thermodynamics\""));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
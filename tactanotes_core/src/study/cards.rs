@@ -0,0 +1,196 @@
+// Feature F3: Flashcards
+// Cloze cards come from the defined terms of a note's structured summary (F2.1): the definition
+// itself, and key points that use the term, with the term blanked out. With a generative model
+// installed, question/answer cards are asked for as well (see `qa_prompt`).
+
+use crate::ai::structured::StructuredSummary;
+use super::srs::ReviewState;
+
+pub const CLOZE_BLANK: &str = "_____";
+const MAX_QA_CARDS: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CardKind {
+    Cloze, // Term blanked out of a definition or key point
+    Qa,    // Written by the LLM
+}
+
+impl CardKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Cloze => "cloze",
+            Self::Qa => "qa",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "cloze" => Some(Self::Cloze),
+            "qa" | "basic" => Some(Self::Qa),
+            _ => None,
+        }
+    }
+}
+
+// A generated card, before it is stored
+#[derive(Debug, Clone, PartialEq)]
+pub struct Flashcard {
+    pub kind: CardKind,
+    pub front: String,
+    pub back: String,
+}
+
+// A card in the `cards` table
+#[derive(Debug, Clone, PartialEq)]
+pub struct Card {
+    pub id: i64,
+    pub note_id: i64,
+    pub kind: CardKind,
+    pub front: String,
+    pub back: String,
+    pub review: ReviewState, // F3.2 schedule
+}
+
+pub fn cloze_cards(summary: &StructuredSummary) -> Vec<Flashcard> {
+    let mut cards = Vec::new();
+    for definition in &summary.definitions {
+        push_unique(&mut cards, Flashcard {
+            kind: CardKind::Cloze,
+            front: format!("{}: {}", CLOZE_BLANK, definition.definition),
+            back: definition.term.clone(),
+        });
+    }
+
+    // Longest term first, so "kinetic energy" wins over "energy"
+    let mut terms: Vec<&str> = summary.definitions.iter().map(|d| d.term.as_str()).collect();
+    terms.sort_by_key(|t| std::cmp::Reverse(t.len()));
+    for point in &summary.key_points {
+        if let Some((term, front)) = terms.iter().find_map(|t| blank_term(point, t).map(|f| (t, f))) {
+            push_unique(&mut cards, Flashcard { kind: CardKind::Cloze, front, back: term.to_string() });
+        }
+    }
+    cards
+}
+
+// `sentence` with the first whole-word, case-insensitive match of `term` blanked
+fn blank_term(sentence: &str, term: &str) -> Option<String> {
+    if term.is_empty() { return None; }
+    // ASCII lowercasing keeps byte offsets
+    let lower = sentence.to_ascii_lowercase();
+    let needle = term.to_ascii_lowercase();
+    let mut from = 0;
+    while let Some(at) = lower[from..].find(&needle).map(|i| i + from) {
+        let end = at + needle.len();
+        let before = sentence[..at].chars().next_back().is_none_or(|c| !c.is_alphanumeric());
+        let after = sentence[end..].chars().next().is_none_or(|c| !c.is_alphanumeric());
+        if before && after {
+            return Some(format!("{}{}{}", &sentence[..at], CLOZE_BLANK, &sentence[end..]));
+        }
+        from = end;
+    }
+    None
+}
+
+// Prompt template (ai/prompt.rs) asking the LLM for question/answer cards
+pub fn qa_prompt() -> String {
+    format!(
+        "Write up to {} flashcards that test the most important facts in these lecture notes. \
+         Write each card as a line starting with \"Q: \" with the question, then a line starting \
+         with \"A: \" with a short answer. Write nothing else.\n\nNotes:\n{{{{transcript}}}}",
+        MAX_QA_CARDS,
+    )
+}
+
+// Reads "Q: ... / A: ..." pairs; an answer may run over several lines
+pub fn parse_qa(output: &str) -> Vec<Flashcard> {
+    let mut cards = Vec::new();
+    let mut question: Option<String> = None;
+    let mut answer: Option<String> = None;
+    fn finish(question: &mut Option<String>, answer: &mut Option<String>, cards: &mut Vec<Flashcard>) {
+        if let (Some(q), Some(a)) = (question.take(), answer.take()) {
+            if !q.is_empty() && !a.is_empty() && cards.len() < MAX_QA_CARDS {
+                push_unique(cards, Flashcard { kind: CardKind::Qa, front: q, back: a });
+            }
+        }
+    }
+    for line in output.lines() {
+        let line = line.trim().trim_start_matches(['-', '*']).trim();
+        if let Some(q) = strip_label(line, &["Q:", "Question:"]) {
+            finish(&mut question, &mut answer, &mut cards);
+            question = Some(q.to_string());
+        } else if let Some(a) = strip_label(line, &["A:", "Answer:"]) {
+            if question.is_some() {
+                answer = Some(a.to_string());
+            }
+        } else if let Some(a) = answer.as_mut() {
+            if !line.is_empty() {
+                a.push(' ');
+                a.push_str(line);
+            }
+        }
+    }
+    finish(&mut question, &mut answer, &mut cards);
+    cards
+}
+
+fn strip_label<'a>(line: &'a str, labels: &[&str]) -> Option<&'a str> {
+    labels.iter().find_map(|label| {
+        let head = line.get(..label.len())?;
+        head.eq_ignore_ascii_case(label).then(|| line[label.len()..].trim())
+    })
+}
+
+// Same front (ignoring case and spacing) means same card
+pub fn same_front(a: &str, b: &str) -> bool {
+    let key = |s: &str| s.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+    key(a) == key(b)
+}
+
+fn push_unique(cards: &mut Vec<Flashcard>, card: Flashcard) {
+    if !cards.iter().any(|c| same_front(&c.front, &card.front)) {
+        cards.push(card);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::structured::Definition;
+
+    #[test]
+    fn test_cloze_cards_blank_defined_terms() {
+        let summary = StructuredSummary {
+            key_points: vec![
+                "Kinetic energy grows with the square of speed.".to_string(),
+                "Energy is conserved in a closed system.".to_string(),
+                "Potential wells trap particles.".to_string(),
+            ],
+            definitions: vec![
+                Definition { term: "Energy".to_string(), definition: "the capacity to do work".to_string() },
+                Definition { term: "kinetic energy".to_string(), definition: "energy of motion".to_string() },
+            ],
+            ..Default::default()
+        };
+        let cards = cloze_cards(&summary);
+        let fronts: Vec<&str> = cards.iter().map(|c| c.front.as_str()).collect();
+        assert_eq!(fronts, vec![
+            "_____: the capacity to do work",
+            "_____: energy of motion",
+            "_____ grows with the square of speed.",
+            "_____ is conserved in a closed system.",
+        ]);
+        assert_eq!(cards[2].back, "kinetic energy");
+        assert!(blank_term("Energetic students", "energy").is_none());
+    }
+
+    #[test]
+    fn test_qa_output_is_parsed() {
+        let output = "Here are your cards:\nQ: What does entropy measure?\nA: Disorder of a system,\n   in J/K.\n\n\
+            - Q: Who published the Carnot cycle?\n- A: Sadi Carnot\nQ: Unanswered question?\nQ: What does entropy measure?\nA: Again";
+        let cards = parse_qa(output);
+        assert_eq!(cards.len(), 2);
+        assert_eq!(cards[0].back, "Disorder of a system, in J/K.");
+        assert_eq!(cards[1], Flashcard { kind: CardKind::Qa, front: "Who published the Carnot cycle?".to_string(), back: "Sadi Carnot".to_string() });
+        assert!(crate::ai::prompt::validate(&qa_prompt()).is_ok());
+    }
+}
@@ -0,0 +1,3 @@
+pub mod cards;
+pub mod anki;
+pub mod srs;
@@ -0,0 +1,136 @@
+// Feature F3.2: Spaced repetition (SM-2)
+// Classic SuperMemo-2 with Anki's four answer buttons. "Again" puts the card back into a short
+// relearning step instead of resetting it to tomorrow, so a lapse is seen again in the same session.
+// Runs entirely on device; the state lives on the card row and every answer is logged.
+
+use serde::{Deserialize, Serialize};
+
+const INITIAL_EASE: f32 = 2.5;
+const MIN_EASE: f32 = 1.3;
+const EASY_BONUS: f32 = 1.3;
+const HARD_FACTOR: f32 = 1.2;
+const RELEARN_SECONDS: i64 = 10 * 60;
+const MAX_INTERVAL_DAYS: u32 = 36500;
+const DAY_SECONDS: i64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Grade {
+    Again = 1,
+    Hard = 2,
+    Good = 3,
+    Easy = 4,
+}
+
+impl Grade {
+    pub fn from_button(button: u8) -> Option<Self> {
+        match button {
+            1 => Some(Self::Again),
+            2 => Some(Self::Hard),
+            3 => Some(Self::Good),
+            4 => Some(Self::Easy),
+            _ => None,
+        }
+    }
+
+    // SM-2 response quality (0-5)
+    fn quality(&self) -> f32 {
+        match self {
+            Self::Again => 1.0,
+            Self::Hard => 3.0,
+            Self::Good => 4.0,
+            Self::Easy => 5.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ReviewState {
+    pub ease: f32,
+    pub interval_days: u32,
+    pub repetitions: u32, // Successful reviews in a row
+    pub lapses: u32,
+    pub due_at: i64, // Unix seconds; 0 = new, never reviewed
+}
+
+impl Default for ReviewState {
+    fn default() -> Self {
+        Self { ease: INITIAL_EASE, interval_days: 0, repetitions: 0, lapses: 0, due_at: 0 }
+    }
+}
+
+impl ReviewState {
+    pub fn is_new(&self) -> bool {
+        self.due_at == 0
+    }
+
+    pub fn review(&self, grade: Grade, now: i64) -> ReviewState {
+        let q = grade.quality();
+        let ease = (self.ease + 0.1 - (5.0 - q) * (0.08 + (5.0 - q) * 0.02)).max(MIN_EASE);
+
+        if grade == Grade::Again {
+            return ReviewState {
+                ease,
+                interval_days: 0,
+                repetitions: 0,
+                lapses: self.lapses + u32::from(self.repetitions > 0),
+                due_at: now + RELEARN_SECONDS,
+            };
+        }
+
+        let interval = match (self.repetitions, grade) {
+            (0, Grade::Easy) => 4.0,
+            (0, _) => 1.0,
+            (1, Grade::Hard) => 3.0,
+            (1, _) => 6.0,
+            (_, Grade::Hard) => self.interval_days as f32 * HARD_FACTOR,
+            (_, Grade::Easy) => self.interval_days as f32 * ease * EASY_BONUS,
+            _ => self.interval_days as f32 * ease,
+        };
+        // Always at least a day further than last time, so Hard cannot stall a card
+        let interval_days = (interval.round() as u32)
+            .max(self.interval_days + u32::from(self.repetitions > 1))
+            .clamp(1, MAX_INTERVAL_DAYS);
+
+        ReviewState {
+            ease,
+            interval_days,
+            repetitions: self.repetitions + 1,
+            lapses: self.lapses,
+            due_at: now + interval_days as i64 * DAY_SECONDS,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sm2_intervals_grow_and_lapses_relearn() {
+        let now = 1_700_000_000;
+        let card = ReviewState::default();
+        assert!(card.is_new());
+
+        let first = card.review(Grade::Good, now);
+        assert_eq!((first.interval_days, first.repetitions), (1, 1));
+        assert_eq!(first.due_at, now + DAY_SECONDS);
+        assert!((first.ease - 2.5).abs() < 1e-6); // Good keeps the ease
+
+        let second = first.review(Grade::Good, first.due_at);
+        assert_eq!(second.interval_days, 6);
+        let third = second.review(Grade::Good, second.due_at);
+        assert_eq!(third.interval_days, 15);
+        let easy = second.review(Grade::Easy, second.due_at);
+        assert!(easy.interval_days > third.interval_days && easy.ease > third.ease);
+        let hard = second.review(Grade::Hard, second.due_at);
+        assert!(hard.interval_days > second.interval_days && hard.ease < second.ease);
+
+        let lapse = third.review(Grade::Again, third.due_at);
+        assert_eq!((lapse.repetitions, lapse.lapses), (0, 1));
+        assert_eq!(lapse.due_at, third.due_at + RELEARN_SECONDS);
+        assert!(lapse.ease >= MIN_EASE && lapse.ease < third.ease);
+
+        assert_eq!(Grade::from_button(3), Some(Grade::Good));
+        assert_eq!(Grade::from_button(5), None);
+    }
+}
@@ -0,0 +1,56 @@
+use tactanotes_core::ai::asr::{AsrBackendKind, AsrSegment, DecodeOptions, MockAsr};
+use tactanotes_core::ai::manager::ModelManager;
+
+#[test]
+fn test_scripted_mock_through_model_manager() {
+    println!(">>> TESTING ASR BACKEND SELECTION <<<");
+
+    let mut manager = ModelManager::new("./models");
+    assert_eq!(manager.asr_backend(), AsrBackendKind::platform_default());
+
+    // Configured mock loads without model files
+    manager.set_asr_backend(AsrBackendKind::Mock);
+    manager.load_asr();
+    assert!(manager.asr_capabilities().is_some());
+    assert!(manager.transcribe_segments(&[0.0; 16000], &DecodeOptions::default()).is_empty());
+
+    // Scripted mock drives deterministic output
+    let mock = MockAsr::with_script(vec![vec![AsrSegment {
+        text: "Entropy is a measure of disorder.".to_string(),
+        start_ms: 0,
+        end_ms: 3000,
+        avg_token_prob: 0.92,
+        min_token_prob: 0.71,
+        no_speech_prob: Some(0.0),
+        language: None,
+    }]]);
+    manager.install_asr_backend(Box::new(mock));
+
+    let segments = manager.transcribe_segments(&[0.0; 48000], &DecodeOptions::default());
+    assert_eq!(segments.len(), 1);
+    assert_eq!(segments[0].text, "Entropy is a measure of disorder.");
+    assert_eq!(manager.transcribe(&[0.0; 48000]), "");
+
+    assert_eq!(AsrBackendKind::parse("Moonshine"), Some(AsrBackendKind::Onnx));
+    assert_eq!(AsrBackendKind::parse("tesseract"), None);
+}
+
+#[test]
+fn test_translate_mode_needs_a_multilingual_model() {
+    let dir = std::env::temp_dir().join(format!("tactanotes_multilingual_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut manager = ModelManager::new(dir.to_str().unwrap());
+
+    // Nothing loaded yet, and no multilingual model installed for either backend
+    manager.set_asr_backend(AsrBackendKind::Whisper);
+    assert!(manager.set_multilingual(true).is_err());
+    manager.set_asr_backend(AsrBackendKind::Onnx);
+    assert!(manager.set_multilingual(true).is_err());
+    assert!(manager.set_multilingual(false).is_ok());
+
+    manager.set_asr_backend(AsrBackendKind::Mock);
+    manager.load_asr();
+    assert!(manager.set_multilingual(true).is_ok());
+    assert!(manager.asr_capabilities().unwrap().translation);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,145 @@
+use tactanotes_core::study::cards::{CardKind, Flashcard};
+use tactanotes_core::study::srs::{Grade, ReviewState};
+use tactanotes_core::Database;
+
+// Feature F3.2: Card schedules, the review log and the due queue; F09: their sync deltas
+
+const DAY: i64 = 24 * 60 * 60;
+
+fn temp_db(name: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("tactanotes_{}_{}.db", name, std::process::id()));
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+    }
+    path
+}
+
+fn card(front: &str, back: &str) -> Flashcard {
+    Flashcard { kind: CardKind::Qa, front: front.to_string(), back: back.to_string() }
+}
+
+// Grades the card as the study screen does: next state from the stored one, then record it
+fn grade(db: &Database, card_id: i64, grade: Grade, now: i64) -> ReviewState {
+    let state = db.get_card(card_id).unwrap().unwrap().review.review(grade, now);
+    db.record_review(card_id, grade, &state, 4000, now).unwrap();
+    state
+}
+
+// Four cards on one note, in order: entropy, carnot, kelvin, joule
+fn seeded(db: &Database) -> Vec<i64> {
+    let note_id = db.add_note("Thermodynamics", "", None).unwrap();
+    db.add_cards(note_id, &[
+        card("What is entropy?", "A measure of disorder"),
+        card("What limits a heat engine's efficiency?", "The Carnot efficiency"),
+        card("What is absolute zero in Celsius?", "-273.15"),
+        card("What is the SI unit of energy?", "The joule"),
+    ]).unwrap();
+    db.get_cards(note_id).unwrap().iter().map(|c| c.id).collect()
+}
+
+#[test]
+fn test_review_updates_the_schedule_and_appends_to_the_log() {
+    let path = temp_db("cards_review");
+    let db = Database::open(path.to_str().unwrap(), "pw").unwrap();
+    let ids = seeded(&db);
+    let now = chrono::Utc::now().timestamp();
+
+    assert!(db.get_card(ids[0]).unwrap().unwrap().review.is_new());
+    let first = grade(&db, ids[0], Grade::Good, now);
+    assert_eq!(db.get_card(ids[0]).unwrap().unwrap().review, first);
+    assert_eq!((first.interval_days, first.repetitions, first.due_at), (1, 1, now + DAY));
+
+    let second = grade(&db, ids[0], Grade::Again, now + DAY);
+    let stored = db.get_card(ids[0]).unwrap().unwrap().review;
+    assert_eq!(stored, second);
+    assert_eq!((stored.repetitions, stored.lapses), (0, 1));
+
+    // Both answers are logged, oldest first, with the schedule they produced
+    let log = db.get_modified_reviews(0).unwrap();
+    assert_eq!(log.len(), 2);
+    assert!(log.iter().all(|r| r.card_id == ids[0] && r.duration_ms == 4000));
+    assert_eq!(log.iter().map(|r| r.grade).collect::<Vec<_>>(), vec![Grade::Good as u8, Grade::Again as u8]);
+    assert_eq!(log.iter().map(|r| r.reviewed_at).collect::<Vec<_>>(), vec![now, now + DAY]);
+    assert_eq!((log[0].ease, log[0].interval_days), (first.ease, 1));
+    assert_eq!((log[1].ease, log[1].interval_days), (second.ease, 0));
+    // The other cards are untouched
+    assert!(db.get_card(ids[1]).unwrap().unwrap().review.is_new());
+
+    drop(db);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_due_queue_after_grading() {
+    let path = temp_db("cards_due");
+    let db = Database::open(path.to_str().unwrap(), "pw").unwrap();
+    let ids = seeded(&db);
+    let now = chrono::Utc::now().timestamp();
+    let due = |at: i64| db.get_due_cards(None, at, 10).unwrap().iter().map(|c| c.id).collect::<Vec<_>>();
+
+    // Nothing reviewed yet: all new, in the order they were made
+    assert_eq!(due(now), ids);
+    assert_eq!(db.count_due_cards(None, now).unwrap(), (0, 4));
+
+    grade(&db, ids[0], Grade::Good, now);           // Due tomorrow
+    grade(&db, ids[1], Grade::Again, now);          // Relearned in ten minutes
+    grade(&db, ids[3], Grade::Easy, now - 5 * DAY); // Overdue since yesterday
+
+    // Overdue reviews first, oldest first, then new cards; cards not yet due are left out
+    assert_eq!(due(now + 3600), vec![ids[3], ids[1], ids[2]]);
+    assert_eq!(db.count_due_cards(None, now + 3600).unwrap(), (2, 1));
+    assert_eq!(due(now + 2 * DAY), vec![ids[3], ids[1], ids[0], ids[2]]);
+    assert_eq!(db.get_due_cards(None, now + 2 * DAY, 2).unwrap().len(), 2);
+
+    // Deleted cards leave the queue
+    db.delete_card(ids[3]).unwrap();
+    assert_eq!(due(now + 2 * DAY), vec![ids[1], ids[0], ids[2]]);
+    assert!(db.get_card(ids[3]).unwrap().is_none());
+
+    drop(db);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_card_and_review_deltas_since_a_timestamp() {
+    let path = temp_db("cards_delta");
+    let db = Database::open(path.to_str().unwrap(), "pw").unwrap();
+    let before = chrono::Utc::now().timestamp() - 1;
+    let ids = seeded(&db);
+    let later = before + 1000;
+
+    let reviewed = grade(&db, ids[0], Grade::Good, later + 10);
+    db.delete_card(ids[1]).unwrap();
+
+    // Everything since before the cards were made, including the tombstone
+    let cards = db.get_modified_cards(before).unwrap();
+    assert_eq!(cards.len(), 4);
+    let deleted = cards.iter().find(|c| c.id == ids[1]).unwrap();
+    assert!(deleted.is_deleted);
+    assert!(cards.iter().filter(|c| c.id != ids[1]).all(|c| !c.is_deleted));
+    let delta = cards.iter().find(|c| c.id == ids[0]).unwrap();
+    assert_eq!(delta.review, reviewed);
+    assert_eq!((delta.kind.as_str(), delta.updated_at), ("qa", later + 10));
+    // Front and back leave the device encrypted
+    assert_ne!(delta.encrypted_front, b"What is entropy?".to_vec());
+    assert!(!delta.encrypted_back.is_empty());
+
+    // Only the review happened after `later`
+    let cards = db.get_modified_cards(later).unwrap();
+    assert_eq!(cards.iter().map(|c| c.id).collect::<Vec<_>>(), vec![ids[0]]);
+    let reviews = db.get_modified_reviews(later).unwrap();
+    assert_eq!(reviews.len(), 1);
+    assert_eq!((reviews[0].card_id, reviews[0].reviewed_at), (ids[0], later + 10));
+    assert!(db.get_modified_reviews(later + 10).unwrap().is_empty());
+
+    // A later delete is exported as a tombstone on its own
+    let since = chrono::Utc::now().timestamp();
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+    db.delete_card(ids[2]).unwrap();
+    let cards = db.get_modified_cards(since).unwrap();
+    let tombstones: Vec<_> = cards.iter().filter(|c| c.is_deleted).map(|c| c.id).collect();
+    assert_eq!(tombstones, vec![ids[2]]);
+
+    drop(db);
+    let _ = std::fs::remove_file(&path);
+}
@@ -0,0 +1,86 @@
+use tactanotes_core::Database;
+
+// Feature F2.7: Tags (migration v1 from the old notes.tags column, and the note_tags table)
+
+fn temp_db(name: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("tactanotes_{}_{}.db", name, std::process::id()));
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+    }
+    path
+}
+
+fn tags(db: &Database, note_id: i64) -> Vec<(String, String)> {
+    db.get_note_tags(note_id).unwrap()
+}
+
+fn pair(name: &str, source: &str) -> (String, String) {
+    (name.to_string(), source.to_string())
+}
+
+#[test]
+fn test_old_tags_column_is_migrated() {
+    let path = temp_db("tags_migration");
+    {
+        // Schema before F2.7: comma (or # / ;) separated tags on the note itself
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE notes (
+                 id INTEGER PRIMARY KEY, title TEXT, content TEXT, created_at INTEGER, updated_at INTEGER,
+                 is_deleted INTEGER DEFAULT 0, tags TEXT
+             );
+             INSERT INTO notes (id, title, tags) VALUES (1, 'Thermo', 'Physics, #Entropy; heat  engines,physics');
+             INSERT INTO notes (id, title, tags) VALUES (2, 'Empty', '');
+             INSERT INTO notes (id, title, tags) VALUES (3, 'None', NULL);",
+        ).unwrap();
+    }
+
+    let db = Database::open(path.to_str().unwrap(), "pw").unwrap();
+    let migrated = vec![pair("entropy", "user"), pair("heat engines", "user"), pair("physics", "user")];
+    assert_eq!(tags(&db, 1), migrated);
+    assert!(tags(&db, 2).is_empty() && tags(&db, 3).is_empty());
+    drop(db);
+
+    {
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        assert!(conn.prepare("SELECT tags FROM notes").is_err(), "the tags column is dropped");
+        let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap();
+        assert!(version >= 1);
+    }
+
+    // Opening again runs nothing twice
+    let db = Database::open(path.to_str().unwrap(), "pw").unwrap();
+    assert_eq!(tags(&db, 1), migrated);
+    assert_eq!(db.get_tags().unwrap(), vec![("entropy".to_string(), 1), ("heat engines".to_string(), 1), ("physics".to_string(), 1)]);
+}
+
+#[test]
+fn test_user_tags_outlive_automatic_ones() {
+    let path = temp_db("tags");
+    let db = Database::open(path.to_str().unwrap(), "pw").unwrap();
+    let note = db.add_note("Thermo", "Entropy and heat engines", None).unwrap();
+    let other = db.add_note("Stats", "Entropy in information theory", None).unwrap();
+    let names = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+    assert_eq!(db.add_note_tags(note, &names(&["entropy", "carnot"]), "user").unwrap(), 2);
+    assert_eq!(db.add_note_tags(note, &names(&["entropy"]), "user").unwrap(), 0);
+    assert_eq!(db.add_note_tags(other, &names(&["entropy"]), "user").unwrap(), 1);
+
+    // An automatic tag the user already set stays theirs
+    db.set_auto_tags(note, &names(&["heat engines", "entropy"])).unwrap();
+    assert_eq!(tags(&db, note), vec![pair("carnot", "user"), pair("entropy", "user"), pair("heat engines", "auto")]);
+
+    // Removed automatic tags are not proposed again; the other automatic ones are replaced
+    assert!(db.remove_note_tag(note, "heat engines").unwrap());
+    db.set_auto_tags(note, &names(&["heat engines", "second law"])).unwrap();
+    assert_eq!(tags(&db, note), vec![pair("carnot", "user"), pair("entropy", "user"), pair("second law", "auto")]);
+
+    assert!(db.remove_note_tag(note, "carnot").unwrap());
+    assert!(!db.remove_note_tag(note, "carnot").unwrap());
+    assert!(!db.remove_note_tag(note, "unknown").unwrap());
+
+    assert_eq!(db.get_tags().unwrap()[0], ("entropy".to_string(), 2));
+    let tagged: Vec<i64> = db.get_notes_by_tag("entropy").unwrap().into_iter().map(|(id, _, _, _)| id).collect();
+    assert!(tagged.contains(&note) && tagged.contains(&other) && tagged.len() == 2);
+    assert!(db.get_notes_by_tag("heat engines").unwrap().is_empty());
+}
@@ -0,0 +1,101 @@
+use tactanotes_core::ai::mmap::{self, MappedModel, MemoryUsage};
+
+const WEIGHTS_MB: usize = 64;
+
+// Touches every page so lazily mapped memory is actually resident
+fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().step_by(4096).map(|&b| b as u64).sum()
+}
+
+fn delta_mb(before: MemoryUsage, after: MemoryUsage) -> (i64, i64) {
+    (
+        (after.anon_kb as i64 - before.anon_kb as i64) / 1024,
+        (after.file_kb as i64 - before.file_kb as i64) / 1024,
+    )
+}
+
+#[test]
+fn test_mapped_weights_stay_off_the_heap() {
+    println!(">>> MEASURING MODEL LOAD RSS (heap read vs mmap) <<<");
+    let Some(baseline) = mmap::memory_usage() else {
+        println!("No /proc/self/status on this platform, skipping.");
+        return;
+    };
+
+    let path = std::env::temp_dir().join(format!("tactanotes_rss_{}.bin", std::process::id()));
+    // Written in 1MB blocks: mimalloc keeps freed pages resident, so a 64MB Vec here would
+    // be silently reused by the heap read below and hide its cost
+    {
+        use std::io::Write;
+        let block: Vec<u8> = (0..1024 * 1024).map(|i| (i % 251) as u8).collect();
+        let mut file = std::fs::File::create(&path).unwrap();
+        for _ in 0..WEIGHTS_MB {
+            file.write_all(&block).unwrap();
+        }
+    }
+    let before = mmap::memory_usage().unwrap();
+
+    // 1. Heap read, as model_for_path / fs::read loaders do
+    let heap = std::fs::read(&path).unwrap();
+    let heap_sum = checksum(&heap);
+    let (heap_anon, heap_file) = delta_mb(before, mmap::memory_usage().unwrap());
+    drop(heap);
+    let after_heap = mmap::memory_usage().unwrap();
+
+    // 2. Read-only mapping
+    let mapped = MappedModel::open(&path).unwrap();
+    assert!(mapped.is_mapped());
+    assert_eq!(checksum(&mapped), heap_sum);
+    let (map_anon, map_file) = delta_mb(after_heap, mmap::memory_usage().unwrap());
+    drop(mapped);
+    let (unmap_anon, unmap_file) = delta_mb(after_heap, mmap::memory_usage().unwrap());
+    let _ = std::fs::remove_file(&path);
+
+    println!("baseline           : heap {}MB, files {}MB", baseline.anon_kb / 1024, baseline.file_kb / 1024);
+    println!("fs::read  {}MB     : heap {:+}MB, files {:+}MB", WEIGHTS_MB, heap_anon, heap_file);
+    println!("mmap      {}MB     : heap {:+}MB, files {:+}MB", WEIGHTS_MB, map_anon, map_file);
+    println!("after unmap        : heap {:+}MB, files {:+}MB", unmap_anon, unmap_file);
+
+    let expected = WEIGHTS_MB as i64 * 3 / 4;
+    assert!(heap_anon >= expected, "heap read should be anonymous memory ({}MB)", heap_anon);
+    assert!(map_anon < 8, "mapped weights leaked onto the heap ({}MB)", map_anon);
+    assert!(map_file >= expected, "mapped weights should be file-backed ({}MB)", map_file);
+    assert!(unmap_file < expected, "unmapping should release the file pages ({}MB)", unmap_file);
+}
+
+// Real loaders, when the models are installed in ./models (skipped otherwise). Each runtime
+// builds its own tensors, so one copy on the heap is expected; a second one means the file
+// itself was read into a Vec somewhere on the way.
+fn measure_load<T>(name: &str, file: &std::path::Path, load: impl FnOnce() -> anyhow::Result<T>) {
+    let Ok(meta) = std::fs::metadata(file) else {
+        println!("{} not installed at {:?}, skipping.", name, file);
+        return;
+    };
+    let Some(before) = mmap::memory_usage() else {
+        println!("No /proc/self/status on this platform, skipping.");
+        return;
+    };
+    let size_mb = (meta.len() / (1024 * 1024)) as i64;
+    let model = load().unwrap();
+    let (anon, file_backed) = delta_mb(before, mmap::memory_usage().unwrap());
+    println!("{:<18} {}MB : heap {:+}MB, files {:+}MB", name, size_mb, anon, file_backed);
+    drop(model);
+
+    assert!(anon <= size_mb * 3 / 2 + 8, "{} loaded a heap copy of its weights ({}MB for {}MB)", name, anon, size_mb);
+}
+
+#[test]
+fn test_real_model_loads_skip_the_heap_copy() {
+    use tactanotes_core::ai::asr::{AsrBackend, WhisperModel};
+    use tactanotes_core::ai::rag::VectorStore;
+
+    println!(">>> MEASURING REAL MODEL LOAD RSS <<<");
+    let models = std::path::Path::new("./models");
+    measure_load("Whisper tiny.en", &models.join("ggml-tiny.en.bin"), || {
+        let mut whisper = WhisperModel::new();
+        whisper.load("./models").map(|_| whisper)
+    });
+    measure_load("MiniLM export", &models.join("minilm").join("model.onnx"), || {
+        VectorStore::from_dir(&models.join("minilm"))
+    });
+}
@@ -0,0 +1,109 @@
+use tactanotes_core::engine::{jobs, Engine};
+use std::time::Duration;
+
+// Feature F18: Stopping a recording ends it; the jobs that need the model slot run afterwards
+// (no model files installed)
+
+const LECTURE: &str = "Entropy is defined as a measure of disorder. The Carnot efficiency depends on the reservoir \
+    temperatures, and entropy never decreases in an isolated system.";
+
+fn open_engine(name: &str) -> (Engine, std::path::PathBuf) {
+    let db_path = std::env::temp_dir().join(format!("tactanotes_session_{}_{}.db", name, std::process::id()));
+    let _ = std::fs::remove_file(&db_path);
+    (Engine::new(db_path.to_str().unwrap(), "./models-missing"), db_path)
+}
+
+fn wait_finished(engine: &mut Engine, job_id: i64) -> jobs::JobInfo {
+    loop {
+        let job = jobs::wait(job_id, Duration::from_secs(30)).expect("job exists");
+        if job.status.is_finished() {
+            engine.collect_model_job();
+            return job;
+        }
+    }
+}
+
+#[test]
+fn test_questions_are_answered_after_a_recording() {
+    let (mut engine, db_path) = open_engine("ask");
+    engine.add_note("Thermodynamics", LECTURE, None).unwrap();
+
+    engine.stop_recording_and_summarize(None);
+
+    let (job_id, answer) = engine.start_ask("What is entropy?", None, Box::new(|_| true))
+        .expect("the model slot is free once the recording has stopped");
+    let (text, _) = answer.recv_timeout(Duration::from_secs(30)).unwrap();
+    assert!(!text.is_empty());
+    assert_eq!(wait_finished(&mut engine, job_id).status, jobs::JobStatus::Done);
+    // ...and again once that answer is done
+    assert!(engine.start_ask("What is the Carnot efficiency?", None, Box::new(|_| true)).is_ok());
+
+    drop(engine);
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[test]
+fn test_queued_retranscription_runs_after_a_recording() {
+    let models_dir = std::env::temp_dir().join(format!("tactanotes_session_models_{}", std::process::id()));
+    std::fs::create_dir_all(&models_dir).unwrap();
+    // Present but unreadable: the job claims it, fails to load it and counts the attempt
+    std::fs::write(models_dir.join("ggml-base.bin"), b"not a model").unwrap();
+    let db_path = std::env::temp_dir().join(format!("tactanotes_session_retranscribe_{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&db_path);
+    let mut engine = Engine::new(db_path.to_str().unwrap(), models_dir.to_str().unwrap());
+
+    let audio_path = models_dir.join("session.wav");
+    let spec = hound::WavSpec { channels: 1, sample_rate: 16000, bits_per_sample: 16, sample_format: hound::SampleFormat::Int };
+    let mut writer = hound::WavWriter::create(&audio_path, spec).unwrap();
+    for _ in 0..16000 {
+        writer.write_sample(0i16).unwrap();
+    }
+    writer.finalize().unwrap();
+    let note_id = engine.add_note("Thermodynamics", LECTURE, None).unwrap();
+    engine.add_attachment(note_id, "audio", audio_path.to_str().unwrap()).unwrap();
+
+    engine.stop_recording_and_summarize(None);
+    let queued = engine.queue_retranscription(note_id).unwrap();
+    engine.update_power_state(true, true);
+
+    // The scheduler looks at the queue every 300 ticks
+    let deadline = std::time::Instant::now() + Duration::from_secs(30);
+    let attempts = loop {
+        engine.tick();
+        let (_, _, attempts, _) = engine.get_retranscribe_jobs(note_id).unwrap().into_iter()
+            .find(|(id, _, _, _)| *id == queued)
+            .unwrap();
+        if attempts > 0 { break attempts; }
+        assert!(std::time::Instant::now() < deadline, "the queued job never ran");
+        std::thread::sleep(Duration::from_millis(1));
+    };
+    assert_eq!(attempts, 1);
+
+    drop(engine);
+    let _ = std::fs::remove_file(&db_path);
+    let _ = std::fs::remove_dir_all(&models_dir);
+}
+
+#[test]
+fn test_flashcards_are_generated_after_a_recording() {
+    let (mut engine, db_path) = open_engine("flashcards");
+    let note_id = engine.add_note("Thermodynamics", LECTURE, None).unwrap();
+
+    engine.stop_recording_and_summarize(None);
+
+    let job_id = engine.start_flashcards(note_id).expect("the model slot is free once the recording has stopped");
+    let job = wait_finished(&mut engine, job_id);
+    assert_eq!(job.status, jobs::JobStatus::Done);
+    let added: usize = job.result.unwrap().parse().unwrap();
+    assert!(added > 0);
+    assert_eq!(engine.get_cards(note_id).unwrap().len(), added);
+
+    // ASR is not left loaded, so jobs may borrow the embedding model
+    let search = engine.start_search("entropy", tactanotes_core::ai::transcript::TranscriptTrack::Original);
+    if let jobs::JobStatus::Failed(error) = wait_finished(&mut engine, search).status {
+        assert!(!error.contains("while recording"), "{}", error);
+    }
+
+    drop(engine);
+    let _ = std::fs::remove_file(&db_path);
+}
@@ -0,0 +1,46 @@
+use std::path::Path;
+use tactanotes_core::ai::llm::{ExtractiveSummarizer, GenerationOptions, Summarizer};
+
+// tests/golden/<name>.txt          lecture transcript
+// tests/golden/<name>.unsure.txt   optional low-confidence segments, one per line
+// tests/golden/<name>.summary.txt  expected extractive summary
+// Run with UPDATE_GOLDEN=1 to rewrite the expected files after an intended ranking change.
+#[test]
+fn test_extractive_summaries_match_golden_files() {
+    println!(">>> TESTING EXTRACTIVE SUMMARIES AGAINST GOLDEN FILES <<<");
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let update = std::env::var("UPDATE_GOLDEN").is_ok();
+
+    let mut names: Vec<String> = std::fs::read_dir(&dir).unwrap()
+        .filter_map(|e| e.ok())
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .filter(|f| f.ends_with(".txt") && f.matches('.').count() == 1)
+        .map(|f| f.trim_end_matches(".txt").to_string())
+        .collect();
+    names.sort();
+    assert!(names.len() >= 3, "golden transcripts missing in {:?}", dir);
+
+    let mut mismatches = Vec::new();
+    for name in &names {
+        let transcript = std::fs::read_to_string(dir.join(format!("{}.txt", name))).unwrap();
+        let unsure: Vec<String> = std::fs::read_to_string(dir.join(format!("{}.unsure.txt", name)))
+            .map(|s| s.lines().map(|l| l.to_string()).collect())
+            .unwrap_or_default();
+
+        let summary = ExtractiveSummarizer
+            .summarize(&transcript, &unsure, &GenerationOptions::default(), &mut |_| true)
+            .unwrap();
+        println!("--- {} ---\n{}", name, summary);
+
+        let expected_path = dir.join(format!("{}.summary.txt", name));
+        if update {
+            std::fs::write(&expected_path, format!("{}\n", summary)).unwrap();
+            continue;
+        }
+        let expected = std::fs::read_to_string(&expected_path).unwrap_or_default();
+        if expected.trim_end() != summary {
+            mismatches.push(name.clone());
+        }
+    }
+    assert!(mismatches.is_empty(), "summaries differ from golden files: {:?}", mismatches);
+}
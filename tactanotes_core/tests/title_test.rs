@@ -0,0 +1,47 @@
+use tactanotes_core::engine::Engine;
+
+// Feature F2.6: Retitling stored notes (keyphrase titles, no GGUF model installed)
+
+const LECTURE: &str = "Today we look at entropy. Entropy is a measure of disorder. The Carnot efficiency \
+    depends on the reservoir temperatures, and entropy never decreases in an isolated system. The Carnot \
+    efficiency is the upper limit for heat engines.";
+
+#[test]
+fn test_regenerate_titles_keeps_user_titles_when_only_untitled() {
+    let db_path = std::env::temp_dir().join(format!("tactanotes_titles_{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&db_path);
+    let mut engine = Engine::new(db_path.to_str().unwrap(), "./models-missing");
+
+    let thermo = engine.create_folder("Thermodynamics").unwrap();
+    // 12 Mar 2026, 12:00 UTC: the same day in every time zone within 11 hours of it
+    let untitled = engine.add_note("Note 1773316800", LECTURE, Some(thermo)).unwrap();
+    let named = engine.add_note("My entropy notes", LECTURE, Some(thermo)).unwrap();
+    let elsewhere = engine.add_note("Note 1773316800", LECTURE, None).unwrap();
+
+    // only_untitled, one folder
+    let retitled = engine.regenerate_titles(Some(thermo), true).unwrap();
+    assert_eq!(retitled.len(), 1);
+    assert_eq!(retitled[0].0, untitled);
+    assert_eq!(retitled[0].1, "Thermodynamics: Carnot efficiency and entropy (12 Mar 2026)");
+    assert_eq!(engine.get_note(untitled).unwrap().1, retitled[0].1);
+    assert_eq!(engine.get_note(named).unwrap().1, "My entropy notes");
+    assert_eq!(engine.get_note(elsewhere).unwrap().1, "Note 1773316800");
+
+    // Every note, user titles included; a note outside any folder has no subject
+    let retitled = engine.regenerate_titles(None, false).unwrap();
+    assert_eq!(retitled.len(), 3);
+    assert!(engine.get_note(named).unwrap().1.starts_with("Thermodynamics: Carnot efficiency and entropy ("));
+    assert_eq!(engine.get_note(elsewhere).unwrap().1, "Carnot efficiency and entropy (12 Mar 2026)");
+
+    // Nothing left to title
+    assert!(engine.regenerate_titles(None, true).unwrap().is_empty());
+
+    // The recording time is gone from the title now, so the date is the note's last change
+    let title = engine.regenerate_note_title(elsewhere).unwrap();
+    assert!(title.starts_with("Carnot efficiency and entropy ("), "{}", title);
+    assert_eq!(engine.get_note(elsewhere).unwrap().1, title);
+    assert!(engine.regenerate_note_title(-1).is_err());
+
+    drop(engine);
+    let _ = std::fs::remove_file(&db_path);
+}
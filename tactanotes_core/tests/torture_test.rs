@@ -34,6 +34,8 @@ fn torture_test_10_hours_simulation() {
              
              // Trigger a summary event every hour to stress memory swap
              engine.stop_recording_and_summarize(None);
+             // Stopping ends the recording; the next hour is a new one
+             engine.start_recording().expect("Failed to restart recording");
         }
         
         // Fast forward simulation: We don't verify sleep here, just logic cycles.
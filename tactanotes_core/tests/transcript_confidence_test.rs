@@ -0,0 +1,63 @@
+use tactanotes_core::ai::filter::FilterConfig;
+use tactanotes_core::ai::transcript::{TranscriptSegment, TranscriptTrack};
+use tactanotes_core::{api, Database};
+
+// Feature F1.4: Decoder confidence is stored per segment and handed to the UI, which dims the
+// segments the hallucination filter would flag
+
+fn segment(track: TranscriptTrack, start_ms: i64, text: &str, avg: f32, min: f32) -> TranscriptSegment {
+    TranscriptSegment {
+        track,
+        start_ms,
+        end_ms: start_ms + 2000,
+        text: text.to_string(),
+        avg_token_prob: avg,
+        min_token_prob: min,
+        language: Some("en".to_string()),
+    }
+}
+
+#[test]
+fn test_low_confidence_segments_read_back_through_the_api() {
+    let path = std::env::temp_dir().join(format!("tactanotes_confidence_{}.db", std::process::id()));
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+    }
+    let note_id = {
+        // Same password as the engine's
+        let db = Database::open(path.to_str().unwrap(), "default_password").unwrap();
+        let note_id = db.add_note("Thermodynamics", "", None).unwrap();
+        db.add_transcript_segments(note_id, &[
+            segment(TranscriptTrack::Original, 0, "Entropy is a measure of disorder.", 0.92, 0.71),
+            segment(TranscriptTrack::Original, 2000, "The car no cycle.", 0.41, 0.05),
+            segment(TranscriptTrack::Original, 4000, "Heat flows from hot to cold.", 0.88, 0.60),
+            segment(TranscriptTrack::Translation, 0, "Entropy is a measure of disorder.", 0.35, 0.12),
+        ]).unwrap();
+        note_id
+    };
+
+    api::init_app(path.to_str().unwrap().to_string(), "./models-missing".to_string()).unwrap();
+    let original = api::get_transcript(note_id, "original".to_string()).unwrap();
+    assert_eq!(original.len(), 3);
+    let (avg, min): (Vec<f32>, Vec<f32>) = original.iter().map(|s| (s.avg_token_prob, s.min_token_prob)).unzip();
+    assert_eq!(avg, vec![0.92, 0.41, 0.88]);
+    assert_eq!(min, vec![0.71, 0.05, 0.60]);
+
+    // Only the mumbled segment is below the flag threshold, with its span intact
+    let threshold = FilterConfig::default().flag_avg_token_prob;
+    let flagged: Vec<_> = original.iter().filter(|s| s.avg_token_prob < threshold).collect();
+    assert_eq!(flagged.len(), 1);
+    assert_eq!((flagged[0].start_ms, flagged[0].end_ms), (2000, 4000));
+    assert_eq!(flagged[0].text, "The car no cycle.");
+
+    // Each track keeps its own probabilities, in every transcript version
+    let translation = api::get_transcript(note_id, "translation".to_string()).unwrap();
+    assert_eq!(translation.len(), 1);
+    assert!(translation[0].avg_token_prob < threshold);
+    let versioned = api::get_transcript_version(note_id, "original".to_string(), 1).unwrap();
+    assert_eq!(versioned.iter().map(|s| s.avg_token_prob).collect::<Vec<_>>(), avg);
+
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+    }
+}